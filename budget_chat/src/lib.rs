@@ -1,8 +1,130 @@
 pub mod models;
 
-use models::Member;
+use models::{Member, Message};
+use shared::{
+    async_server::{AsyncServer, Context, Stream},
+    config::Config,
+    line::{self, AsyncLineReader},
+    warn,
+};
+use std::{
+    collections::HashSet,
+    io::Result,
+    sync::{Arc, Mutex},
+};
+use tokio::{
+    io::{self, AsyncWriteExt, BufReader},
+    sync::broadcast::{self, error::RecvError},
+};
 
-use std::collections::HashSet;
+/// Defaults for `run`, before flags and environment variables are applied.
+pub fn default_config() -> Config {
+    Config::new(2)
+}
+
+/// Serves the protocol as configured by `config` until the process is asked to shut down.
+pub fn run(config: &Config) -> Result<()> {
+    let server = AsyncServer::builder().config(config).build()?;
+
+    let members = Arc::new(Mutex::new(HashSet::<Member>::new()));
+    let (sender, _) = broadcast::channel(2048);
+
+    let max_line_length = config.max_line_length;
+
+    server.run(move |stream, context: Context| {
+        handle_connection(
+            context.id,
+            stream,
+            max_line_length,
+            Arc::clone(&members),
+            sender.clone(),
+        )
+    })
+}
+
+async fn handle_connection(
+    id: usize,
+    stream: Stream,
+    max_line_length: usize,
+    members: Arc<Mutex<HashSet<Member>>>,
+    sender: broadcast::Sender<Message>,
+) -> Result<()> {
+    let (reader, mut writer) = io::split(stream);
+    let mut lines = AsyncLineReader::new(BufReader::new(reader), max_line_length);
+
+    writer.write_all(b"Enter name:\n").await?;
+
+    let member = match lines.next_line().await {
+        Ok(Some(name)) if is_valid_name(&name) => Member {
+            id,
+            name: name.trim().to_string(),
+        },
+        Ok(_) => return Ok(()),
+        Err(e) if line::is_too_long(&e) => {
+            warn!("Oversized name: {}", e);
+            return Ok(());
+        }
+        Err(e) => return Err(e),
+    };
+
+    let formatted_names = format_names(members.lock().unwrap().clone());
+    writer
+        .write_all(format!("{}\n", formatted_names).as_bytes())
+        .await?;
+    members.lock().unwrap().insert(member.clone());
+
+    let mut receiver = sender.subscribe();
+
+    sender
+        .send(Message {
+            sender_id: member.id,
+            contents: format!("* {} has entered the room", member.name),
+        })
+        .unwrap();
+
+    let result = loop {
+        tokio::select! {
+            line = lines.next_line() => match line {
+                Ok(Some(message)) => {
+                    sender
+                        .send(Message {
+                            sender_id: member.id,
+                            contents: format!("[{}] {}", member.name, message.trim()),
+                        })
+                        .unwrap();
+                }
+                Ok(None) => break Ok(()),
+                Err(e) if line::is_too_long(&e) => {
+                    warn!("Oversized message from {}: {}", member.name, e);
+                    break Ok(());
+                }
+                Err(e) => break Err(e),
+            },
+            value = receiver.recv() => match value {
+                Ok(value) if value.sender_id != member.id => {
+                    if let Err(e) = writer
+                        .write_all(format!("{}\n", value.contents).as_bytes())
+                        .await
+                    {
+                        break Err(e);
+                    }
+                }
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break Ok(()),
+            },
+        }
+    };
+
+    members.lock().unwrap().remove(&member);
+    sender
+        .send(Message {
+            sender_id: member.id,
+            contents: format!("* {} has left the room", member.name),
+        })
+        .unwrap();
+
+    result
+}
 
 pub fn format_names(members: HashSet<Member>) -> String {
     let names = members
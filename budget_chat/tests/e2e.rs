@@ -0,0 +1,92 @@
+use shared::{
+    config::Config,
+    testing::{self, TcpClient, Transport},
+};
+use std::{net::SocketAddr, time::Duration};
+
+fn server() -> SocketAddr {
+    testing::spawn(
+        Transport::Tcp,
+        budget_chat::default_config(),
+        budget_chat::run,
+    )
+}
+
+fn join(address: SocketAddr, name: &str) -> TcpClient {
+    let mut client = TcpClient::connect(address);
+    client.expect_line("Enter name:");
+    client.send_line(name);
+    client
+}
+
+#[test]
+fn runs_the_example_session() {
+    let address = server();
+
+    let mut bob = join(address, "bob");
+    bob.expect_line("* The room contains: ");
+
+    let mut charlie = join(address, "charlie");
+    charlie.expect_line("* The room contains: bob");
+    bob.expect_line("* charlie has entered the room");
+
+    let mut dave = join(address, "dave");
+    let room = dave.read_line();
+    assert!(
+        room == "* The room contains: bob, charlie" || room == "* The room contains: charlie, bob"
+    );
+    bob.expect_line("* dave has entered the room");
+    charlie.expect_line("* dave has entered the room");
+
+    bob.send_line("hi");
+    charlie.expect_line("[bob] hi");
+    dave.expect_line("[bob] hi");
+    bob.expect_silence(Duration::from_millis(200));
+
+    drop(dave);
+    bob.expect_line("* dave has left the room");
+    charlie.expect_line("* dave has left the room");
+}
+
+#[test]
+fn rejects_invalid_names() {
+    let address = server();
+
+    for name in ["", "bad name", "émoji🙂"] {
+        let mut client = join(address, name);
+        client.expect_closed();
+    }
+}
+
+#[test]
+fn does_not_announce_clients_that_never_joined() {
+    let address = server();
+
+    let mut alice = join(address, "alice");
+    alice.expect_line("* The room contains: ");
+
+    let mut lurker = TcpClient::connect(address);
+    lurker.expect_line("Enter name:");
+    drop(lurker);
+
+    alice.expect_silence(Duration::from_millis(200));
+}
+
+#[test]
+fn disconnects_members_sending_oversized_messages() {
+    let config = Config {
+        max_line_length: 32,
+        ..budget_chat::default_config()
+    };
+    let address = testing::spawn(Transport::Tcp, config, budget_chat::run);
+
+    let mut alice = join(address, "alice");
+    alice.expect_line("* The room contains: ");
+    let mut bob = join(address, "bob");
+    bob.expect_line("* The room contains: alice");
+    alice.expect_line("* bob has entered the room");
+
+    bob.send_line(&"spam".repeat(10));
+    bob.expect_closed();
+    alice.expect_line("* bob has left the room");
+}
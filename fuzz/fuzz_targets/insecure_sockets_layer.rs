@@ -0,0 +1,22 @@
+#![no_main]
+
+use insecure_sockets_layer::{cipher::Cipher, most_toys};
+use libfuzzer_sys::fuzz_target;
+use std::io::BufReader;
+
+// A client's byte stream: a cipher spec, then encoded request lines.
+fuzz_target!(|stream: &[u8]| {
+    let mut reader = BufReader::new(stream);
+
+    let Ok(mut cipher) = Cipher::new(&mut reader) else {
+        return;
+    };
+
+    if cipher.is_redundant() {
+        return;
+    }
+
+    while let Ok(line) = cipher.decode_line(&mut reader) {
+        cipher.encode_string(format!("{}\n", most_toys(&line)));
+    }
+});
@@ -0,0 +1,35 @@
+#![no_main]
+
+use job_centre::{models::Request, queue::QueueManager};
+use libfuzzer_sys::fuzz_target;
+use std::collections::HashMap;
+
+// A client's request lines, applied to the queues as the server would, except that a waiting
+// get doesn't wait.
+fuzz_target!(|stream: &str| {
+    let mut manager = QueueManager::new();
+    let mut active = HashMap::new();
+
+    for line in stream.lines() {
+        match serde_json::from_str(line) {
+            Ok(Request::Put { queue, pri, job }) => {
+                manager.add_job(queue, job, pri);
+            }
+            Ok(Request::Get { queues, .. }) => {
+                if let Some(job) = manager.get_highest_priority_job(queues) {
+                    active.insert(job.id, job);
+                }
+            }
+            Ok(Request::Delete { id }) => {
+                active.remove(&id);
+                manager.delete_job(id);
+            }
+            Ok(Request::Abort { id }) => {
+                if let Some(job) = active.remove(&id) {
+                    manager.abort_job(job);
+                }
+            }
+            Err(_) => {}
+        }
+    }
+});
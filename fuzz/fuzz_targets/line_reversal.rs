@@ -0,0 +1,9 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use line_reversal::lcrp::message::parse_message;
+
+// One UDP packet, as the listener receives it.
+fuzz_target!(|packet: &[u8]| {
+    let _ = parse_message(packet);
+});
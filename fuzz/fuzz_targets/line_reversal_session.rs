@@ -0,0 +1,41 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use line_reversal::lcrp::listener::LrcpListener;
+use std::{
+    io::{ErrorKind, Read, Write},
+    net::UdpSocket,
+};
+
+// A peer's packets to an open session, each followed by one poll of the session as its
+// handler would make, echoing back whatever lines it completes.
+fuzz_target!(|packets: Vec<&[u8]>| {
+    let mut listener = LrcpListener::bind("127.0.0.1:0").unwrap();
+    let peer = UdpSocket::bind("127.0.0.1:0").unwrap();
+    peer.connect(listener.socket.local_addr().unwrap()).unwrap();
+    peer.set_nonblocking(true).unwrap();
+
+    peer.send(b"/connect/1/").unwrap();
+    let stream = listener.accept().unwrap();
+    let mut buf = [0; 64];
+
+    for packet in packets {
+        if peer.send(packet).is_err() {
+            continue;
+        }
+
+        match (&stream).read(&mut buf) {
+            Ok(0) => break,
+            Ok(len) => {
+                let _ = (&stream).write(&buf[..len]);
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+            Err(_) => break,
+        }
+
+        // The listener takes packets the session leaves, which would otherwise block it.
+        let mut leftover = [0; 1000];
+        while listener.socket.recv_from(&mut leftover).is_ok() {}
+        while peer.recv(&mut leftover).is_ok() {}
+    }
+});
@@ -0,0 +1,33 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use means_to_an_end::{consume_messages, index::PriceIndex};
+use std::{collections::HashMap, io::BufReader};
+
+// A client's byte stream of inserts and queries, with each mean checked against a scan of
+// every price.
+fuzz_target!(|stream: &[u8]| {
+    let mut index = PriceIndex::new();
+    let mut prices = HashMap::new();
+
+    for message in consume_messages(BufReader::new(stream)).flatten() {
+        match message.message_type {
+            b'I' => {
+                index.insert(message.a, message.b);
+                prices.insert(message.a, message.b);
+            }
+            b'Q' => {
+                let (sum, count) = prices
+                    .iter()
+                    .filter(|(&timestamp, _)| (message.a..=message.b).contains(&timestamp))
+                    .fold((0i64, 0i64), |(sum, count), (_, &price)| {
+                        (sum + i64::from(price), count + 1)
+                    });
+                let expected = if count == 0 { 0 } else { (sum / count) as i32 };
+
+                assert_eq!(index.mean(message.a, message.b), expected);
+            }
+            _ => break,
+        }
+    }
+});
@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pest_control::read::consume_messages;
+
+// A client's or authority's byte stream.
+fuzz_target!(|stream: &[u8]| {
+    for message in consume_messages(stream) {
+        if message.is_err() {
+            break;
+        }
+    }
+});
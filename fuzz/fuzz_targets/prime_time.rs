@@ -0,0 +1,14 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use prime_time::{respond, rpc::Registry};
+use std::sync::OnceLock;
+
+static REGISTRY: OnceLock<Registry> = OnceLock::new();
+
+// One request line.
+fuzz_target!(|line: &str| {
+    let registry = REGISTRY.get_or_init(Registry::standard);
+
+    let _ = respond(registry, line);
+});
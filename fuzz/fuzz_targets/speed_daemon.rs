@@ -0,0 +1,16 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use shared::codec::FrameDecoder;
+use speed_daemon::read::decode_message;
+
+// A client's byte stream, arriving in two pieces.
+fuzz_target!(|pieces: (&[u8], &[u8])| {
+    let mut decoder = FrameDecoder::new(decode_message);
+
+    for piece in [pieces.0, pieces.1] {
+        decoder.extend(piece);
+
+        while let Ok(Some(_)) = decoder.decode() {}
+    }
+});
@@ -0,0 +1,22 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use std::collections::HashMap;
+use unusual_database_program::{parse_request, Request};
+
+// A sequence of datagrams, applied to a store as the server would.
+fuzz_target!(|datagrams: Vec<&[u8]>| {
+    let mut store = HashMap::new();
+
+    for datagram in datagrams {
+        match parse_request(datagram) {
+            Some(Request::Retrieve(key)) => {
+                let _ = store.get(key);
+            }
+            Some(Request::Insert(key, value)) => {
+                store.insert(key, value);
+            }
+            None => {}
+        }
+    }
+});
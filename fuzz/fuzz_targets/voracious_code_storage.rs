@@ -0,0 +1,15 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use std::io;
+use voracious_code_storage::{handle_session, Options, Storage};
+
+// A client's byte stream, with small limits so that both are reached.
+fuzz_target!(|stream: &[u8]| {
+    let options = Options {
+        max_line_length: 256,
+        max_file_size: 1024,
+    };
+
+    let _ = handle_session(stream, io::sink(), options, &Storage::default());
+});
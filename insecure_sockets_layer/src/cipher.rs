@@ -1,5 +1,11 @@
 use core::str;
-use std::io::{prelude::Read, BufReader, Result};
+use std::io::{prelude::Read, BufReader, Error, ErrorKind, Result};
+
+/// Longest cipher spec a client may send, in bytes, including the terminating zero.
+const MAX_SPEC_LENGTH: usize = 80;
+
+/// Longest line a client may send, in bytes, including the newline.
+const MAX_LINE_LENGTH: usize = 5000;
 
 #[derive(Debug)]
 pub enum Operation {
@@ -12,17 +18,23 @@ pub enum Operation {
 
 pub struct Cipher {
     pub spec: Vec<Operation>,
-    server_pos: i32,
-    client_pos: i32,
+    server_pos: u8,
+    client_pos: u8,
 }
 
 impl Cipher {
     pub fn new<R: Read>(reader: &mut BufReader<R>) -> Result<Cipher> {
         let mut spec: Vec<Operation> = Vec::new();
         let mut buffer = [0; 1];
+        let mut reader = reader.take(MAX_SPEC_LENGTH as u64);
 
         loop {
-            reader.read_exact(&mut buffer)?;
+            reader.read_exact(&mut buffer).map_err(|e| match e.kind() {
+                ErrorKind::UnexpectedEof if reader.limit() == 0 => {
+                    Error::new(ErrorKind::InvalidData, "Cipher spec too long")
+                }
+                _ => e,
+            })?;
 
             match buffer[0] {
                 0x00 => break,
@@ -37,33 +49,40 @@ impl Cipher {
                     spec.push(Operation::AddN(buffer[0]));
                 }
                 0x05 => spec.push(Operation::AddPos),
-                _ => {}
+                _ => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        "Invalid cipher operation",
+                    ))
+                }
             }
         }
 
         Ok(Cipher {
             spec,
-            server_pos: -1,
-            client_pos: -1,
+            server_pos: 0,
+            client_pos: 0,
         })
     }
 
     fn encode_byte(&mut self, byte: u8) -> u8 {
-        self.client_pos += 1;
+        let pos = self.client_pos;
+        self.client_pos = pos.wrapping_add(1);
 
         self.spec
             .iter()
             .fold(byte, |encoded, operation| match operation {
                 Operation::ReverseBits => encoded.reverse_bits(),
                 Operation::XorN(n) => encoded ^ n,
-                Operation::XorPos => encoded ^ (self.client_pos & 0xFF) as u8,
+                Operation::XorPos => encoded ^ pos,
                 Operation::AddN(n) => encoded.wrapping_add(*n),
-                Operation::AddPos => encoded.wrapping_add((self.client_pos & 0xFF) as u8),
+                Operation::AddPos => encoded.wrapping_add(pos),
             })
     }
 
     fn decode_byte(&mut self, byte: u8) -> u8 {
-        self.server_pos += 1;
+        let pos = self.server_pos;
+        self.server_pos = pos.wrapping_add(1);
 
         self.spec
             .iter()
@@ -71,9 +90,9 @@ impl Cipher {
             .fold(byte, |decoded, operation| match operation {
                 Operation::ReverseBits => decoded.reverse_bits(),
                 Operation::XorN(n) => decoded ^ n,
-                Operation::XorPos => decoded ^ (self.server_pos & 0xFF) as u8,
+                Operation::XorPos => decoded ^ pos,
                 Operation::AddN(n) => decoded.wrapping_sub(*n),
-                Operation::AddPos => decoded.wrapping_sub((self.server_pos & 0xFF) as u8),
+                Operation::AddPos => decoded.wrapping_sub(pos),
             })
     }
 
@@ -89,6 +108,10 @@ impl Cipher {
         let mut buffer = [0; 1];
 
         while decoded_bytes.last() != Some(&b'\n') {
+            if decoded_bytes.len() == MAX_LINE_LENGTH {
+                return Err(Error::new(ErrorKind::InvalidData, "Line too long"));
+            }
+
             reader.read_exact(&mut buffer)?;
             decoded_bytes.push(self.decode_byte(buffer[0]));
         }
@@ -103,7 +126,7 @@ impl Cipher {
         let test_word = "abcdefg".to_string();
         let encoded = self.encode_string(test_word.clone());
 
-        self.client_pos = -1;
+        self.client_pos = 0;
 
         match str::from_utf8(&encoded) {
             Ok(s) => s == test_word.clone(),
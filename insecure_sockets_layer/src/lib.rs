@@ -0,0 +1,68 @@
+pub mod cipher;
+
+use cipher::Cipher;
+use shared::{
+    config::{Config, DEFAULT_IDLE_TIMEOUT},
+    debug,
+    server::{Context, Server, Stream},
+};
+use std::io::{prelude::*, BufReader, Error, ErrorKind, Result};
+
+/// Defaults for `run`, before flags and environment variables are applied.
+pub fn default_config() -> Config {
+    Config {
+        idle_timeout: Some(DEFAULT_IDLE_TIMEOUT),
+        ..Config::new(10)
+    }
+}
+
+/// Serves the protocol as configured by `config` until the process is asked to shut down.
+pub fn run(config: &Config) -> Result<()> {
+    let server = Server::builder().config(config).build()?;
+
+    server.run(|stream, _: Context| handle_connection(stream))
+}
+
+fn parse_int(str: &str) -> u128 {
+    let digits = str
+        .chars()
+        .filter(|c| c.is_ascii_digit())
+        .collect::<String>();
+    digits.parse::<u128>().unwrap_or(0)
+}
+
+/// The toy in a comma-separated request line that has the most copies, e.g. `10x toy car`.
+pub fn most_toys(line: &str) -> &str {
+    line.trim()
+        .split(',')
+        .max_by_key(|toy| parse_int(toy))
+        .unwrap_or_default()
+}
+
+fn handle_connection(stream: Stream) -> Result<()> {
+    let mut reader = BufReader::new(&stream);
+    let mut writer = &stream;
+
+    let mut cipher = match Cipher::new(&mut reader) {
+        Ok(cipher) => cipher,
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => {
+            debug!("Client closed before sending its whole cipher spec");
+            return Ok(());
+        }
+        Err(e) => return Err(e),
+    };
+
+    if cipher.is_redundant() {
+        return Err(Error::from(ErrorKind::InvalidData));
+    }
+
+    while let Ok(line) = cipher.decode_line(&mut reader) {
+        let mut max_toy = most_toys(&line).to_string();
+        max_toy.push('\n');
+
+        let encoded = cipher.encode_string(max_toy);
+        writer.write_all(&encoded)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,101 @@
+use shared::testing::{self, TcpClient, Transport};
+use std::net::SocketAddr;
+
+fn server() -> SocketAddr {
+    testing::spawn(
+        Transport::Tcp,
+        insecure_sockets_layer::default_config(),
+        insecure_sockets_layer::run,
+    )
+}
+
+#[test]
+fn runs_the_example_session() {
+    let mut client = TcpClient::connect(server());
+
+    // xor(123), addpos, reversebits
+    client.send([0x02, 0x7b, 0x05, 0x01, 0x00]);
+
+    // "4x dog,5x car\n" -> "5x car\n"
+    client.send([
+        0xf2, 0x20, 0xba, 0x44, 0x18, 0x84, 0xba, 0xaa, 0xd0, 0x26, 0x44, 0xa4, 0xa8, 0x7e,
+    ]);
+    client.expect([0x72, 0x20, 0xba, 0xd8, 0x78, 0x70, 0xee]);
+
+    // "3x rat,2x cat\n" -> "3x rat\n"
+    client.send([
+        0x6a, 0x48, 0xd6, 0x58, 0x34, 0x44, 0xd6, 0x7a, 0x98, 0x4e, 0x0c, 0xcc, 0x94, 0x31,
+    ]);
+    client.expect([0xf2, 0xd0, 0x26, 0xc8, 0xa4, 0xd8, 0x7e]);
+}
+
+#[test]
+fn keeps_stream_positions_across_packets() {
+    let mut client = TcpClient::connect(server());
+
+    // xorpos only, so every byte depends on its position in the stream.
+    client.send([0x03, 0x00]);
+
+    let request: Vec<u8> = b"10x a,2x b\n"
+        .iter()
+        .enumerate()
+        .map(|(pos, byte)| byte ^ pos as u8)
+        .collect();
+
+    client.send(&request[..4]);
+    client.send(&request[4..]);
+
+    let response: Vec<u8> = b"10x a\n"
+        .iter()
+        .enumerate()
+        .map(|(pos, byte)| byte ^ pos as u8)
+        .collect();
+    client.expect(response);
+}
+
+#[test]
+fn disconnects_no_op_ciphers() {
+    let address = server();
+
+    for spec in [
+        &[0x00][..],
+        &[0x02, 0x00, 0x00],
+        &[0x02, 0xab, 0x02, 0xab, 0x00],
+        &[0x01, 0x01, 0x00],
+        &[0x02, 0xa0, 0x02, 0x0b, 0x02, 0xab, 0x00],
+    ] {
+        let mut client = TcpClient::connect(address);
+        client.send(spec);
+        client.expect_closed();
+    }
+}
+
+#[test]
+fn disconnects_unknown_cipher_operations() {
+    let mut client = TcpClient::connect(server());
+
+    client.send([0x02, 0x7b, 0x06, 0x00]);
+    client.expect_closed();
+}
+
+#[test]
+fn disconnects_endless_cipher_specs() {
+    let mut client = TcpClient::connect(server());
+
+    client.send([0x01; 100]);
+    client.expect_closed();
+}
+
+#[test]
+fn answers_lines_without_counts() {
+    let mut client = TcpClient::connect(server());
+
+    // xor(1)
+    client.send([0x02, 0x01, 0x00]);
+
+    let encode = |line: &[u8]| line.iter().map(|byte| byte ^ 1).collect::<Vec<_>>();
+    client.send(encode(b"dog\n"));
+    client.expect(encode(b"dog\n"));
+    client.send(encode(b"2x cat,5x dog\n"));
+    client.expect(encode(b"5x dog\n"));
+}
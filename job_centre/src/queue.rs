@@ -1,13 +1,17 @@
+use std::cmp::Reverse;
 use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::io::{prelude::*, BufReader};
+use std::sync::Arc;
 
 use serde_json::Value;
+use shared::metrics::{self, Counter};
 
 pub struct QueueManager {
     pub queues: HashMap<String, Vec<Job>>,
     pub allocated: HashSet<u32>,
     pub deleted: HashSet<u32>,
+    jobs_queued: Arc<Counter>,
 }
 
 pub struct Job {
@@ -23,6 +27,11 @@ impl QueueManager {
             queues: HashMap::new(),
             allocated: HashSet::new(),
             deleted: HashSet::new(),
+            jobs_queued: metrics::counter(
+                "job_centre_jobs_queued_total",
+                "Jobs added with a put request.",
+                &[],
+            ),
         }
     }
 
@@ -47,11 +56,13 @@ impl QueueManager {
 
         if let Some(queue) = self.queues.get_mut(&queue_id) {
             queue.push(job);
-            queue.sort_by(|a, b| b.pri.cmp(&a.pri));
+            queue.sort_by_key(|job| Reverse(job.pri));
         } else {
             self.queues.insert(queue_id, vec![job]);
         }
 
+        self.jobs_queued.inc();
+
         id
     }
 
@@ -62,7 +73,7 @@ impl QueueManager {
 
         if let Some(queue) = self.queues.get_mut(&job.queue_id.clone()) {
             queue.push(job);
-            queue.sort_by(|a, b| b.pri.cmp(&a.pri));
+            queue.sort_by_key(|job| Reverse(job.pri));
         }
 
         "ok".to_string()
@@ -107,3 +118,9 @@ impl QueueManager {
         }
     }
 }
+
+impl Default for QueueManager {
+    fn default() -> QueueManager {
+        QueueManager::new()
+    }
+}
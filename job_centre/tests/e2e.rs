@@ -0,0 +1,160 @@
+use serde_json::{json, Value};
+use shared::{
+    config::Config,
+    testing::{self, TcpClient, Transport},
+};
+use std::{net::SocketAddr, time::Duration};
+
+fn server() -> SocketAddr {
+    testing::spawn(
+        Transport::Tcp,
+        job_centre::default_config(),
+        job_centre::run,
+    )
+}
+
+fn request(client: &mut TcpClient, request: Value) -> Value {
+    client.send_line(&request.to_string());
+    serde_json::from_str(&client.read_line()).unwrap()
+}
+
+fn put(client: &mut TcpClient, queue: &str, pri: u32) -> u64 {
+    let response = request(
+        client,
+        json!({"request": "put", "queue": queue, "job": {"title": queue}, "pri": pri}),
+    );
+    assert_eq!(response["status"], "ok");
+
+    response["id"].as_u64().unwrap()
+}
+
+#[test]
+fn gets_highest_priority_job_across_queues() {
+    let mut client = TcpClient::connect(server());
+
+    put(&mut client, "queue1", 10);
+    let id = put(&mut client, "queue2", 123);
+
+    let response = request(
+        &mut client,
+        json!({"request": "get", "queues": ["queue1", "queue2"]}),
+    );
+    assert_eq!(
+        response,
+        json!({"status": "ok", "id": id, "job": {"title": "queue2"}, "pri": 123, "queue": "queue2"})
+    );
+}
+
+#[test]
+fn reports_no_job_for_empty_queues() {
+    let mut client = TcpClient::connect(server());
+
+    let response = request(&mut client, json!({"request": "get", "queues": ["empty"]}));
+    assert_eq!(response, json!({"status": "no-job"}));
+}
+
+#[test]
+fn deletes_jobs_once() {
+    let mut client = TcpClient::connect(server());
+    let id = put(&mut client, "queue1", 1);
+
+    let response = request(&mut client, json!({"request": "delete", "id": id}));
+    assert_eq!(response, json!({"status": "ok"}));
+
+    let response = request(&mut client, json!({"request": "delete", "id": id}));
+    assert_eq!(response, json!({"status": "no-job"}));
+
+    let response = request(&mut client, json!({"request": "get", "queues": ["queue1"]}));
+    assert_eq!(response, json!({"status": "no-job"}));
+}
+
+#[test]
+fn aborted_jobs_return_to_their_queue() {
+    let mut client = TcpClient::connect(server());
+    let id = put(&mut client, "queue1", 1);
+
+    let response = request(&mut client, json!({"request": "get", "queues": ["queue1"]}));
+    assert_eq!(response["id"], id);
+
+    let response = request(&mut client, json!({"request": "abort", "id": id}));
+    assert_eq!(response, json!({"status": "ok"}));
+
+    let response = request(&mut client, json!({"request": "get", "queues": ["queue1"]}));
+    assert_eq!(response["id"], id);
+}
+
+#[test]
+fn only_the_assigned_client_can_abort() {
+    let address = server();
+    let mut worker = TcpClient::connect(address);
+    let mut other = TcpClient::connect(address);
+    let id = put(&mut worker, "queue1", 1);
+
+    request(&mut worker, json!({"request": "get", "queues": ["queue1"]}));
+
+    let response = request(&mut other, json!({"request": "abort", "id": id}));
+    assert_eq!(response["status"], "error");
+}
+
+#[test]
+fn disconnecting_aborts_assigned_jobs() {
+    let address = server();
+    let mut client = TcpClient::connect(address);
+    let id = put(&mut client, "queue1", 1);
+
+    let mut worker = TcpClient::connect(address);
+    request(&mut worker, json!({"request": "get", "queues": ["queue1"]}));
+    worker.shutdown_write();
+    worker.expect_closed();
+
+    let response = request(
+        &mut client,
+        json!({"request": "get", "queues": ["queue1"], "wait": true}),
+    );
+    assert_eq!(response["id"], id);
+}
+
+#[test]
+fn waiting_get_blocks_until_a_job_is_put() {
+    let address = server();
+    let mut worker = TcpClient::connect(address);
+    let mut producer = TcpClient::connect(address);
+
+    worker.send_line(&json!({"request": "get", "queues": ["queue1"], "wait": true}).to_string());
+    worker.expect_silence(Duration::from_millis(200));
+
+    let id = put(&mut producer, "queue1", 5);
+
+    let response: Value = serde_json::from_str(&worker.read_line()).unwrap();
+    assert_eq!(response["id"], id);
+}
+
+#[test]
+fn rejects_invalid_requests() {
+    let mut client = TcpClient::connect(server());
+
+    for invalid in ["{}", "not json", r#"{"request":"put","queue":"q1"}"#] {
+        client.send_line(invalid);
+        let response: Value = serde_json::from_str(&client.read_line()).unwrap();
+        assert_eq!(response["status"], "error");
+    }
+}
+
+#[test]
+fn rejects_oversized_requests_and_carries_on() {
+    let config = Config {
+        max_line_length: 128,
+        ..job_centre::default_config()
+    };
+    let mut client = TcpClient::connect(testing::spawn(Transport::Tcp, config, job_centre::run));
+
+    let job = json!({"data": "x".repeat(128)});
+    let response = request(
+        &mut client,
+        json!({"request": "put", "queue": "q1", "job": job, "pri": 1}),
+    );
+    assert_eq!(response["status"], "error");
+    assert_eq!(response["error"], "Request too long");
+
+    put(&mut client, "q1", 1);
+}
@@ -1,9 +1,10 @@
 use crate::lcrp::message::{parse_message, LcrpMessage};
 use crate::lcrp::stream::LrcpStream;
+use shared::pool::ShutdownHandle;
 use std::{
     cell::RefCell,
     collections::HashMap,
-    io::{self, ErrorKind},
+    io::{self, Error, ErrorKind},
     net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket},
     str, thread,
     time::{Duration, Instant},
@@ -12,6 +13,7 @@ use std::{
 pub struct LrcpListener {
     pub socket: UdpSocket,
     pub sessions: HashMap<i32, Instant>,
+    pub shutdown: Option<ShutdownHandle>,
 }
 
 pub struct Incoming<'a> {
@@ -26,15 +28,24 @@ impl LrcpListener {
         Ok(LrcpListener {
             socket,
             sessions: HashMap::new(),
+            shutdown: None,
         })
     }
 
-    pub fn incoming(&mut self) -> Incoming {
+    pub fn set_shutdown_handle(&mut self, handle: ShutdownHandle) {
+        self.shutdown = Some(handle);
+    }
+
+    pub fn incoming(&mut self) -> Incoming<'_> {
         Incoming { listener: self }
     }
 
     pub fn accept(&mut self) -> io::Result<LrcpStream> {
         loop {
+            if matches!(self.shutdown, Some(ref handle) if handle.is_shutdown()) {
+                return Err(Error::from(ErrorKind::Interrupted));
+            }
+
             thread::sleep(Duration::from_millis(5));
             self.sessions
                 .retain(|_, v| v.elapsed() < Duration::from_secs(30));
@@ -73,7 +84,7 @@ impl LrcpListener {
                         socket: clone,
                         ack: RefCell::new(0),
                         llen: RefCell::new(0),
-                        received: RefCell::new("".to_string()),
+                        received: RefCell::new(Vec::new()),
                         sent: RefCell::new("".to_string()),
                     };
 
@@ -84,6 +95,9 @@ impl LrcpListener {
                 {
                     self.socket.recv_from(&mut buf)?;
                     self.sessions.remove(session_id);
+
+                    let response = format!("/close/{}/", session_id);
+                    self.socket.send_to(response.as_bytes(), src)?;
                 }
                 Ok(
                     LcrpMessage::Ack { ref session_id, .. }
@@ -112,6 +126,9 @@ impl LrcpListener {
 impl Iterator for Incoming<'_> {
     type Item = io::Result<LrcpStream>;
     fn next(&mut self) -> Option<io::Result<LrcpStream>> {
-        Some(self.listener.accept())
+        match self.listener.accept() {
+            Err(e) if e.kind() == ErrorKind::Interrupted => None,
+            result => Some(result),
+        }
     }
 }
@@ -24,7 +24,14 @@ pub enum LcrpMessage {
 
 pub fn escape(data: String) -> String {
     let mut escaped = str::replace(&str::replace(data.as_str(), r"\", r"\\"), r"`", r"\/");
-    String::truncate(&mut escaped, 950);
+    let mut end = escaped.len().min(950);
+
+    // Don't split a character, which `truncate` would panic on.
+    while !escaped.is_char_boundary(end) {
+        end -= 1;
+    }
+
+    String::truncate(&mut escaped, end);
 
     escaped
 }
@@ -33,17 +40,26 @@ pub fn unescape(data: String) -> String {
     str::replace(&str::replace(data.as_str(), r"\\", r"\"), r"\/", r"`")
 }
 
+/// Parses a numeric field, which must be digits only and below 2^31.
+fn parse_number(text: &str) -> Option<i32> {
+    if text.is_empty() || !text.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    text.parse().ok()
+}
+
 pub fn parse_message(packet: &[u8]) -> io::Result<LcrpMessage> {
-    let data = str::from_utf8(packet).unwrap();
+    let data = str::from_utf8(packet).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
 
     match unescape(data.to_string()).split('/').collect::<Vec<&str>>()[..] {
-        ["", "connect", session_id_str, ""] => match session_id_str.parse::<i32>() {
-            Ok(session_id) => Ok(LcrpMessage::Connect { session_id }),
+        ["", "connect", session_id_str, ""] => match parse_number(session_id_str) {
+            Some(session_id) => Ok(LcrpMessage::Connect { session_id }),
             _ => Err(Error::from(ErrorKind::InvalidData)),
         },
         ["", "data", session_id_str, pos_str, data, ""] => {
-            match (session_id_str.parse::<i32>(), pos_str.parse::<i32>()) {
-                (Ok(session_id), Ok(pos)) => Ok(LcrpMessage::Data {
+            match (parse_number(session_id_str), parse_number(pos_str)) {
+                (Some(session_id), Some(pos)) => Ok(LcrpMessage::Data {
                     session_id,
                     data: data.as_bytes().to_vec(),
                     pos,
@@ -52,13 +68,13 @@ pub fn parse_message(packet: &[u8]) -> io::Result<LcrpMessage> {
             }
         }
         ["", "ack", session_id_str, length_str, ""] => {
-            match (session_id_str.parse::<i32>(), length_str.parse::<i32>()) {
-                (Ok(session_id), Ok(len)) => Ok(LcrpMessage::Ack { session_id, len }),
+            match (parse_number(session_id_str), parse_number(length_str)) {
+                (Some(session_id), Some(len)) => Ok(LcrpMessage::Ack { session_id, len }),
                 _ => Err(Error::from(ErrorKind::InvalidData)),
             }
         }
-        ["", "close", session_id_str, ""] => match session_id_str.parse::<i32>() {
-            Ok(session_id) => Ok(LcrpMessage::Close { session_id }),
+        ["", "close", session_id_str, ""] => match parse_number(session_id_str) {
+            Some(session_id) => Ok(LcrpMessage::Close { session_id }),
             _ => Err(Error::from(ErrorKind::InvalidData)),
         },
         [""] => Err(Error::from(ErrorKind::UnexpectedEof)),
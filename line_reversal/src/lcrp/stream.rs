@@ -13,7 +13,7 @@ pub struct LrcpStream {
     pub src: SocketAddr,
     pub ack: RefCell<i32>,
     pub llen: RefCell<i32>,
-    pub received: RefCell<String>,
+    pub received: RefCell<Vec<u8>>,
     pub sent: RefCell<String>,
 }
 
@@ -45,16 +45,15 @@ impl LrcpStream {
                         .send_to(response.to_string().as_bytes(), self.src)?;
                 }
 
-                if pos < *ack && (pos + data.len() as i32) > *ack {
+                // Data we already have, so the peer missed our ack for it.
+                if pos < *ack {
                     let response = format!("/ack/{}/{}/", session_id, ack);
                     self.socket
                         .send_to(response.to_string().as_bytes(), self.src)?;
                 }
 
                 if pos == *ack {
-                    self.received
-                        .borrow_mut()
-                        .push_str(str::from_utf8(&data).unwrap());
+                    self.received.borrow_mut().extend_from_slice(&data);
                     *ack += data.len() as i32;
 
                     let response = format!("/ack/{}/{}/", session_id, ack);
@@ -67,7 +66,10 @@ impl LrcpStream {
             Ok(LcrpMessage::Ack { session_id, len }) if session_id == self.session_id => {
                 self.socket.recv_from(&mut lrcp_buf)?;
 
-                if len > self.sent.borrow().len() as i32 {
+                // Acking more than we sent, or part of a character, is a misbehaving peer.
+                if len > self.sent.borrow().len() as i32
+                    || !self.sent.borrow().is_char_boundary(len as usize)
+                {
                     let response = format!("/close/{}/", session_id);
                     self.socket
                         .send_to(response.to_string().as_bytes(), self.src)?;
@@ -124,11 +126,12 @@ impl Read for &LrcpStream {
             Ok(()) => {
                 let mut received = self.received.borrow_mut();
 
-                match received.rfind('\n') {
+                match received.iter().rposition(|&b| b == b'\n') {
                     Some(n) => {
-                        buf[..=n].copy_from_slice(received[..=n].as_bytes());
-                        received.drain(..=n);
-                        Ok(n + 1)
+                        let len = buf.len().min(n + 1);
+                        buf[..len].copy_from_slice(&received[..len]);
+                        received.drain(..len);
+                        Ok(len)
                     }
                     _ => Err(Error::from(ErrorKind::WouldBlock)),
                 }
@@ -140,7 +143,7 @@ impl Read for &LrcpStream {
 
 impl Write for &LrcpStream {
     fn write(&mut self, write_buf: &[u8]) -> io::Result<usize> {
-        let data = str::from_utf8(write_buf).unwrap();
+        let data = str::from_utf8(write_buf).map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
 
         let response = format!(
             "/data/{}/{}/{}/",
@@ -0,0 +1,70 @@
+pub mod lcrp;
+
+use lcrp::{listener::LrcpListener, stream::LrcpStream};
+use shared::{config::Config, error, log, metrics, pool::ThreadPool, signal, warn};
+use std::io::{BufRead, BufReader, ErrorKind, Result, Write};
+
+/// Defaults for `run`, before flags and environment variables are applied.
+pub fn default_config() -> Config {
+    Config::new(100)
+}
+
+/// Serves the protocol as configured by `config` until the process is asked to shut down.
+pub fn run(config: &Config) -> Result<()> {
+    let mut listener = LrcpListener::bind(&config.address())?;
+
+    if let Some(address) = config.metrics_address() {
+        metrics::serve(&address)?;
+    }
+
+    let pool = ThreadPool::new(config.workers);
+    let shutdown = pool.shutdown_handle();
+    listener.set_shutdown_handle(shutdown.clone());
+    signal::on_terminate(move || shutdown.shutdown())?;
+
+    for stream in listener.incoming() {
+        let stream = stream?;
+
+        pool.execute(|_| {
+            let _scope = log::connection(stream.src);
+
+            if let Err(e) = handle_connection(stream) {
+                error!("Connection error: {}", e);
+            }
+        });
+    }
+
+    let report = pool.shutdown(config.shutdown_timeout);
+
+    if !report.is_clean() {
+        warn!(
+            "{} sessions still open at shutdown on workers {:?}; abandoning.",
+            report.busy.len(),
+            report.busy
+        );
+    }
+
+    Ok(())
+}
+
+fn handle_connection(stream: LrcpStream) -> Result<()> {
+    let mut reader = BufReader::new(&stream);
+    let mut writer = &stream;
+
+    loop {
+        let mut line = String::new();
+        match reader.read_line(&mut line) {
+            Ok(0) => break,
+            Ok(_) => {
+                line.pop();
+                let mut response = line.chars().rev().collect::<String>();
+                response.push('\n');
+                writer.write_all(response.as_bytes())?;
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+            Err(_) => break,
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,134 @@
+use shared::testing::{self, Transport, UdpClient};
+use std::{net::SocketAddr, time::Duration};
+
+fn server() -> SocketAddr {
+    testing::spawn(
+        Transport::Udp,
+        line_reversal::default_config(),
+        line_reversal::run,
+    )
+}
+
+fn connect(address: SocketAddr, session: i32) -> UdpClient {
+    let client = UdpClient::connect(address);
+    client.send(format!("/connect/{session}/"));
+    client.expect(format!("/ack/{session}/0/"));
+    client
+}
+
+#[test]
+fn reverses_lines() {
+    let client = connect(server(), 12345);
+
+    client.send("/data/12345/0/hello\n/");
+    client.expect("/ack/12345/6/");
+    client.expect("/data/12345/0/olleh\n/");
+    client.send("/ack/12345/6/");
+
+    client.send("/data/12345/6/Hello, world!\n/");
+    client.expect("/ack/12345/20/");
+    client.expect("/data/12345/6/!dlrow ,olleH\n/");
+    client.send("/ack/12345/20/");
+}
+
+#[test]
+fn reassembles_lines_split_across_packets() {
+    let client = connect(server(), 7);
+
+    client.send("/data/7/0/ab/");
+    client.expect("/ack/7/2/");
+    client.send("/data/7/2/c\n/");
+    client.expect("/ack/7/4/");
+    client.expect("/data/7/0/cba\n/");
+    client.send("/ack/7/4/");
+}
+
+#[test]
+fn acks_with_current_position_when_data_skips_ahead() {
+    let client = connect(server(), 8);
+
+    client.send("/data/8/5/later\n/");
+    client.expect("/ack/8/0/");
+}
+
+#[test]
+fn acks_data_it_already_has_again() {
+    let client = connect(server(), 9);
+
+    client.send("/data/9/0/ab/");
+    client.expect("/ack/9/2/");
+    client.send("/data/9/0/ab/");
+    client.expect("/ack/9/2/");
+}
+
+#[test]
+fn retransmits_unacknowledged_data() {
+    let client = connect(server(), 99);
+
+    client.send("/data/99/0/abc\n/");
+    client.expect("/ack/99/4/");
+    client.expect("/data/99/0/cba\n/");
+    client.expect("/data/99/0/cba\n/");
+
+    client.send("/ack/99/4/");
+}
+
+#[test]
+fn escapes_slashes_and_backslashes() {
+    let client = connect(server(), 5);
+
+    client.send(r"/data/5/0/a\/b\\c\n/".replace(r"\n", "\n"));
+    client.expect("/ack/5/6/");
+    client.expect(r"/data/5/0/c\\b\/a".to_string() + "\n/");
+    client.send("/ack/5/6/");
+}
+
+#[test]
+fn closes_unknown_sessions() {
+    let client = UdpClient::connect(server());
+
+    client.send("/data/404/0/hello\n/");
+    client.expect("/close/404/");
+}
+
+#[test]
+fn closes_sessions_on_request() {
+    let client = connect(server(), 3);
+
+    client.send("/close/3/");
+    client.expect("/close/3/");
+}
+
+#[test]
+fn ignores_invalid_packets() {
+    let client = UdpClient::connect(server());
+
+    client.send("/connect/");
+    client.send("/data/1/not-a-number/x/");
+    client.send("garbage");
+    client.expect_silence(Duration::from_millis(200));
+}
+
+#[test]
+fn survives_packets_that_are_not_utf8() {
+    let address = server();
+
+    UdpClient::connect(address).send([b'/', 0xff, 0xfe, b'/']);
+    connect(address, 7);
+}
+
+#[test]
+fn ignores_negative_and_oversized_numbers() {
+    let client = connect(server(), 4);
+
+    client.send("/ack/4/-5/");
+    client.send("/data/4/-1/x/");
+    client.send("/ack/4/2147483648/");
+    client.send("/connect/-4/");
+    client.expect_silence(Duration::from_millis(200));
+
+    client.send("/data/4/0/ab\n/");
+    client.expect("/ack/4/3/");
+    client.expect("/data/4/0/ba\n/");
+    client.send("/ack/4/3/");
+}
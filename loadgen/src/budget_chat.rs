@@ -0,0 +1,161 @@
+use crate::{connect, is_timeout, report::Samples, Options, TIMEOUT};
+use std::{
+    io::{prelude::*, BufReader, Error, ErrorKind, Result},
+    net::TcpStream,
+    time::{Duration, Instant},
+};
+
+/// How often an idle reader wakes up to check the deadline.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Clients pair up in the one room: even ids ping and their odd partners answer, and each
+/// round trip through the server is one request. Every message still goes to the whole room,
+/// so load grows with the square of the number of clients. An odd client out just listens.
+pub(crate) fn client(
+    options: &Options,
+    id: usize,
+    deadline: Instant,
+    samples: &mut Samples,
+) -> Result<()> {
+    let stream = connect(options)?;
+    stream.set_read_timeout(Some(POLL_INTERVAL))?;
+
+    let pair = id / 2;
+    let mut lines = Lines::new(&stream);
+
+    if id % 2 == 0 {
+        let partner = format!("pong{pair}");
+        let present = join(&mut lines, &format!("ping{pair}"))?;
+
+        if !present.contains(&partner) {
+            wait_for(&mut lines, &partner, deadline)?;
+        }
+
+        ping(&mut lines, &partner, deadline, samples)
+    } else {
+        join(&mut lines, &format!("pong{pair}"))?;
+        pong(&mut lines, &format!("[ping{pair}] ping "), deadline)
+    }
+}
+
+/// Joins the room as `name`, returning the names of those already there.
+fn join(lines: &mut Lines, name: &str) -> Result<Vec<String>> {
+    lines.expect_within(TIMEOUT)?;
+    lines.write(name)?;
+
+    let present = lines.expect_within(TIMEOUT)?;
+    let names = present
+        .strip_prefix("* The room contains:")
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Expected the room's members"))?
+        .split(',')
+        .map(|name| name.trim().to_string())
+        .collect();
+
+    Ok(names)
+}
+
+fn wait_for(lines: &mut Lines, partner: &str, deadline: Instant) -> Result<()> {
+    let entered = format!("* {partner} has entered the room");
+
+    while Instant::now() < deadline {
+        if lines.next()?.as_deref() == Some(&entered) {
+            return Ok(());
+        }
+    }
+
+    Ok(())
+}
+
+fn ping(lines: &mut Lines, partner: &str, deadline: Instant, samples: &mut Samples) -> Result<()> {
+    for round in 0.. {
+        if Instant::now() >= deadline {
+            break;
+        }
+
+        let pong = format!("[{partner}] pong {round}");
+        let started = Instant::now();
+        lines.write(&format!("ping {round}"))?;
+
+        loop {
+            match lines.next()? {
+                Some(line) if line == pong => {
+                    samples.latencies.push(started.elapsed());
+                    break;
+                }
+                Some(line) if line == format!("* {partner} has left the room") => {
+                    return Err(Error::new(ErrorKind::ConnectionReset, "Partner left"));
+                }
+                _ if started.elapsed() > TIMEOUT => {
+                    samples.errors += 1;
+                    break;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn pong(lines: &mut Lines, ping: &str, deadline: Instant) -> Result<()> {
+    while Instant::now() < deadline {
+        if let Some(round) = lines
+            .next()?
+            .as_deref()
+            .and_then(|line| line.strip_prefix(ping))
+        {
+            lines.write(&format!("pong {round}"))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads lines through read timeouts, keeping any partial line for the next call.
+struct Lines<'a> {
+    reader: BufReader<&'a TcpStream>,
+    line: Vec<u8>,
+}
+
+impl<'a> Lines<'a> {
+    fn new(stream: &'a TcpStream) -> Lines<'a> {
+        Lines {
+            reader: BufReader::new(stream),
+            line: Vec::new(),
+        }
+    }
+
+    /// The next whole line without its newline, or `None` if none arrived in time.
+    fn next(&mut self) -> Result<Option<String>> {
+        match self.reader.read_until(b'\n', &mut self.line) {
+            Ok(0) => Err(Error::from(ErrorKind::UnexpectedEof)),
+            Ok(_) if self.line.ends_with(b"\n") => {
+                self.line.pop();
+                let line = String::from_utf8_lossy(&self.line).into_owned();
+                self.line.clear();
+
+                Ok(Some(line))
+            }
+            Ok(_) => Ok(None),
+            Err(e) if is_timeout(e.kind()) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn expect_within(&mut self, timeout: Duration) -> Result<String> {
+        let started = Instant::now();
+
+        while started.elapsed() < timeout {
+            if let Some(line) = self.next()? {
+                return Ok(line);
+            }
+        }
+
+        Err(Error::from(ErrorKind::TimedOut))
+    }
+
+    fn write(&mut self, line: &str) -> Result<()> {
+        let mut writer = *self.reader.get_ref();
+        writeln!(writer, "{line}")
+    }
+}
@@ -0,0 +1,130 @@
+//! Simulated clients for the protocol servers, for reproducing checker-like load locally. Each
+//! client runs on its own thread and times every request it makes; `run` combines their
+//! samples into a `Report`.
+
+mod budget_chat;
+mod line_reversal;
+mod means_to_an_end;
+mod prime_time;
+mod random;
+pub mod report;
+mod speed_daemon;
+
+use random::Random;
+use report::{Report, Samples};
+use shared::debug;
+use std::{
+    io::{ErrorKind, Result},
+    net::{SocketAddr, TcpStream},
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// How long a client waits for any one response before counting it as failed.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long a client waits before reconnecting after an error.
+const RETRY_DELAY: Duration = Duration::from_millis(100);
+
+pub struct Options {
+    pub address: SocketAddr,
+    pub clients: usize,
+    pub duration: Duration,
+    /// Fraction of UDP packets dropped in each direction, for protocols over UDP.
+    pub loss: f64,
+}
+
+/// Runs one client's connection until `deadline`, recording into `samples`. Returning an
+/// error counts as one failed request; the client reconnects if there is time left.
+type Client = fn(&Options, usize, Instant, &mut Samples) -> Result<()>;
+
+pub struct Protocol {
+    pub name: &'static str,
+    client: Client,
+}
+
+pub const PROTOCOLS: &[Protocol] = &[
+    Protocol {
+        name: "prime_time",
+        client: prime_time::client,
+    },
+    Protocol {
+        name: "means_to_an_end",
+        client: means_to_an_end::client,
+    },
+    Protocol {
+        name: "budget_chat",
+        client: budget_chat::client,
+    },
+    Protocol {
+        name: "speed_daemon",
+        client: speed_daemon::client,
+    },
+    Protocol {
+        name: "line_reversal",
+        client: line_reversal::client,
+    },
+];
+
+pub fn protocol(name: &str) -> Option<&'static Protocol> {
+    PROTOCOLS.iter().find(|protocol| protocol.name == name)
+}
+
+/// Runs `options.clients` clients of `protocol` against `options.address` for
+/// `options.duration`.
+pub fn run(protocol: &Protocol, options: &Options) -> Report {
+    let start = Instant::now();
+    let deadline = start + options.duration;
+
+    let samples = thread::scope(|scope| {
+        let handles: Vec<_> = (0..options.clients)
+            .map(|id| scope.spawn(move || simulate(protocol, options, id, deadline)))
+            .collect();
+
+        handles
+            .into_iter()
+            .fold(Samples::default(), |mut total, handle| {
+                total.merge(handle.join().unwrap());
+                total
+            })
+    });
+
+    Report::new(options.clients, start.elapsed(), samples)
+}
+
+fn simulate(protocol: &Protocol, options: &Options, id: usize, deadline: Instant) -> Samples {
+    let mut samples = Samples::default();
+
+    while Instant::now() < deadline {
+        if let Err(e) = (protocol.client)(options, id, deadline, &mut samples) {
+            debug!("Client {id} failed: {e}");
+            samples.errors += 1;
+            thread::sleep(RETRY_DELAY);
+        }
+    }
+
+    samples
+}
+
+fn connect(options: &Options) -> Result<TcpStream> {
+    let stream = TcpStream::connect(options.address)?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_nodelay(true)?;
+
+    Ok(stream)
+}
+
+/// A generator for client `id`, seeded differently on every run so that repeated runs against
+/// one server don't collide.
+fn random(id: usize) -> Random {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos() as u64;
+
+    Random::new(nanos ^ (id as u64).rotate_left(32))
+}
+
+fn is_timeout(kind: ErrorKind) -> bool {
+    matches!(kind, ErrorKind::WouldBlock | ErrorKind::TimedOut)
+}
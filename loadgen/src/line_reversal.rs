@@ -0,0 +1,165 @@
+use crate::{is_timeout, random, report::Samples, Options, Random, TIMEOUT};
+use line_reversal::lcrp::message::{parse_message, LcrpMessage};
+use std::{
+    io::{Error, ErrorKind, Result},
+    net::{Ipv4Addr, UdpSocket},
+    time::{Duration, Instant},
+};
+
+/// How long to wait for an ack before sending data again.
+const RETRANSMIT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Each client runs an LRCP session, sending one line at a time and timing it until the whole
+/// reversed line is back. Packets in both directions are dropped with probability
+/// `options.loss`, so retransmission on both ends gets exercised.
+pub(crate) fn client(
+    options: &Options,
+    id: usize,
+    deadline: Instant,
+    samples: &mut Samples,
+) -> Result<()> {
+    let mut session = Session::connect(options, id)?;
+
+    while Instant::now() < deadline {
+        let length = 1 + session.random.below(40);
+        let line: String = (0..length)
+            .map(|_| char::from(b'a' + session.random.below(26) as u8))
+            .collect();
+
+        let started = Instant::now();
+        session.request(&line)?;
+        samples.latencies.push(started.elapsed());
+    }
+
+    session.send(&format!("/close/{}/", session.id))
+}
+
+struct Session {
+    socket: UdpSocket,
+    id: i32,
+    random: Random,
+    loss: f64,
+    /// Bytes sent, and how many of them the server has acknowledged.
+    sent: usize,
+    acked: usize,
+    /// Bytes received in order.
+    received: Vec<u8>,
+}
+
+impl Session {
+    fn connect(options: &Options, id: usize) -> Result<Session> {
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?;
+        socket.connect(options.address)?;
+        socket.set_read_timeout(Some(RETRANSMIT_INTERVAL))?;
+
+        let mut random = random(id);
+        let mut session = Session {
+            socket,
+            id: random.below(i32::MAX as u64) as i32,
+            random,
+            loss: options.loss,
+            sent: 0,
+            acked: 0,
+            received: Vec::new(),
+        };
+
+        let started = Instant::now();
+
+        while started.elapsed() < TIMEOUT {
+            session.send(&format!("/connect/{}/", session.id))?;
+
+            if let Some(LcrpMessage::Ack { len: 0, .. }) = session.recv()? {
+                return Ok(session);
+            }
+        }
+
+        Err(Error::from(ErrorKind::TimedOut))
+    }
+
+    /// Sends `line` and waits for it to be acknowledged and for its reversal to arrive.
+    fn request(&mut self, line: &str) -> Result<()> {
+        let data = format!("/data/{}/{}/{line}\n/", self.id, self.sent);
+        self.sent += line.len() + 1;
+
+        let mut expected: Vec<u8> = line.bytes().rev().collect();
+        expected.push(b'\n');
+        let end = self.received.len() + expected.len();
+
+        let started = Instant::now();
+        let mut last_sent = None;
+
+        while self.acked < self.sent || self.received.len() < end {
+            if started.elapsed() > TIMEOUT {
+                return Err(Error::from(ErrorKind::TimedOut));
+            }
+
+            if self.acked < self.sent
+                && last_sent.is_none_or(|sent: Instant| sent.elapsed() >= RETRANSMIT_INTERVAL)
+            {
+                self.send(&data)?;
+                last_sent = Some(Instant::now());
+            }
+
+            match self.recv()? {
+                Some(LcrpMessage::Ack { len, .. }) => {
+                    self.acked = self.acked.max(len as usize);
+                }
+                Some(LcrpMessage::Data { pos, data, .. }) => {
+                    if pos as usize == self.received.len() {
+                        self.received.extend(data);
+                    }
+
+                    self.send(&format!("/ack/{}/{}/", self.id, self.received.len()))?;
+                }
+                Some(LcrpMessage::Close { .. }) => {
+                    return Err(Error::from(ErrorKind::ConnectionReset));
+                }
+                Some(LcrpMessage::Connect { .. }) | None => {}
+            }
+        }
+
+        if self.received[end - expected.len()..end] != expected {
+            return Err(Error::new(ErrorKind::InvalidData, "Wrong reversal"));
+        }
+
+        Ok(())
+    }
+
+    /// Sends `packet`, unless it is lost.
+    fn send(&mut self, packet: &str) -> Result<()> {
+        if !self.random.chance(self.loss) {
+            self.socket.send(packet.as_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    /// The next packet for this session that isn't lost, or `None` if none arrives in time.
+    fn recv(&mut self) -> Result<Option<LcrpMessage>> {
+        let mut buffer = [0; 1000];
+
+        let len = match self.socket.recv(&mut buffer) {
+            Ok(len) => len,
+            Err(e) if is_timeout(e.kind()) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        if self.random.chance(self.loss) {
+            return Ok(None);
+        }
+
+        match parse_message(&buffer[..len]) {
+            Ok(message) if session_id(&message) == self.id => Ok(Some(message)),
+            _ => Ok(None),
+        }
+    }
+}
+
+fn session_id(message: &LcrpMessage) -> i32 {
+    match message {
+        LcrpMessage::Ack { session_id, .. }
+        | LcrpMessage::Close { session_id }
+        | LcrpMessage::Connect { session_id }
+        | LcrpMessage::Data { session_id, .. } => *session_id,
+    }
+}
@@ -0,0 +1,91 @@
+use loadgen::{Options, PROTOCOLS};
+use shared::{
+    config,
+    log::{self, Format, Level},
+};
+use std::{
+    env,
+    io::{Error, ErrorKind, Result},
+    net::{SocketAddr, ToSocketAddrs},
+    process,
+    time::Duration,
+};
+
+const USAGE: &str = "Usage: loadgen <protocol> [options]
+
+Runs simulated clients against a server and reports throughput and latency percentiles.
+
+Options:
+  --address <host:port>   Server to load [default: 127.0.0.1:8080]
+  --clients <count>       Concurrent clients [default: 10]
+  --duration <duration>   How long to run, e.g. 30s or 500ms [default: 10s]
+  --loss <fraction>       UDP packets to drop in each direction, e.g. 0.1 [default: 0]
+  --log-level <level>     error, warn, info or debug [default: warn]
+  --help                  Print this message";
+
+fn main() -> Result<()> {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    if args.is_empty() || args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        let names: Vec<&str> = PROTOCOLS.iter().map(|protocol| protocol.name).collect();
+        println!("{USAGE}\n\nProtocols: {}", names.join(", "));
+        process::exit(0);
+    }
+
+    let Some(protocol) = loadgen::protocol(&args[0]) else {
+        return Err(invalid(format!("Unknown protocol: {}", args[0])));
+    };
+
+    let mut options = Options {
+        address: SocketAddr::from(([127, 0, 0, 1], 8080)),
+        clients: 10,
+        duration: Duration::from_secs(10),
+        loss: 0.0,
+    };
+    let mut log_level = Level::Warn;
+
+    let mut flags = args[1..].iter();
+
+    while let Some(arg) = flags.next() {
+        let Some(flag) = arg.strip_prefix("--") else {
+            return Err(invalid(format!("Unexpected argument: {arg}")));
+        };
+        let Some(value) = flags.next() else {
+            return Err(invalid(format!("Missing value for {arg}")));
+        };
+
+        match flag {
+            "address" => {
+                options.address = value
+                    .to_socket_addrs()?
+                    .next()
+                    .ok_or_else(|| invalid(format!("Invalid address: {value}")))?;
+            }
+            "clients" => options.clients = parse(flag, value)?,
+            "duration" => options.duration = config::parse_duration(flag, value)?,
+            "loss" => options.loss = parse(flag, value)?,
+            "log-level" => log_level = parse(flag, value)?,
+            _ => return Err(invalid(format!("Unknown flag: {arg}"))),
+        }
+    }
+
+    log::init(log_level, Format::Text);
+
+    println!(
+        "Running {} {} clients against {} for {:?}",
+        options.clients, protocol.name, options.address, options.duration
+    );
+    println!("{}", loadgen::run(protocol, &options));
+
+    Ok(())
+}
+
+fn parse<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T> {
+    value
+        .parse()
+        .map_err(|_| invalid(format!("Invalid value for --{flag}: {value}")))
+}
+
+fn invalid(message: String) -> Error {
+    Error::new(ErrorKind::InvalidInput, message)
+}
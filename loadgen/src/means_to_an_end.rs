@@ -0,0 +1,48 @@
+use crate::{connect, random, report::Samples, Options};
+use means_to_an_end::Message;
+use shared::codec::Encode;
+use std::{
+    io::{prelude::*, Result},
+    time::Instant,
+};
+
+/// Inserts a price and then queries the mean of a recent range, timing the answer. Each
+/// connection is its own session, so its price history grows for as long as it runs.
+pub(crate) fn client(
+    options: &Options,
+    id: usize,
+    deadline: Instant,
+    samples: &mut Samples,
+) -> Result<()> {
+    let mut stream = connect(options)?;
+    let mut random = random(id);
+    let mut timestamp = 0i32;
+    let mut mean = [0; 4];
+
+    while Instant::now() < deadline {
+        timestamp = timestamp.wrapping_add(1 + random.below(100) as i32);
+
+        let insert = Message {
+            message_type: b'I',
+            a: timestamp,
+            b: random.below(10_000) as i32,
+        };
+        let query = Message {
+            message_type: b'Q',
+            a: timestamp.wrapping_sub(random.below(10_000) as i32),
+            b: timestamp,
+        };
+
+        let mut bytes = Vec::new();
+        insert.encode(&mut bytes)?;
+        query.encode(&mut bytes)?;
+
+        let started = Instant::now();
+        stream.write_all(&bytes)?;
+        stream.read_exact(&mut mean)?;
+
+        samples.latencies.push(started.elapsed());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,62 @@
+use crate::{connect, random, report::Samples, Options, Random};
+use prime_time::models::{Request, Response};
+use serde_json::value::RawValue;
+use std::{
+    io::{prelude::*, BufReader, Error, ErrorKind, Result},
+    time::Instant,
+};
+
+/// Sends one `isPrime` request at a time and checks that each gets an answer.
+pub(crate) fn client(
+    options: &Options,
+    id: usize,
+    deadline: Instant,
+    samples: &mut Samples,
+) -> Result<()> {
+    let stream = connect(options)?;
+    let mut reader = BufReader::new(&stream);
+    let mut writer = &stream;
+    let mut random = random(id);
+    let mut line = String::new();
+
+    while Instant::now() < deadline {
+        let request = Request {
+            method: "isPrime".to_string(),
+            number: number(&mut random),
+            jsonrpc: None,
+            id: None,
+        };
+        let mut bytes = serde_json::to_vec(&request)?;
+        bytes.push(b'\n');
+
+        let started = Instant::now();
+        writer.write_all(&bytes)?;
+
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(Error::from(ErrorKind::UnexpectedEof));
+        }
+
+        let response: Response = serde_json::from_str(&line)?;
+
+        if response.method != "isPrime" {
+            return Err(Error::new(ErrorKind::InvalidData, "Unexpected method"));
+        }
+
+        samples.latencies.push(started.elapsed());
+    }
+
+    Ok(())
+}
+
+/// Mostly integers below a million, with some non-integral floats and numbers too large for
+/// a `u64` mixed in.
+fn number(random: &mut Random) -> Box<RawValue> {
+    let number = match random.below(10) {
+        0 => format!("{}.5", random.below(1_000_000)),
+        1 => format!("{}{:06}", u64::MAX, random.below(1_000_000)),
+        _ => random.below(1_000_000).to_string(),
+    };
+
+    RawValue::from_string(number).unwrap()
+}
@@ -0,0 +1,81 @@
+use std::{fmt, time::Duration};
+
+/// What one simulated client observed: how long each completed request took, and how many
+/// requests failed or went unanswered.
+#[derive(Debug, Default)]
+pub struct Samples {
+    pub latencies: Vec<Duration>,
+    pub errors: usize,
+}
+
+impl Samples {
+    pub fn merge(&mut self, other: Samples) {
+        self.latencies.extend(other.latencies);
+        self.errors += other.errors;
+    }
+}
+
+/// Totals for a whole run.
+#[derive(Debug)]
+pub struct Report {
+    pub clients: usize,
+    pub elapsed: Duration,
+    pub requests: usize,
+    pub errors: usize,
+    /// Sorted, shortest first.
+    pub latencies: Vec<Duration>,
+}
+
+impl Report {
+    pub fn new(clients: usize, elapsed: Duration, mut samples: Samples) -> Report {
+        samples.latencies.sort_unstable();
+
+        Report {
+            clients,
+            elapsed,
+            requests: samples.latencies.len(),
+            errors: samples.errors,
+            latencies: samples.latencies,
+        }
+    }
+
+    /// Completed requests per second.
+    pub fn throughput(&self) -> f64 {
+        self.requests as f64 / self.elapsed.as_secs_f64()
+    }
+
+    /// The latency that `quantile` of requests completed within, e.g. 0.99 for p99.
+    pub fn percentile(&self, quantile: f64) -> Option<Duration> {
+        let rank = (quantile * self.latencies.len() as f64).ceil() as usize;
+
+        self.latencies.get(rank.saturating_sub(1)).copied()
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{} clients, {:.1}s: {} requests, {} errors, {:.1} requests/s",
+            self.clients,
+            self.elapsed.as_secs_f64(),
+            self.requests,
+            self.errors,
+            self.throughput()
+        )?;
+
+        write!(f, "latency")?;
+
+        for (label, quantile) in [("p50", 0.5), ("p90", 0.9), ("p99", 0.99), ("p99.9", 0.999)] {
+            match self.percentile(quantile) {
+                Some(latency) => write!(f, " {label}={latency:.2?}")?,
+                None => write!(f, " {label}=-")?,
+            }
+        }
+
+        match self.latencies.last() {
+            Some(latency) => write!(f, " max={latency:.2?}"),
+            None => write!(f, " max=-"),
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use crate::{connect, is_timeout, random, report::Samples, Options};
+use shared::codec::{Decode, DecodePrefixed, Encode, FrameDecoder};
+use speed_daemon::models::{IAmCamera, IAmDispatcher, Plate, Ticket};
+use std::{
+    io::{prelude::*, Error, ErrorKind, Result},
+    net::TcpStream,
+    time::Instant,
+};
+
+const LIMIT: u16 = 60;
+
+/// Messages from the server to a dispatcher.
+enum Reply {
+    Ticket(Ticket),
+    Error(String),
+    Heartbeat,
+}
+
+fn decode_reply(input: &mut &[u8]) -> Result<Reply> {
+    let reply = match u8::decode(input)? {
+        0x21 => Reply::Ticket(Ticket::decode(input)?),
+        0x10 => Reply::Error(String::decode_prefixed::<u8>(input)?),
+        0x41 => Reply::Heartbeat,
+        _ => return Err(Error::new(ErrorKind::InvalidData, "Unknown message type")),
+    };
+
+    Ok(reply)
+}
+
+/// Each client has a road of its own, with two cameras ten miles apart and a dispatcher. A
+/// request is one speeding car: a plate at each camera, timed until the dispatcher has its
+/// ticket.
+pub(crate) fn client(
+    options: &Options,
+    id: usize,
+    deadline: Instant,
+    samples: &mut Samples,
+) -> Result<()> {
+    let road = id as u16;
+    let mut random = random(id);
+
+    let mut dispatcher = connect(options)?;
+    send(&mut dispatcher, 0x81, &IAmDispatcher { roads: vec![road] })?;
+
+    let mut first = camera(options, road, 0)?;
+    let mut second = camera(options, road, 10)?;
+
+    let mut replies = FrameDecoder::new(decode_reply);
+    let prefix = format!("{:08X}", random.next() as u32);
+
+    for car in 0u32.. {
+        if Instant::now() >= deadline {
+            break;
+        }
+
+        let plate = format!("{prefix}{car}");
+
+        // Ten miles in six minutes: 100 mph.
+        send(
+            &mut first,
+            0x20,
+            &Plate {
+                plate: plate.clone(),
+                timestamp: 0,
+            },
+        )?;
+        let started = Instant::now();
+        send(
+            &mut second,
+            0x20,
+            &Plate {
+                plate: plate.clone(),
+                timestamp: 360,
+            },
+        )?;
+
+        loop {
+            match replies.read(&mut dispatcher) {
+                Ok(Some(Reply::Ticket(ticket))) if ticket.plate == plate => {
+                    samples.latencies.push(started.elapsed());
+                    break;
+                }
+                Ok(Some(Reply::Ticket(_) | Reply::Heartbeat)) => {}
+                Ok(Some(Reply::Error(message))) => {
+                    return Err(Error::new(ErrorKind::InvalidData, message));
+                }
+                Ok(None) => return Err(Error::from(ErrorKind::UnexpectedEof)),
+                // The partial reply stays buffered in case it turns up after all.
+                Err(e) if is_timeout(e.kind()) => {
+                    samples.errors += 1;
+                    break;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn camera(options: &Options, road: u16, mile: u16) -> Result<TcpStream> {
+    let mut camera = connect(options)?;
+    send(
+        &mut camera,
+        0x80,
+        &IAmCamera {
+            road,
+            mile,
+            limit: LIMIT,
+        },
+    )?;
+
+    Ok(camera)
+}
+
+fn send(stream: &mut TcpStream, message_type: u8, message: &impl Encode) -> Result<()> {
+    let mut buffer = vec![message_type];
+    message.encode(&mut buffer)?;
+
+    stream.write_all(&buffer)
+}
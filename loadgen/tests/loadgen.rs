@@ -0,0 +1,102 @@
+use loadgen::{
+    report::{Report, Samples},
+    Options,
+};
+use shared::{
+    config::Config,
+    testing::{self, Transport},
+};
+use std::{io::Result, net::SocketAddr, time::Duration};
+
+/// Runs two clients of `protocol` for a moment against a server from `run`.
+fn load(
+    protocol: &str,
+    transport: Transport,
+    config: Config,
+    run: fn(&Config) -> Result<()>,
+) -> Report {
+    let address: SocketAddr = testing::spawn(transport, config, run);
+
+    let options = Options {
+        address,
+        clients: 2,
+        duration: Duration::from_millis(300),
+        loss: 0.0,
+    };
+
+    loadgen::run(loadgen::protocol(protocol).unwrap(), &options)
+}
+
+fn assert_clean(report: Report) {
+    assert!(report.requests > 0, "{report}");
+    assert_eq!(report.errors, 0, "{report}");
+}
+
+#[test]
+fn loads_prime_time() {
+    assert_clean(load(
+        "prime_time",
+        Transport::Tcp,
+        prime_time::default_config(),
+        prime_time::run,
+    ));
+}
+
+#[test]
+fn loads_means_to_an_end() {
+    assert_clean(load(
+        "means_to_an_end",
+        Transport::Tcp,
+        means_to_an_end::default_config(),
+        means_to_an_end::run,
+    ));
+}
+
+#[test]
+fn loads_budget_chat() {
+    assert_clean(load(
+        "budget_chat",
+        Transport::Tcp,
+        budget_chat::default_config(),
+        budget_chat::run,
+    ));
+}
+
+#[test]
+fn loads_speed_daemon() {
+    assert_clean(load(
+        "speed_daemon",
+        Transport::Tcp,
+        speed_daemon::default_config(),
+        speed_daemon::run,
+    ));
+}
+
+#[test]
+fn loads_line_reversal() {
+    assert_clean(load(
+        "line_reversal",
+        Transport::Udp,
+        line_reversal::default_config(),
+        line_reversal::run,
+    ));
+}
+
+#[test]
+fn reports_nearest_rank_percentiles() {
+    let samples = Samples {
+        latencies: (1..=100).rev().map(Duration::from_millis).collect(),
+        errors: 3,
+    };
+    let report = Report::new(4, Duration::from_secs(2), samples);
+
+    assert_eq!(report.throughput(), 50.0);
+    assert_eq!(report.percentile(0.5), Some(Duration::from_millis(50)));
+    assert_eq!(report.percentile(0.99), Some(Duration::from_millis(99)));
+    assert_eq!(report.percentile(1.0), Some(Duration::from_millis(100)));
+    assert_eq!(
+        report.to_string(),
+        "4 clients, 2.0s: 100 requests, 3 errors, 50.0 requests/s\n\
+         latency p50=50.00ms p90=90.00ms p99=99.00ms p99.9=100.00ms max=100.00ms"
+    );
+}
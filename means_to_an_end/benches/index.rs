@@ -0,0 +1,77 @@
+//! Compares range means over a `PriceIndex` against the scan of every price `means_to_an_end`
+//! used to do. Run with `cargo bench -p means_to_an_end`.
+
+use means_to_an_end::index::PriceIndex;
+use shared::bench;
+use std::{collections::HashMap, hint::black_box, time::Duration};
+
+/// How queries were answered before `PriceIndex`: a pass over every price the session holds,
+/// whatever the range.
+fn find_mean_price(min: i32, max: i32, prices: &HashMap<i32, i32>) -> i32 {
+    let (sum, count) = prices
+        .iter()
+        .fold((0i64, 0), |(acc_sum, acc_count), (timestamp, price)| {
+            if *timestamp >= min && *timestamp <= max {
+                (acc_sum + i64::from(*price), acc_count + 1)
+            } else {
+                (acc_sum, acc_count)
+            }
+        });
+
+    if count == 0 {
+        return 0;
+    }
+
+    (sum / count).try_into().unwrap_or_default()
+}
+
+fn main() {
+    let budget = Duration::from_millis(500);
+
+    for size in [1_000, 10_000, 100_000] {
+        // Timestamps spread out and shuffled, as a client inserting out of order would.
+        let prices: Vec<(i32, i32)> = (0..size)
+            .map(|i| ((i * 7919 % size) * 60, 100 + i % 50))
+            .collect();
+        let queries: Vec<(i32, i32)> = (0..1_000)
+            .map(|i| (i * size / 1_000 * 30, i * size / 1_000 * 30 + size * 15))
+            .collect();
+
+        let map: HashMap<i32, i32> = prices.iter().copied().collect();
+        let mut index = PriceIndex::new();
+        for &(timestamp, price) in &prices {
+            index.insert(timestamp, price);
+        }
+
+        bench::run(&format!("HashMap insert, {size} prices"), budget, || {
+            prices.iter().copied().collect::<HashMap<_, _>>()
+        });
+        bench::run(&format!("PriceIndex insert, {size} prices"), budget, || {
+            let mut index = PriceIndex::new();
+            for &(timestamp, price) in &prices {
+                index.insert(timestamp, price);
+            }
+            index
+        });
+        bench::run(
+            &format!("scan, 1000 queries over {size} prices"),
+            budget,
+            || {
+                queries
+                    .iter()
+                    .map(|&(min, max)| find_mean_price(min, max, black_box(&map)))
+                    .sum::<i32>()
+            },
+        );
+        bench::run(
+            &format!("PriceIndex, 1000 queries over {size} prices"),
+            budget,
+            || {
+                queries
+                    .iter()
+                    .map(|&(min, max)| black_box(&index).mean(min, max))
+                    .sum::<i32>()
+            },
+        );
+    }
+}
@@ -0,0 +1,164 @@
+use crate::index::PriceIndex;
+use shared::{info, warn};
+use std::{
+    collections::HashMap,
+    fs::{File, OpenOptions},
+    io::{prelude::*, BufReader, Error, ErrorKind, Result},
+    path::Path,
+    sync::Mutex,
+};
+
+/// The longest asset name, so that its length fits in the record's first byte.
+pub const MAX_NAME_LENGTH: usize = u8::MAX as usize;
+
+/// Prices shared by every session that names the same asset, kept in an append-only file so
+/// they outlive the process.
+///
+/// Each record is the name's length as one byte, the name, then the timestamp and price as
+/// big-endian `i32`s, in the order they were inserted.
+pub struct History {
+    state: Mutex<State>,
+}
+
+struct State {
+    assets: HashMap<String, PriceIndex>,
+    file: File,
+    /// Bytes of complete records in `file`.
+    length: u64,
+}
+
+impl History {
+    /// Opens the history at `path`, creating it if needed, and replays its records. A record
+    /// cut short by a crash is dropped so that later records line up.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<History> {
+        let path = path.as_ref();
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(path)?;
+
+        let mut assets = HashMap::<String, PriceIndex>::new();
+        let mut reader = BufReader::new(&mut file);
+        let mut complete = 0;
+        let mut records = 0;
+
+        while let Some((asset, timestamp, price)) = read_record(&mut reader)? {
+            complete += 1 + asset.len() as u64 + 8;
+            assets.entry(asset).or_default().insert(timestamp, price);
+            records += 1;
+        }
+
+        let length = file.metadata()?.len();
+
+        if complete < length {
+            warn!(
+                "Dropping {} bytes of incomplete record at the end of {}",
+                length - complete,
+                path.display()
+            );
+            file.set_len(complete)?;
+        }
+
+        let length = complete;
+
+        info!(
+            "Replayed {} prices for {} assets from {}",
+            records,
+            assets.len(),
+            path.display()
+        );
+
+        Ok(History {
+            state: Mutex::new(State {
+                assets,
+                file,
+                length,
+            }),
+        })
+    }
+
+    /// Appends `price` at `timestamp` for `asset`, replacing any price already there.
+    pub fn insert(&self, asset: &str, timestamp: i32, price: i32) -> Result<()> {
+        let mut record = Vec::with_capacity(1 + asset.len() + 8);
+        record.push(name_length(asset)?);
+        record.extend(asset.as_bytes());
+        record.extend(timestamp.to_be_bytes());
+        record.extend(price.to_be_bytes());
+
+        let mut state = self.state.lock().unwrap();
+
+        if let Err(e) = state.file.write_all(&record) {
+            // Cut off any part of the record that was written, or records appended after it
+            // would be misread on replay.
+            let length = state.length;
+            state.file.set_len(length)?;
+            return Err(e);
+        }
+
+        state.length += record.len() as u64;
+
+        match state.assets.get_mut(asset) {
+            Some(prices) => prices.insert(timestamp, price),
+            None => {
+                let mut prices = PriceIndex::new();
+                prices.insert(timestamp, price);
+                state.assets.insert(asset.to_string(), prices);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The mean price of `asset` from `min` to `max` inclusive, as `PriceIndex::mean`.
+    pub fn mean(&self, asset: &str, min: i32, max: i32) -> i32 {
+        let state = self.state.lock().unwrap();
+
+        state
+            .assets
+            .get(asset)
+            .map_or(0, |prices| prices.mean(min, max))
+    }
+
+    /// Waits for every record appended so far to reach the disk.
+    pub fn sync(&self) -> Result<()> {
+        self.state.lock().unwrap().file.sync_data()
+    }
+}
+
+/// Reads the next record, or `None` at the end of the file or of its last complete record.
+fn read_record<R: BufRead>(reader: &mut R) -> Result<Option<(String, i32, i32)>> {
+    let mut length = [0; 1];
+
+    match reader.read_exact(&mut length) {
+        Ok(()) => {}
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+
+    let mut record = vec![0; usize::from(length[0]) + 8];
+
+    match reader.read_exact(&mut record) {
+        Ok(()) => {}
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+
+    let (name, numbers) = record.split_at(usize::from(length[0]));
+    let asset = String::from_utf8(name.to_vec())
+        .map_err(|_| Error::new(ErrorKind::InvalidData, "Asset name is not UTF-8"))?;
+    let timestamp = i32::from_be_bytes(numbers[..4].try_into().unwrap());
+    let price = i32::from_be_bytes(numbers[4..].try_into().unwrap());
+
+    Ok(Some((asset, timestamp, price)))
+}
+
+fn name_length(asset: &str) -> Result<u8> {
+    match u8::try_from(asset.len()) {
+        Ok(length) if length > 0 => Ok(length),
+        _ => Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("Asset names must be 1 to {MAX_NAME_LENGTH} bytes"),
+        )),
+    }
+}
@@ -0,0 +1,213 @@
+use std::{
+    cmp::Ordering,
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+};
+
+/// Marks a missing child.
+const NIL: usize = usize::MAX;
+
+/// A session's prices by timestamp, in a treap whose nodes also hold the price sum and count
+/// of their subtree. Inserts and range means both take O(log n) expected time.
+pub struct PriceIndex {
+    nodes: Vec<Node>,
+    root: usize,
+    /// xorshift state for node priorities, seeded randomly so clients can't unbalance the tree.
+    random: u64,
+}
+
+struct Node {
+    timestamp: i32,
+    price: i32,
+    priority: u64,
+    left: usize,
+    right: usize,
+    sum: i64,
+    count: u64,
+}
+
+impl PriceIndex {
+    pub fn new() -> PriceIndex {
+        PriceIndex {
+            nodes: Vec::new(),
+            root: NIL,
+            random: RandomState::new().build_hasher().finish() | 1,
+        }
+    }
+
+    /// Records `price` at `timestamp`, replacing any price already there.
+    pub fn insert(&mut self, timestamp: i32, price: i32) {
+        if self.replace(timestamp, price) {
+            return;
+        }
+
+        self.random ^= self.random << 13;
+        self.random ^= self.random >> 7;
+        self.random ^= self.random << 17;
+
+        let node = self.nodes.len();
+        self.nodes.push(Node {
+            timestamp,
+            price,
+            priority: self.random,
+            left: NIL,
+            right: NIL,
+            sum: 0,
+            count: 0,
+        });
+
+        // Walk down to where the new node's priority puts it, counting its price on the way.
+        let (mut parent, mut child) = (NIL, self.root);
+
+        while child != NIL && self.nodes[child].priority > self.random {
+            let current = &mut self.nodes[child];
+            current.sum += i64::from(price);
+            current.count += 1;
+            parent = child;
+            child = if timestamp < current.timestamp {
+                current.left
+            } else {
+                current.right
+            };
+        }
+
+        let (below, above) = self.split(child, timestamp);
+        self.nodes[node].left = below;
+        self.nodes[node].right = above;
+        self.update(node);
+
+        if parent == NIL {
+            self.root = node;
+        } else if timestamp < self.nodes[parent].timestamp {
+            self.nodes[parent].left = node;
+        } else {
+            self.nodes[parent].right = node;
+        }
+    }
+
+    /// The mean price from `min` to `max` inclusive, rounded towards zero, or 0 if there are
+    /// no prices in that range.
+    pub fn mean(&self, min: i32, max: i32) -> i32 {
+        if min > max {
+            return 0;
+        }
+
+        let (sum_to_max, count_to_max) = self.prefix(max, true);
+        let (sum_below_min, count_below_min) = self.prefix(min, false);
+        let count = count_to_max - count_below_min;
+
+        if count == 0 {
+            return 0;
+        }
+
+        ((sum_to_max - sum_below_min) / count as i64) as i32
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Changes the price at `timestamp` if there is one, fixing up the sums on the path to it.
+    fn replace(&mut self, timestamp: i32, price: i32) -> bool {
+        let Some(previous) = self.find(timestamp) else {
+            return false;
+        };
+        let change = i64::from(price) - i64::from(previous);
+        let mut node = self.root;
+
+        loop {
+            let current = &mut self.nodes[node];
+            current.sum += change;
+
+            node = match timestamp.cmp(&current.timestamp) {
+                Ordering::Less => current.left,
+                Ordering::Greater => current.right,
+                Ordering::Equal => {
+                    current.price = price;
+                    return true;
+                }
+            };
+        }
+    }
+
+    fn find(&self, timestamp: i32) -> Option<i32> {
+        let mut node = self.root;
+
+        while let Some(current) = self.nodes.get(node) {
+            node = match timestamp.cmp(&current.timestamp) {
+                Ordering::Less => current.left,
+                Ordering::Greater => current.right,
+                Ordering::Equal => return Some(current.price),
+            };
+        }
+
+        None
+    }
+
+    /// The sum and count of prices at timestamps below `bound`, or up to it if `inclusive`.
+    fn prefix(&self, bound: i32, inclusive: bool) -> (i64, u64) {
+        let (mut sum, mut count) = (0, 0);
+        let mut node = self.root;
+
+        while node != NIL {
+            let current = &self.nodes[node];
+
+            if current.timestamp < bound || (inclusive && current.timestamp == bound) {
+                let (left_sum, left_count) = self.totals(current.left);
+                sum += left_sum + i64::from(current.price);
+                count += left_count + 1;
+                node = current.right;
+            } else {
+                node = current.left;
+            }
+        }
+
+        (sum, count)
+    }
+
+    /// Splits the subtree at `node` into timestamps below and above `timestamp`, which isn't
+    /// in it.
+    fn split(&mut self, node: usize, timestamp: i32) -> (usize, usize) {
+        if node == NIL {
+            return (NIL, NIL);
+        }
+
+        if self.nodes[node].timestamp < timestamp {
+            let (below, above) = self.split(self.nodes[node].right, timestamp);
+            self.nodes[node].right = below;
+            self.update(node);
+            (node, above)
+        } else {
+            let (below, above) = self.split(self.nodes[node].left, timestamp);
+            self.nodes[node].left = above;
+            self.update(node);
+            (below, node)
+        }
+    }
+
+    fn update(&mut self, node: usize) {
+        let (left_sum, left_count) = self.totals(self.nodes[node].left);
+        let (right_sum, right_count) = self.totals(self.nodes[node].right);
+        let current = &mut self.nodes[node];
+
+        current.sum = left_sum + right_sum + i64::from(current.price);
+        current.count = left_count + right_count + 1;
+    }
+
+    fn totals(&self, node: usize) -> (i64, u64) {
+        match self.nodes.get(node) {
+            Some(node) => (node.sum, node.count),
+            None => (0, 0),
+        }
+    }
+}
+
+impl Default for PriceIndex {
+    fn default() -> PriceIndex {
+        PriceIndex::new()
+    }
+}
@@ -1,12 +1,125 @@
-use std::collections::HashMap;
-use std::io::{prelude::*, BufReader, ErrorKind, Result};
+pub mod history;
+pub mod index;
+
+use history::{History, MAX_NAME_LENGTH};
+use index::PriceIndex;
+use shared::{
+    codec::{self, Decode, Encode},
+    config::{Config, Flag, DEFAULT_IDLE_TIMEOUT},
+    server::{Context, Server, Stream},
+};
+use std::{
+    io::{prelude::*, BufReader, Error, ErrorKind, Result},
+    path::PathBuf,
+};
+
+/// Flags for keeping prices of named assets, read by `Options::from_config`.
+pub const FLAGS: [Flag; 1] = [Flag {
+    name: "history",
+    value: "<path>",
+    env: "PROTOHACKERS_HISTORY",
+    help: "Append-only file of prices for sessions that name an asset, replayed on startup",
+}];
+
+/// Where to keep prices for named assets, beyond the listener settings in `Config`. Without
+/// a history file every session's prices are its own, as the protocol specifies.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Options {
+    pub history: Option<PathBuf>,
+}
+
+impl Options {
+    /// Reads the values given for `FLAGS` in `config`.
+    pub fn from_config(config: &Config) -> Options {
+        Options {
+            history: config
+                .flag("history")
+                .filter(|path| !path.is_empty())
+                .map(PathBuf::from),
+        }
+    }
+}
+
+/// Defaults for `run`, before flags and environment variables are applied.
+pub fn default_config() -> Config {
+    Config {
+        idle_timeout: Some(DEFAULT_IDLE_TIMEOUT),
+        ..Config::new(5).with_flags(&FLAGS)
+    }
+}
+
+/// Serves the protocol as configured by `config` until the process is asked to shut down.
+pub fn run(config: &Config) -> Result<()> {
+    serve(config, Options::from_config(config))
+}
+
+/// Like `run`, but with a history file if `options` has one. A session that sends `N` with
+/// the length of an asset name in `a`, followed by the name, then inserts into and queries
+/// that asset's history, shared with every other session naming it and kept across restarts.
+pub fn serve(config: &Config, options: Options) -> Result<()> {
+    let history = options.history.map(History::open).transpose()?;
+    let server = Server::builder().config(config).build()?;
+
+    server.run(move |stream, _: Context| handle_connection(stream, history.as_ref()))
+}
+
+fn handle_connection(stream: Stream, history: Option<&History>) -> Result<()> {
+    let mut asset = None;
+    let answered = answer_messages(&stream, history, &mut asset);
+
+    // However the session ends, the prices it added to the history reach the disk.
+    match (history, asset) {
+        (Some(history), Some(_)) => answered.and(history.sync()),
+        _ => answered,
+    }
+}
+
+/// Answers the session's messages, setting `asset` once it names one.
+fn answer_messages(
+    stream: &Stream,
+    history: Option<&History>,
+    asset: &mut Option<String>,
+) -> Result<()> {
+    let reader = BufReader::new(stream);
+    let mut writer = stream;
+    let mut messages = consume_messages(reader);
+    let mut prices = PriceIndex::new();
+
+    while let Some(message) = messages.next() {
+        let message = message?;
+
+        match (message.message_type, history, &*asset) {
+            (b'I', Some(history), Some(asset)) => {
+                history.insert(asset, message.a, message.b)?;
+            }
+            (b'I', ..) => {
+                prices.insert(message.a, message.b);
+            }
+            (b'Q', Some(history), Some(asset)) => {
+                let mean = history.mean(asset, message.a, message.b);
+                writer.write_all(&mean.to_be_bytes())?;
+            }
+            (b'Q', ..) => {
+                let mean = prices.mean(message.a, message.b);
+                writer.write_all(&mean.to_be_bytes())?;
+            }
+            (b'N', Some(_), _) => {
+                *asset = Some(messages.read_name(message.a)?);
+            }
+            _ => break,
+        }
+    }
+
+    Ok(())
+}
 
 pub struct MessageIterator<R> {
     reader: BufReader<R>,
 }
 
+#[derive(Clone, Debug, PartialEq, Encode, Decode)]
 pub struct Message {
-    pub message_type: char,
+    pub message_type: u8,
     pub a: i32,
     pub b: i32,
 }
@@ -18,39 +131,34 @@ impl<R: Read> Iterator for MessageIterator<R> {
         let mut buffer = [0; 9];
 
         match self.reader.read_exact(&mut buffer) {
-            Ok(()) => {
-                let message = Message {
-                    message_type: buffer[0] as char,
-                    a: i32::from_be_bytes(buffer[1..5].try_into().unwrap()),
-                    b: i32::from_be_bytes(buffer[5..].try_into().unwrap()),
-                };
-
-                Some(Ok(message))
-            }
+            Ok(()) => Some(codec::from_bytes(&buffer)),
             Err(e) if e.kind() == ErrorKind::UnexpectedEof => None,
             Err(e) => Some(Err(e)),
         }
     }
 }
 
-pub fn consume_messages<R>(reader: BufReader<R>) -> MessageIterator<R> {
-    MessageIterator { reader }
-}
+impl<R: Read> MessageIterator<R> {
+    /// Reads the `length` bytes of asset name that follow an `N` message.
+    pub fn read_name(&mut self, length: i32) -> Result<String> {
+        let length = usize::try_from(length)
+            .ok()
+            .filter(|length| (1..=MAX_NAME_LENGTH).contains(length))
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Asset names must be 1 to {MAX_NAME_LENGTH} bytes"),
+                )
+            })?;
 
-pub fn find_mean_price(min: i32, max: i32, prices: &HashMap<i32, i32>) -> i32 {
-    let (sum, count) = prices
-        .iter()
-        .fold((0i64, 0), |(acc_sum, acc_count), (timestamp, price)| {
-            if *timestamp >= min && *timestamp <= max {
-                (acc_sum + i64::from(*price), acc_count + 1)
-            } else {
-                (acc_sum, acc_count)
-            }
-        });
+        let mut name = vec![0; length];
+        self.reader.read_exact(&mut name)?;
 
-    if count == 0 {
-        return 0;
+        String::from_utf8(name)
+            .map_err(|_| Error::new(ErrorKind::InvalidData, "Asset name is not UTF-8"))
     }
+}
 
-    (sum / count).try_into().unwrap_or_default()
+pub fn consume_messages<R>(reader: BufReader<R>) -> MessageIterator<R> {
+    MessageIterator { reader }
 }
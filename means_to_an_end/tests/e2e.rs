@@ -0,0 +1,174 @@
+use means_to_an_end::Options;
+use shared::testing::{self, TcpClient, Transport};
+use std::{env, fs, net::SocketAddr, path::PathBuf, process};
+
+fn server() -> SocketAddr {
+    testing::spawn(
+        Transport::Tcp,
+        means_to_an_end::default_config(),
+        means_to_an_end::run,
+    )
+}
+
+fn server_with_history(history: PathBuf) -> SocketAddr {
+    let options = Options {
+        history: Some(history),
+    };
+
+    testing::spawn(
+        Transport::Tcp,
+        means_to_an_end::default_config(),
+        move |config| means_to_an_end::serve(config, options),
+    )
+}
+
+/// A fresh history file for the test called `name`.
+fn history_file(name: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("means-e2e-{}-{name}", process::id()));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+fn name(asset: &str) -> Vec<u8> {
+    let mut bytes = message(b'N', asset.len() as i32, 0);
+    bytes.extend(asset.as_bytes());
+    bytes
+}
+
+fn message(kind: u8, a: i32, b: i32) -> Vec<u8> {
+    let mut bytes = vec![kind];
+    bytes.extend(a.to_be_bytes());
+    bytes.extend(b.to_be_bytes());
+    bytes
+}
+
+#[test]
+fn answers_the_example_session() {
+    let mut client = TcpClient::connect(server());
+
+    client.send(message(b'I', 12345, 101));
+    client.send(message(b'I', 12346, 102));
+    client.send(message(b'I', 12347, 100));
+    client.send(message(b'I', 40960, 5));
+    client.send(message(b'Q', 12288, 16384));
+
+    client.expect(101i32.to_be_bytes());
+}
+
+#[test]
+fn answers_zero_for_empty_or_inverted_ranges() {
+    let mut client = TcpClient::connect(server());
+
+    client.send(message(b'Q', 0, 100));
+    client.expect(0i32.to_be_bytes());
+
+    client.send(message(b'I', 50, 10));
+    client.send(message(b'Q', 100, 0));
+    client.expect(0i32.to_be_bytes());
+}
+
+#[test]
+fn averages_without_overflowing() {
+    let mut client = TcpClient::connect(server());
+
+    client.send(message(b'I', 1, i32::MAX));
+    client.send(message(b'I', 2, i32::MAX));
+    client.send(message(b'Q', i32::MIN, i32::MAX));
+
+    client.expect(i32::MAX.to_be_bytes());
+}
+
+#[test]
+fn keeps_sessions_separate() {
+    let address = server();
+    let mut first = TcpClient::connect(address);
+    let mut second = TcpClient::connect(address);
+
+    first.send(message(b'I', 1, 10));
+    second.send(message(b'I', 1, 20));
+
+    first.send(message(b'Q', 0, 2));
+    second.send(message(b'Q', 0, 2));
+
+    first.expect(10i32.to_be_bytes());
+    second.expect(20i32.to_be_bytes());
+}
+
+#[test]
+fn reassembles_messages_split_across_packets() {
+    let mut client = TcpClient::connect(server());
+    let insert = message(b'I', 7, 42);
+
+    client.send(&insert[..3]);
+    client.send(&insert[3..]);
+    client.send(message(b'Q', 7, 7));
+
+    client.expect(42i32.to_be_bytes());
+}
+
+#[test]
+fn shares_named_assets_across_sessions_and_restarts() {
+    let history = history_file("shared");
+    let address = server_with_history(history.clone());
+
+    let mut first = TcpClient::connect(address);
+    first.send(name("BTC"));
+    first.send(message(b'I', 1, 100));
+    first.send(message(b'I', 2, 200));
+    first.send(message(b'Q', 0, 10));
+    first.expect(150i32.to_be_bytes());
+    drop(first);
+
+    let mut second = TcpClient::connect(address);
+    second.send(name("BTC"));
+    second.send(message(b'I', 2, 300));
+    second.send(message(b'Q', 0, 10));
+    second.expect(200i32.to_be_bytes());
+
+    let mut other = TcpClient::connect(address);
+    other.send(name("ETH"));
+    other.send(message(b'Q', 0, 10));
+    other.expect(0i32.to_be_bytes());
+
+    let mut restarted = TcpClient::connect(server_with_history(history.clone()));
+    restarted.send(name("BTC"));
+    restarted.send(message(b'Q', 0, 10));
+    restarted.expect(200i32.to_be_bytes());
+
+    let _ = fs::remove_file(history);
+}
+
+#[test]
+fn keeps_unnamed_sessions_separate_with_a_history() {
+    let history = history_file("unnamed");
+    let address = server_with_history(history.clone());
+    let mut first = TcpClient::connect(address);
+    let mut second = TcpClient::connect(address);
+
+    first.send(message(b'I', 1, 10));
+    first.send(message(b'Q', 0, 2));
+    first.expect(10i32.to_be_bytes());
+    second.send(message(b'Q', 0, 2));
+    second.expect(0i32.to_be_bytes());
+
+    let _ = fs::remove_file(history);
+}
+
+#[test]
+fn refuses_names_without_a_history() {
+    let mut client = TcpClient::connect(server());
+
+    client.send(name("BTC"));
+    client.expect_closed();
+}
+
+#[test]
+fn refuses_empty_names() {
+    let history = history_file("empty");
+    let mut client = TcpClient::connect(server_with_history(history.clone()));
+
+    client.send(message(b'N', 0, 0));
+    client.expect_closed();
+
+    let _ = fs::remove_file(history);
+}
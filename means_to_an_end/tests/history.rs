@@ -0,0 +1,102 @@
+use means_to_an_end::{history::History, Options};
+use std::{
+    env, fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process,
+};
+
+/// A history file unique to this test process, removed when dropped.
+struct TempFile(PathBuf);
+
+impl TempFile {
+    fn new(name: &str) -> TempFile {
+        let path = env::temp_dir().join(format!("means-history-{}-{name}", process::id()));
+        let _ = fs::remove_file(&path);
+        TempFile(path)
+    }
+
+    fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+#[test]
+fn replays_prices_after_reopening() {
+    let file = TempFile::new("replay");
+
+    let history = History::open(file.path()).unwrap();
+    history.insert("BTC", 1, 100).unwrap();
+    history.insert("BTC", 2, 200).unwrap();
+    history.insert("ETH", 1, 7).unwrap();
+    history.insert("BTC", 1, 300).unwrap();
+    drop(history);
+
+    let history = History::open(file.path()).unwrap();
+
+    assert_eq!(history.mean("BTC", 1, 2), 250);
+    assert_eq!(history.mean("ETH", 0, 10), 7);
+    assert_eq!(history.mean("DOGE", 0, 10), 0);
+
+    history.insert("ETH", 2, 9).unwrap();
+    drop(history);
+
+    assert_eq!(History::open(file.path()).unwrap().mean("ETH", 0, 10), 8);
+}
+
+#[test]
+fn drops_a_record_cut_short() {
+    let file = TempFile::new("torn");
+
+    let history = History::open(file.path()).unwrap();
+    history.insert("BTC", 1, 100).unwrap();
+    history.sync().unwrap();
+    drop(history);
+
+    let complete = fs::metadata(file.path()).unwrap().len();
+    fs::OpenOptions::new()
+        .append(true)
+        .open(file.path())
+        .unwrap()
+        .write_all(&[3, b'B', b'T', b'C', 0, 0])
+        .unwrap();
+
+    let history = History::open(file.path()).unwrap();
+
+    assert_eq!(fs::metadata(file.path()).unwrap().len(), complete);
+
+    history.insert("BTC", 2, 200).unwrap();
+    drop(history);
+
+    assert_eq!(History::open(file.path()).unwrap().mean("BTC", 0, 10), 150);
+}
+
+#[test]
+fn refuses_names_that_do_not_fit() {
+    let file = TempFile::new("names");
+    let history = History::open(file.path()).unwrap();
+
+    assert!(history.insert("", 1, 1).is_err());
+    assert!(history.insert(&"x".repeat(256), 1, 1).is_err());
+    assert!(history.insert(&"x".repeat(255), 1, 1).is_ok());
+}
+
+#[test]
+fn takes_the_history_flag_over_the_environment() {
+    let args = ["--port", "5", "--history=prices.log"].map(String::from);
+    let env = |key: &str| (key == "PROTOHACKERS_HISTORY").then(|| "other.log".to_string());
+
+    let config = means_to_an_end::default_config().apply(env, args).unwrap();
+
+    assert_eq!(
+        Options::from_config(&config).history,
+        Some(PathBuf::from("prices.log"))
+    );
+    assert_eq!(config.port, 5);
+}
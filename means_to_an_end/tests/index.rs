@@ -0,0 +1,102 @@
+use means_to_an_end::index::PriceIndex;
+use std::collections::HashMap;
+
+/// The mean over every price, the way the server used to answer queries.
+fn scan(prices: &HashMap<i32, i32>, min: i32, max: i32) -> i32 {
+    let (sum, count) = prices
+        .iter()
+        .filter(|(&timestamp, _)| (min..=max).contains(&timestamp))
+        .fold((0i64, 0i64), |(sum, count), (_, &price)| {
+            (sum + i64::from(price), count + 1)
+        });
+
+    if count == 0 {
+        0
+    } else {
+        (sum / count) as i32
+    }
+}
+
+#[test]
+fn answers_zero_when_empty_or_inverted() {
+    let mut index = PriceIndex::new();
+
+    assert!(index.is_empty());
+    assert_eq!(index.mean(i32::MIN, i32::MAX), 0);
+
+    index.insert(50, 10);
+
+    assert_eq!(index.mean(100, 0), 0);
+    assert_eq!(index.mean(51, 100), 0);
+    assert_eq!(index.mean(50, 50), 10);
+}
+
+#[test]
+fn includes_both_ends_of_the_range() {
+    let mut index = PriceIndex::new();
+
+    for (timestamp, price) in [(12345, 101), (12346, 102), (12347, 100), (40960, 5)] {
+        index.insert(timestamp, price);
+    }
+
+    assert_eq!(index.mean(12288, 16384), 101);
+    assert_eq!(index.mean(12346, 40960), 69);
+    assert_eq!(index.mean(12347, 12347), 100);
+    assert_eq!(index.len(), 4);
+}
+
+#[test]
+fn replaces_prices_at_duplicate_timestamps() {
+    let mut index = PriceIndex::new();
+
+    index.insert(1, 10);
+    index.insert(2, 20);
+    index.insert(1, 30);
+
+    assert_eq!(index.len(), 2);
+    assert_eq!(index.mean(1, 1), 30);
+    assert_eq!(index.mean(1, 2), 25);
+}
+
+#[test]
+fn rounds_towards_zero_without_overflowing() {
+    let mut index = PriceIndex::new();
+
+    index.insert(i32::MIN, i32::MAX);
+    index.insert(0, i32::MAX);
+    index.insert(i32::MAX, i32::MAX - 1);
+
+    assert_eq!(index.mean(i32::MIN, i32::MAX), i32::MAX - 1);
+
+    index.insert(1, -3);
+    index.insert(2, -4);
+
+    assert_eq!(index.mean(1, 2), -3);
+}
+
+#[test]
+fn agrees_with_a_scan_of_every_price() {
+    let mut index = PriceIndex::new();
+    let mut prices = HashMap::new();
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+
+    for _ in 0..5_000 {
+        // Narrow timestamps so that some repeat, and wide prices so that sums get large.
+        let timestamp = (next() % 2_000) as i32 - 1_000;
+        let price = next() as i32;
+        index.insert(timestamp, price);
+        prices.insert(timestamp, price);
+
+        let a = (next() % 2_200) as i32 - 1_100;
+        let b = (next() % 2_200) as i32 - 1_100;
+        assert_eq!(index.mean(a, b), scan(&prices, a, b), "mean({a}, {b})");
+    }
+
+    assert_eq!(index.len(), prices.len());
+}
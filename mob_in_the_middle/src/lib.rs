@@ -1,4 +1,88 @@
 use fancy_regex::Regex;
+use shared::{
+    async_server::{AsyncServer, Context, Stream},
+    config::Config,
+    line::{self, AsyncLineReader},
+    warn,
+};
+use std::io::Result;
+use tokio::{
+    io::{self, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader},
+    net::TcpStream,
+};
+
+/// The chat server whose clients are intercepted.
+pub const UPSTREAM: &str = "chat.protohackers.com:16963";
+
+/// Defaults for `run`, before flags and environment variables are applied.
+pub fn default_config() -> Config {
+    Config::new(2)
+}
+
+/// Serves the protocol as configured by `config` until the process is asked to shut down.
+pub fn run(config: &Config) -> Result<()> {
+    serve(config, UPSTREAM)
+}
+
+/// Like `run`, but proxies to the chat server at `upstream`.
+pub fn serve(config: &Config, upstream: &str) -> Result<()> {
+    let server = AsyncServer::builder().config(config).build()?;
+    let upstream = upstream.to_string();
+    let max_line_length = config.max_line_length;
+
+    server
+        .run(move |stream, _: Context| handle_connection(stream, upstream.clone(), max_line_length))
+}
+
+async fn handle_connection(
+    client_stream: Stream,
+    upstream: String,
+    max_line_length: usize,
+) -> Result<()> {
+    let mut proxy_stream = TcpStream::connect(upstream).await?;
+
+    let (client_reader, client_writer) = io::split(client_stream);
+    let (proxy_reader, proxy_writer) = proxy_stream.split();
+
+    tokio::select! {
+        result = relay(client_reader, proxy_writer, max_line_length) => result,
+        result = relay(proxy_reader, client_writer, max_line_length) => result,
+    }
+}
+
+/// Copies complete lines from `reader` to `writer`, rewriting addresses, until either side closes
+/// or sends a line longer than `max_line_length`.
+async fn relay<R, W>(reader: R, mut writer: W, max_line_length: usize) -> Result<()>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut reader = AsyncLineReader::new(BufReader::new(reader), max_line_length);
+    let mut message = String::new();
+
+    loop {
+        match reader.read_line(&mut message).await {
+            Ok(0) => break,
+            Ok(_) => {}
+            Err(e) if line::is_too_long(&e) => {
+                warn!("Oversized line: {}", e);
+                break;
+            }
+            Err(e) => return Err(e),
+        }
+
+        if !message.ends_with('\n') {
+            break;
+        }
+
+        writer
+            .write_all(replace_addresses(message.to_string()).as_bytes())
+            .await?;
+        message.clear();
+    }
+
+    Ok(())
+}
 
 pub fn replace_addresses(line: String) -> String {
     let boguscoin_regex = Regex::new(r"(?<=^|\s)(7[a-zA-Z0-9]{25,34})(?=$|\s)").unwrap();
@@ -0,0 +1,92 @@
+use shared::{
+    config::Config,
+    testing::{self, TcpClient, Transport},
+};
+use std::net::SocketAddr;
+
+const TONY: &str = "7YWHMfk9JZe0LM0g1ZauHuiSxhI";
+
+/// Starts a proxy in front of a local `budget_chat` server.
+fn server() -> SocketAddr {
+    server_with(mob_in_the_middle::default_config())
+}
+
+fn server_with(config: Config) -> SocketAddr {
+    let upstream = testing::spawn(
+        Transport::Tcp,
+        budget_chat::default_config(),
+        budget_chat::run,
+    );
+
+    testing::spawn(Transport::Tcp, config, move |config| {
+        mob_in_the_middle::serve(config, &upstream.to_string())
+    })
+}
+
+fn join(address: SocketAddr, name: &str) -> TcpClient {
+    let mut client = TcpClient::connect(address);
+    client.expect_line("Enter name:");
+    client.send_line(name);
+    client.read_line();
+    client
+}
+
+#[test]
+fn rewrites_addresses_in_both_directions() {
+    let address = server();
+
+    let mut alice = join(address, "alice");
+    let mut bob = join(address, "bob");
+    alice.expect_line("* bob has entered the room");
+
+    alice.send_line("Send refunds to 7iKDZEwPZSqIvDnHvVN2r0hUWXD5rHX please");
+    bob.expect_line(&format!("[alice] Send refunds to {TONY} please"));
+
+    bob.send_line("7LOrwbDlS8NujgjddyogWgIM93MV5N2VR 7adNeSwJkMakpEcln9HEtthSRtxdmEHOT8T");
+    alice.expect_line(&format!("[bob] {TONY} {TONY}"));
+}
+
+#[test]
+fn leaves_lookalikes_alone() {
+    let address = server();
+
+    let mut alice = join(address, "alice");
+    let mut bob = join(address, "bob");
+    alice.expect_line("* bob has entered the room");
+
+    for line in [
+        "This is too short: 7F1u3wSD5RbOHQmupo9nx4Tnh",
+        "This is too long: 7iKDZEwPZSqIvDnHvVN2r0hUWXD5rHX123456789",
+        "This is a product ID, not a Boguscoin: 7YWHMfk9JZe0LM0g1ZauHuiSxhI-iHjfgeLdKJgdsVOt",
+        "Not a boguscoin at all",
+    ] {
+        alice.send_line(line);
+        bob.expect_line(&format!("[alice] {line}"));
+    }
+}
+
+#[test]
+fn disconnects_the_client_when_upstream_leaves() {
+    let address = server();
+
+    let mut client = TcpClient::connect(address);
+    client.expect_line("Enter name:");
+    client.send_line("not valid!");
+    client.expect_closed();
+}
+
+#[test]
+fn disconnects_clients_sending_oversized_lines() {
+    let address = server_with(Config {
+        max_line_length: 64,
+        ..mob_in_the_middle::default_config()
+    });
+
+    let mut alice = join(address, "alice");
+    let mut bob = join(address, "bob");
+    alice.expect_line("* bob has entered the room");
+
+    bob.send_line(&"7YWHMfk9JZe0LM0g1ZauHuiSxhI ".repeat(3));
+    bob.expect_closed();
+    alice.expect_line("* bob has left the room");
+}
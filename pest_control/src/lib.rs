@@ -0,0 +1,145 @@
+mod helpers;
+pub mod models;
+pub mod read;
+mod site;
+mod write;
+
+use helpers::is_valid_visit;
+use models::{Hello, Message, PestControlError, SiteVisit};
+use read::consume_messages;
+use shared::{
+    config::Config,
+    error,
+    pool::ThreadPool,
+    server::{Context, Server, Stream},
+};
+use site::Site;
+use std::{
+    collections::HashMap,
+    io::{Error, ErrorKind, Result},
+    sync::mpsc::{self},
+    time::Duration,
+};
+use write::write_message;
+
+/// The authority server that sites' target populations and policies are managed with.
+pub const AUTHORITY: &str = "pestcontrol.protohackers.com:20547";
+
+/// Defaults for `run`, before flags and environment variables are applied.
+pub fn default_config() -> Config {
+    Config {
+        read_timeout: Some(Duration::from_secs(20)),
+        ..Config::new(100)
+    }
+}
+
+/// Serves the protocol as configured by `config` until the process is asked to shut down.
+pub fn run(config: &Config) -> Result<()> {
+    serve(config, AUTHORITY)
+}
+
+/// Like `run`, but dials site authorities at `authority`.
+pub fn serve(config: &Config, authority: &str) -> Result<()> {
+    let server = Server::builder().config(config).build()?;
+
+    let (client_sender, client_receiver) = mpsc::channel();
+
+    let authority = authority.to_string();
+
+    server.pool().execute(move |_| {
+        if let Err(e) = control(client_receiver, authority) {
+            error!("Orchestration error: {}", e);
+        }
+    });
+
+    server.run(move |stream, _: Context| handle_client(client_sender.clone(), stream))
+}
+
+fn control(client_receiver: mpsc::Receiver<SiteVisit>, authority: String) -> Result<()> {
+    let pool = ThreadPool::new(100);
+    let mut sites = HashMap::new();
+
+    while let Ok(visit) = client_receiver.recv() {
+        let site = visit.site;
+        let sender = sites
+            .entry(site)
+            .or_insert_with(|| start_site(&pool, &authority, site));
+
+        // A site whose thread has failed no longer receives, so start it again for this visit.
+        if let Err(mpsc::SendError(visit)) = sender.send(visit) {
+            let sender = start_site(&pool, &authority, site);
+            sender.send(visit).ok();
+            sites.insert(site, sender);
+        }
+    }
+
+    Ok(())
+}
+
+/// Connects `site` to its authority on `pool`, returning where to send its visits.
+fn start_site(pool: &ThreadPool, authority: &str, site: u32) -> mpsc::Sender<SiteVisit> {
+    let (site_sender, site_receiver) = mpsc::channel();
+    let authority = authority.to_string();
+
+    pool.execute(move |_| match Site::new(&authority, site, site_receiver) {
+        Ok(mut site) => {
+            if let Err(e) = site.poll() {
+                error!("Error polling site: {}", e);
+            }
+        }
+        Err(e) => {
+            error!("Error creating site: {}", e);
+        }
+    });
+
+    site_sender
+}
+
+fn handle_client(client_sender: mpsc::Sender<SiteVisit>, stream: Stream) -> Result<()> {
+    let mut writer = &stream;
+    let mut messages = consume_messages(&stream);
+
+    write_message(
+        &mut writer,
+        Message::Hello(Hello {
+            protocol: "pestcontrol".to_string(),
+            version: 1,
+        }),
+    )?;
+
+    let valid_hello = matches!(
+        messages.next(),
+        Some(Ok(Message::Hello(message)))
+            if message.protocol == "pestcontrol" && message.version == 1
+    );
+
+    if !valid_hello {
+        write_message(
+            &mut writer,
+            Message::PestControlError(PestControlError {
+                message: "Invalid hello".to_string(),
+            }),
+        )?;
+
+        return Err(Error::from(ErrorKind::InvalidData));
+    }
+
+    while let Some(Ok(Message::SiteVisit(message))) = messages.next() {
+        if !is_valid_visit(message.clone()) {
+            break;
+        }
+
+        client_sender
+            .send(message)
+            .map_err(|_| Error::new(ErrorKind::BrokenPipe, "Site control has stopped"))?;
+    }
+
+    write_message(
+        &mut writer,
+        Message::PestControlError(PestControlError {
+            message: "Invalid message".to_string(),
+        }),
+    )?;
+
+    Ok(())
+}
@@ -1,62 +1,69 @@
-use std::io::BufReader;
+use shared::codec::{Decode, Encode, FrameDecoder};
 
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug, Encode, Decode)]
 pub struct Hello {
+    #[codec(len = u32)]
     pub protocol: String,
     pub version: u32,
 }
 
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug, Encode, Decode)]
 pub struct PestControlError {
+    #[codec(len = u32)]
     pub message: String,
 }
 
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug, Encode, Decode)]
 pub struct Okay {}
 
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug, Encode, Decode)]
 pub struct DialAuthority {
     pub site: u32,
 }
 
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug, Encode, Decode)]
 pub struct TargetPopulation {
+    #[codec(len = u32)]
     pub species: String,
     pub min: u32,
     pub max: u32,
 }
 
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug, Encode, Decode)]
 pub struct TargetPopulations {
     pub site: u32,
+    #[codec(len = u32)]
     pub populations: Vec<TargetPopulation>,
 }
 
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug, Encode, Decode)]
 pub struct CreatePolicy {
+    #[codec(len = u32)]
     pub species: String,
     pub action: u8,
 }
 
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug, Encode, Decode)]
 pub struct DeletePolicy {
     pub policy: u32,
 }
 
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug, Encode, Decode)]
 pub struct PolicyResult {
     pub policy: u32,
 }
 
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug, Encode, Decode)]
 pub struct VisitPopulation {
+    #[codec(len = u32)]
     pub species: String,
     pub count: u32,
 }
 
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug, Encode, Decode)]
 pub struct SiteVisit {
     pub site: u32,
+    #[codec(len = u32)]
     pub populations: Vec<VisitPopulation>,
 }
 
@@ -74,5 +81,6 @@ pub enum Message {
 }
 
 pub struct MessageIterator<R> {
-    pub reader: BufReader<R>,
+    pub reader: R,
+    pub decoder: FrameDecoder<Message>,
 }
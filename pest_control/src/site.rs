@@ -1,17 +1,19 @@
 use std::{
     collections::HashMap,
-    io::{self, BufReader, Error, ErrorKind},
+    io::{self, Error, ErrorKind},
     net::TcpStream,
     sync::mpsc,
 };
 
 use crate::{
     models::{
-        CreatePolicy, DeletePolicy, DialAuthority, Hello, Message, SiteVisit, VisitPopulation,
+        CreatePolicy, DeletePolicy, DialAuthority, Hello, Message, MessageIterator, SiteVisit,
+        VisitPopulation,
     },
     read::consume_messages,
     write::write_message,
 };
+use shared::metrics;
 use PolicyAction::*;
 
 #[derive(Copy, Clone, Debug, PartialEq)]
@@ -36,14 +38,19 @@ pub struct Target {
 pub struct Site {
     pub receiver: mpsc::Receiver<SiteVisit>,
     pub stream: TcpStream,
+    pub messages: MessageIterator<TcpStream>,
     pub targets: HashMap<String, Target>,
     pub policies: HashMap<String, Policy>,
 }
 
 impl Site {
-    pub fn new(site_id: u32, receiver: mpsc::Receiver<SiteVisit>) -> io::Result<Site> {
-        let stream = TcpStream::connect("pestcontrol.protohackers.com:20547")?;
-        let mut reader = consume_messages(BufReader::new(&stream));
+    pub fn new(
+        authority: &str,
+        site_id: u32,
+        receiver: mpsc::Receiver<SiteVisit>,
+    ) -> io::Result<Site> {
+        let stream = TcpStream::connect(authority)?;
+        let mut reader = consume_messages(stream.try_clone()?);
         let mut writer = &stream;
 
         write_message(
@@ -74,6 +81,7 @@ impl Site {
                 let mut site = Site {
                     receiver,
                     stream,
+                    messages: reader,
                     targets: HashMap::new(),
                     policies: HashMap::new(),
                 };
@@ -107,7 +115,7 @@ impl Site {
             }),
         )?;
 
-        match consume_messages(BufReader::new(&self.stream)).next() {
+        match self.messages.next() {
             Some(Ok(Message::PolicyResult(message))) => {
                 self.policies.insert(
                     species.clone(),
@@ -117,6 +125,13 @@ impl Site {
                     },
                 );
 
+                metrics::counter(
+                    "pest_control_policies_created_total",
+                    "Policies created at site authorities.",
+                    &[],
+                )
+                .inc();
+
                 Ok(())
             }
             Some(Ok(Message::PestControlError(e))) => {
@@ -135,9 +150,17 @@ impl Site {
             }),
         )?;
 
-        match consume_messages(BufReader::new(&self.stream)).next() {
+        match self.messages.next() {
             Some(Ok(Message::Okay(_))) => {
                 self.policies.remove(&species);
+
+                metrics::counter(
+                    "pest_control_policies_deleted_total",
+                    "Policies deleted at site authorities.",
+                    &[],
+                )
+                .inc();
+
                 Ok(())
             }
             Some(Ok(Message::PestControlError(e))) => {
@@ -176,7 +199,10 @@ impl Site {
                     (_, Some(policy)) => {
                         if policy.action != recommended_policy {
                             self.delete_policy(species.clone())?;
-                            self.create_policy(species.clone(), recommended_policy)?;
+
+                            if recommended_policy != DoNothing {
+                                self.create_policy(species.clone(), recommended_policy)?;
+                            }
                         }
                     }
                 }
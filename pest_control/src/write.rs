@@ -1,76 +1,30 @@
-use std::io::{Error, ErrorKind, Write};
+use std::io::{Error, ErrorKind, Result, Write};
 
-use crate::{helpers::calculate_checksum, models::Message};
-
-fn write_byte(bytes_to_write: &mut Vec<u8>, character: u8) {
-    bytes_to_write.push(character);
-}
+use shared::codec::Encode;
 
-fn write_string(bytes_to_write: &mut Vec<u8>, string: String) {
-    bytes_to_write.extend_from_slice(&(string.clone().len() as u32).to_be_bytes());
-    bytes_to_write.extend_from_slice(&string.into_bytes());
-}
-
-fn write_u32(bytes_to_write: &mut Vec<u8>, number: u32) {
-    bytes_to_write.extend_from_slice(&number.to_be_bytes());
-}
+use crate::{helpers::calculate_checksum, models::Message};
 
-fn write_length(bytes_to_write: &mut Vec<u8>) {
-    let message_length = (bytes_to_write.len() + 5) as u32;
+/// Wraps `body` with its message type, length and checksum.
+fn frame(message_type: u8, body: &impl Encode) -> Result<Vec<u8>> {
+    let mut bytes = vec![message_type, 0, 0, 0, 0];
+    body.encode(&mut bytes)?;
 
-    bytes_to_write.splice(1..1, message_length.to_be_bytes());
-}
+    let message_length = (bytes.len() + 1) as u32;
+    bytes[1..5].copy_from_slice(&message_length.to_be_bytes());
+    bytes.push(calculate_checksum(&bytes));
 
-fn write_checksum(bytes_to_write: &mut Vec<u8>) {
-    bytes_to_write.push(calculate_checksum(bytes_to_write));
+    Ok(bytes)
 }
 
-pub fn write_message(writer: &mut impl Write, message: Message) -> std::io::Result<()> {
-    let mut bytes_to_write = Vec::new();
-
-    match message {
-        Message::Hello(message) => {
-            write_byte(&mut bytes_to_write, 0x50);
-            write_string(&mut bytes_to_write, message.protocol);
-            write_u32(&mut bytes_to_write, message.version);
-            write_length(&mut bytes_to_write);
-            write_checksum(&mut bytes_to_write);
-
-            writer.write_all(&bytes_to_write)
-        }
-        Message::PestControlError(message) => {
-            write_byte(&mut bytes_to_write, 0x51);
-            write_string(&mut bytes_to_write, message.message);
-            write_length(&mut bytes_to_write);
-            write_checksum(&mut bytes_to_write);
-
-            writer.write_all(&bytes_to_write)
-        }
-        Message::DialAuthority(message) => {
-            write_byte(&mut bytes_to_write, 0x53);
-            write_u32(&mut bytes_to_write, message.site);
-            write_length(&mut bytes_to_write);
-            write_checksum(&mut bytes_to_write);
-
-            writer.write_all(&bytes_to_write)
-        }
-        Message::CreatePolicy(message) => {
-            write_byte(&mut bytes_to_write, 0x55);
-            write_string(&mut bytes_to_write, message.species);
-            write_byte(&mut bytes_to_write, message.action);
-            write_length(&mut bytes_to_write);
-            write_checksum(&mut bytes_to_write);
-
-            writer.write_all(&bytes_to_write)
-        }
-        Message::DeletePolicy(message) => {
-            write_byte(&mut bytes_to_write, 0x56);
-            write_u32(&mut bytes_to_write, message.policy);
-            write_length(&mut bytes_to_write);
-            write_checksum(&mut bytes_to_write);
-
-            writer.write_all(&bytes_to_write)
-        }
-        _ => Err(Error::from(ErrorKind::InvalidInput)),
-    }
+pub fn write_message(writer: &mut impl Write, message: Message) -> Result<()> {
+    let bytes = match message {
+        Message::Hello(message) => frame(0x50, &message)?,
+        Message::PestControlError(message) => frame(0x51, &message)?,
+        Message::DialAuthority(message) => frame(0x53, &message)?,
+        Message::CreatePolicy(message) => frame(0x55, &message)?,
+        Message::DeletePolicy(message) => frame(0x56, &message)?,
+        _ => return Err(Error::from(ErrorKind::InvalidInput)),
+    };
+
+    writer.write_all(&bytes)
 }
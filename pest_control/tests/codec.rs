@@ -0,0 +1,83 @@
+use pest_control::models::{
+    CreatePolicy, DeletePolicy, DialAuthority, Hello, PestControlError, PolicyResult, SiteVisit,
+    TargetPopulation, TargetPopulations, VisitPopulation,
+};
+use proptest::{collection::vec, prelude::*};
+use shared::codec::{self, Decode, Encode};
+use std::fmt::Debug;
+
+fn round_trip<T: Encode + Decode + PartialEq + Debug>(value: T) -> Result<(), TestCaseError> {
+    let bytes = codec::to_bytes(&value).unwrap();
+    prop_assert_eq!(codec::from_bytes::<T>(&bytes).unwrap(), value);
+
+    Ok(())
+}
+
+proptest! {
+    #[test]
+    fn round_trips_hellos(protocol in ".*", version: u32) {
+        round_trip(Hello { protocol, version })?;
+    }
+
+    #[test]
+    fn round_trips_errors(message in ".*") {
+        round_trip(PestControlError { message })?;
+    }
+
+    #[test]
+    fn round_trips_policy_messages(site: u32, policy: u32, species in ".*", action: u8) {
+        round_trip(DialAuthority { site })?;
+        round_trip(CreatePolicy { species, action })?;
+        round_trip(DeletePolicy { policy })?;
+        round_trip(PolicyResult { policy })?;
+    }
+
+    #[test]
+    fn round_trips_target_populations(
+        site: u32,
+        populations in vec((".*", any::<u32>(), any::<u32>()), 0..20),
+    ) {
+        let populations = populations
+            .into_iter()
+            .map(|(species, min, max)| TargetPopulation { species, min, max })
+            .collect();
+
+        round_trip(TargetPopulations { site, populations })?;
+    }
+
+    #[test]
+    fn round_trips_site_visits(site: u32, populations in vec((".*", any::<u32>()), 0..20)) {
+        let populations = populations
+            .into_iter()
+            .map(|(species, count)| VisitPopulation { species, count })
+            .collect();
+
+        round_trip(SiteVisit { site, populations })?;
+    }
+}
+
+#[test]
+fn decodes_the_example_site_visit() {
+    let visit: SiteVisit = codec::from_bytes(&[
+        0x00, 0x00, 0x30, 0x39, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x03, 0x64, 0x6f, 0x67,
+        0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x03, 0x72, 0x61, 0x74, 0x00, 0x00, 0x00, 0x05,
+    ])
+    .unwrap();
+
+    assert_eq!(
+        visit,
+        SiteVisit {
+            site: 12345,
+            populations: vec![
+                VisitPopulation {
+                    species: "dog".to_string(),
+                    count: 1
+                },
+                VisitPopulation {
+                    species: "rat".to_string(),
+                    count: 5
+                },
+            ],
+        }
+    );
+}
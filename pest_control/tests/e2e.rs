@@ -0,0 +1,282 @@
+use shared::testing::{self, TcpClient, Transport, TIMEOUT};
+use std::{
+    io::{prelude::*, Result},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+};
+
+const CULL: u8 = 0x90;
+const CONSERVE: u8 = 0xa0;
+
+/// A policy change made at the fake authority.
+#[derive(Debug, PartialEq)]
+enum Event {
+    Created(String, u8),
+    Deleted(u32),
+}
+
+fn string(string: &str) -> Vec<u8> {
+    let mut bytes = (string.len() as u32).to_be_bytes().to_vec();
+    bytes.extend_from_slice(string.as_bytes());
+    bytes
+}
+
+/// Wraps `body` with its message type, length and checksum.
+fn frame(kind: u8, body: &[u8]) -> Vec<u8> {
+    let mut bytes = vec![kind];
+    bytes.extend_from_slice(&(body.len() as u32 + 6).to_be_bytes());
+    bytes.extend_from_slice(body);
+
+    let sum = bytes.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+    bytes.push(0u8.wrapping_sub(sum));
+    bytes
+}
+
+fn hello() -> Vec<u8> {
+    let mut body = string("pestcontrol");
+    body.extend_from_slice(&1u32.to_be_bytes());
+    frame(0x50, &body)
+}
+
+fn error(message: &str) -> Vec<u8> {
+    frame(0x51, &string(message))
+}
+
+fn site_visit(site: u32, populations: &[(&str, u32)]) -> Vec<u8> {
+    let mut body = site.to_be_bytes().to_vec();
+    body.extend_from_slice(&(populations.len() as u32).to_be_bytes());
+
+    for (species, count) in populations {
+        body.extend(string(species));
+        body.extend_from_slice(&count.to_be_bytes());
+    }
+
+    frame(0x58, &body)
+}
+
+/// Reads one message, returning its type and body.
+fn read_message(stream: &mut TcpStream) -> Result<(u8, Vec<u8>)> {
+    let mut header = [0; 5];
+    stream.read_exact(&mut header)?;
+
+    let len = u32::from_be_bytes(header[1..].try_into().unwrap()) as usize;
+    let mut body = vec![0; len - 5];
+    stream.read_exact(&mut body)?;
+    body.pop();
+
+    Ok((header[0], body))
+}
+
+/// Starts a fake authority that gives every site `targets` and reports each policy change.
+fn authority(targets: &'static [(&'static str, u32, u32)]) -> (SocketAddr, Receiver<Event>) {
+    failing_authority(0, targets)
+}
+
+/// Like `authority`, but closes the first `failures` connections straight away.
+fn failing_authority(
+    failures: usize,
+    targets: &'static [(&'static str, u32, u32)],
+) -> (SocketAddr, Receiver<Event>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten().skip(failures) {
+            let sender = sender.clone();
+            thread::spawn(move || serve_site(stream, targets, sender));
+        }
+    });
+
+    (address, receiver)
+}
+
+fn serve_site(
+    mut stream: TcpStream,
+    targets: &[(&str, u32, u32)],
+    events: Sender<Event>,
+) -> Result<()> {
+    stream.write_all(&hello())?;
+    assert_eq!(read_message(&mut stream)?.0, 0x50);
+
+    let (kind, site) = read_message(&mut stream)?;
+    assert_eq!(kind, 0x53);
+
+    let mut body = site;
+    body.extend_from_slice(&(targets.len() as u32).to_be_bytes());
+    for (species, min, max) in targets {
+        body.extend(string(species));
+        body.extend_from_slice(&min.to_be_bytes());
+        body.extend_from_slice(&max.to_be_bytes());
+    }
+    stream.write_all(&frame(0x54, &body))?;
+
+    let mut next_policy = 1u32;
+
+    loop {
+        match read_message(&mut stream)? {
+            (0x55, body) => {
+                let species = String::from_utf8(body[4..body.len() - 1].to_vec()).unwrap();
+                events
+                    .send(Event::Created(species, body[body.len() - 1]))
+                    .unwrap();
+
+                stream.write_all(&frame(0x57, &next_policy.to_be_bytes()))?;
+                next_policy += 1;
+            }
+            (0x56, body) => {
+                let policy = u32::from_be_bytes(body[..].try_into().unwrap());
+                events.send(Event::Deleted(policy)).unwrap();
+
+                stream.write_all(&frame(0x52, &[]))?;
+            }
+            (kind, _) => panic!("Unexpected message type {kind:#x} at authority"),
+        }
+    }
+}
+
+fn server(authority: SocketAddr) -> SocketAddr {
+    testing::spawn(
+        Transport::Tcp,
+        pest_control::default_config(),
+        move |config| pest_control::serve(config, &authority.to_string()),
+    )
+}
+
+fn connect(address: SocketAddr) -> TcpClient {
+    let mut client = TcpClient::connect(address);
+    client.expect(hello());
+    client.send(hello());
+    client
+}
+
+#[test]
+fn rejects_invalid_hello() {
+    let (authority, _) = authority(&[]);
+    let mut client = TcpClient::connect(server(authority));
+
+    client.expect(hello());
+    client.send(site_visit(1, &[]));
+    client.expect(error("Invalid hello"));
+    client.expect_closed();
+}
+
+#[test]
+fn rejects_bad_checksums() {
+    let (authority, _) = authority(&[]);
+    let mut client = connect(server(authority));
+
+    let mut message = site_visit(1, &[("dog", 1)]);
+    *message.last_mut().unwrap() ^= 0xff;
+    client.send(message);
+
+    client.expect(error("Invalid message"));
+}
+
+#[test]
+fn rejects_oversized_messages() {
+    let (authority, _) = authority(&[]);
+    let mut client = connect(server(authority));
+
+    client.send([0x58, 0xff, 0xff, 0xff, 0xff]);
+
+    client.expect(error("Invalid message"));
+}
+
+#[test]
+fn creates_policies_for_populations_out_of_range() {
+    let (authority, events) = authority(&[("dog", 1, 3), ("cat", 0, 10), ("rat", 5, 6)]);
+    let mut client = connect(server(authority));
+
+    client.send(site_visit(12345, &[("dog", 10), ("cat", 5)]));
+
+    let mut created = vec![
+        events.recv_timeout(TIMEOUT).unwrap(),
+        events.recv_timeout(TIMEOUT).unwrap(),
+    ];
+    created.sort_by_key(|event| format!("{event:?}"));
+
+    assert_eq!(
+        created,
+        [
+            Event::Created("dog".to_string(), CULL),
+            Event::Created("rat".to_string(), CONSERVE),
+        ]
+    );
+}
+
+#[test]
+fn reassembles_messages_split_across_pauses() {
+    let (authority, events) = authority(&[("dog", 1, 3)]);
+    let mut client = connect(server(authority));
+
+    for byte in site_visit(1, &[("dog", 10)]) {
+        client.send([byte]);
+        thread::sleep(TIMEOUT / 200);
+    }
+
+    assert_eq!(
+        events.recv_timeout(TIMEOUT).unwrap(),
+        Event::Created("dog".to_string(), CULL)
+    );
+}
+
+#[test]
+fn replaces_policies_when_populations_change() {
+    let (authority, events) = authority(&[("dog", 1, 3)]);
+    let mut client = connect(server(authority));
+
+    client.send(site_visit(1, &[("dog", 10)]));
+    assert_eq!(
+        events.recv_timeout(TIMEOUT).unwrap(),
+        Event::Created("dog".to_string(), CULL)
+    );
+
+    client.send(site_visit(1, &[("dog", 10)]));
+    client.send(site_visit(1, &[]));
+    assert_eq!(events.recv_timeout(TIMEOUT).unwrap(), Event::Deleted(1));
+    assert_eq!(
+        events.recv_timeout(TIMEOUT).unwrap(),
+        Event::Created("dog".to_string(), CONSERVE)
+    );
+
+    client.send(site_visit(1, &[("dog", 2)]));
+    assert_eq!(events.recv_timeout(TIMEOUT).unwrap(), Event::Deleted(2));
+}
+
+#[test]
+fn shares_sites_between_clients() {
+    let (authority, events) = authority(&[("dog", 1, 3)]);
+    let address = server(authority);
+
+    connect(address).send(site_visit(7, &[("dog", 0)]));
+    assert_eq!(
+        events.recv_timeout(TIMEOUT).unwrap(),
+        Event::Created("dog".to_string(), CONSERVE)
+    );
+
+    connect(address).send(site_visit(7, &[("dog", 0)]));
+    assert!(events.recv_timeout(TIMEOUT / 10).is_err());
+}
+
+#[test]
+fn recovers_from_failed_sites() {
+    let (authority, events) = failing_authority(1, &[("dog", 1, 3)]);
+    let address = server(authority);
+    let mut client = connect(address);
+
+    // The site fails on its first connection to the authority, so keep visiting until one
+    // gets through on a fresh connection.
+    let created = (0..50).find_map(|_| {
+        client.send(site_visit(1, &[("dog", 10)]));
+        events.recv_timeout(TIMEOUT / 50).ok()
+    });
+    assert_eq!(created, Some(Event::Created("dog".to_string(), CULL)));
+
+    connect(address).send(site_visit(2, &[("dog", 0)]));
+    assert_eq!(
+        events.recv_timeout(TIMEOUT).unwrap(),
+        Event::Created("dog".to_string(), CONSERVE)
+    );
+}
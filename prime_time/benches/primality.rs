@@ -0,0 +1,63 @@
+//! Compares the primality checks against the trial division `prime_time` used to do, which
+//! took seconds for primes near `i64::MAX`. Run with `cargo bench -p prime_time`.
+
+use prime_time::primality;
+use shared::bench;
+use std::{hint::black_box, time::Duration};
+
+/// The largest primes below 2^32, 2^50 and 2^63.
+const PRIMES: [(&str, u64); 3] = [
+    ("2^32 - 5", 4_294_967_291),
+    ("2^50 - 27", 1_125_899_906_842_597),
+    ("2^63 - 25", 9_223_372_036_854_775_783),
+];
+
+/// 2^127 - 1.
+const BIG_PRIME: &str = "170141183460469231731687303715884105727";
+
+/// The check `prime_time` made before `primality`, for comparison.
+fn trial_division(n: i64) -> bool {
+    if n <= 1 {
+        return false;
+    }
+
+    let mut range = 2..=((n as f64).sqrt() as i64);
+
+    range.all(|d| n % d != 0)
+}
+
+fn main() {
+    let budget = Duration::from_millis(500);
+    let small: Vec<u64> = (0..100_000).collect();
+
+    bench::run("trial division, 0..100000", budget, || {
+        small.iter().filter(|&&n| trial_division(n as i64)).count()
+    });
+    bench::run("is_prime_batch, 0..100000", budget, || {
+        primality::is_prime_batch(black_box(&small))
+    });
+
+    for (name, prime) in PRIMES {
+        assert!(primality::is_prime_u64(prime));
+
+        // Trial division near 2^63 runs for seconds, so it gets a single run.
+        bench::run(&format!("trial division, {name}"), budget, || {
+            trial_division(black_box(prime as i64))
+        });
+        bench::run(&format!("is_prime_u64, {name}"), budget, || {
+            primality::is_prime_u64(black_box(prime))
+        });
+    }
+
+    bench::run("is_prime_number, 2^127 - 1, cached", budget, || {
+        primality::is_prime_number(black_box(BIG_PRIME))
+    });
+    bench::run("is_prime_number, 2^127 - 1 + 2k, uncached", budget, {
+        let mut k = 0u64;
+        move || {
+            k += 2;
+            let text = format!("170141183460469231731687303715{:09}", 884_105_727 + k);
+            primality::is_prime_number(&text)
+        }
+    });
+}
@@ -0,0 +1,263 @@
+use std::cmp::Ordering;
+
+/// Just enough of an arbitrary-precision unsigned integer for Miller-Rabin: parsing, `mul`,
+/// `rem` and `pow_mod`. Limbs are little-endian with no trailing zeros, so zero has none.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct BigUint {
+    limbs: Vec<u32>,
+}
+
+impl BigUint {
+    /// Parses a string of ASCII digits, or returns `None` if it contains anything else.
+    pub fn from_decimal(digits: &str) -> Option<BigUint> {
+        let mut number = BigUint::from(0);
+
+        for chunk in digits.as_bytes().chunks(9) {
+            let mut value = 0;
+
+            for &digit in chunk {
+                if !digit.is_ascii_digit() {
+                    return None;
+                }
+                value = value * 10 + u32::from(digit - b'0');
+            }
+
+            number.mul_add_small(10u32.pow(chunk.len() as u32), value);
+        }
+
+        Some(number)
+    }
+
+    pub fn bits(&self) -> u64 {
+        match self.limbs.last() {
+            Some(top) => self.limbs.len() as u64 * 32 - u64::from(top.leading_zeros()),
+            None => 0,
+        }
+    }
+
+    pub fn bit(&self, index: u64) -> bool {
+        let limb = self.limbs.get((index / 32) as usize).copied().unwrap_or(0);
+        limb >> (index % 32) & 1 == 1
+    }
+
+    /// The number of trailing zero bits, or 0 for zero.
+    pub fn trailing_zeros(&self) -> u64 {
+        match self.limbs.iter().position(|&limb| limb != 0) {
+            Some(index) => index as u64 * 32 + u64::from(self.limbs[index].trailing_zeros()),
+            None => 0,
+        }
+    }
+
+    pub fn shr(&self, bits: u64) -> BigUint {
+        let (limbs, bits) = ((bits / 32) as usize, (bits % 32) as u32);
+        let rest = self.limbs.get(limbs..).unwrap_or(&[]);
+
+        let shifted = (0..rest.len())
+            .map(|i| {
+                let high = rest.get(i + 1).copied().unwrap_or(0);
+                match bits {
+                    0 => rest[i],
+                    _ => rest[i] >> bits | high << (32 - bits),
+                }
+            })
+            .collect();
+
+        BigUint::normalized(shifted)
+    }
+
+    /// `self - 1`; zero stays zero.
+    pub fn decrement(&self) -> BigUint {
+        let mut limbs = self.limbs.clone();
+
+        for limb in &mut limbs {
+            let (value, borrow) = limb.overflowing_sub(1);
+            *limb = value;
+
+            if !borrow {
+                break;
+            }
+        }
+
+        BigUint::normalized(limbs)
+    }
+
+    pub fn rem_small(&self, divisor: u32) -> u32 {
+        self.limbs.iter().rev().fold(0, |remainder, &limb| {
+            ((u64::from(remainder) << 32 | u64::from(limb)) % u64::from(divisor)) as u32
+        })
+    }
+
+    pub fn mul(&self, other: &BigUint) -> BigUint {
+        let mut product = vec![0u32; self.limbs.len() + other.limbs.len()];
+
+        for (i, &a) in self.limbs.iter().enumerate() {
+            let mut carry = 0u64;
+
+            for (j, &b) in other.limbs.iter().enumerate() {
+                let sum = u64::from(a) * u64::from(b) + u64::from(product[i + j]) + carry;
+                product[i + j] = sum as u32;
+                carry = sum >> 32;
+            }
+
+            product[i + other.limbs.len()] = carry as u32;
+        }
+
+        BigUint::normalized(product)
+    }
+
+    /// `self % modulus` by Knuth's Algorithm D. Panics if `modulus` is zero.
+    pub fn rem(&self, modulus: &BigUint) -> BigUint {
+        if self < modulus {
+            return self.clone();
+        }
+
+        let n = modulus.limbs.len();
+
+        if n == 1 {
+            return BigUint::from(u64::from(self.rem_small(modulus.limbs[0])));
+        }
+
+        // Shift both so the divisor's top bit is set, which keeps each quotient digit estimate
+        // within two of the truth.
+        let shift = modulus.limbs[n - 1].leading_zeros();
+        let divisor = shl(&modulus.limbs, shift);
+        let mut remainder = shl(&self.limbs, shift);
+        remainder.push(0);
+
+        let (top, next) = (u64::from(divisor[n - 1]), u64::from(divisor[n - 2]));
+
+        for j in (0..remainder.len() - n).rev() {
+            let numerator = u64::from(remainder[j + n]) << 32 | u64::from(remainder[j + n - 1]);
+            let mut quotient = numerator / top;
+            let mut rest = numerator % top;
+
+            while quotient >> 32 != 0
+                || quotient * next > (rest << 32 | u64::from(remainder[j + n - 2]))
+            {
+                quotient -= 1;
+                rest += top;
+
+                if rest >> 32 != 0 {
+                    break;
+                }
+            }
+
+            let mut borrow = 0i64;
+            let mut carry = 0u64;
+
+            for i in 0..n {
+                let product = quotient * u64::from(divisor[i]) + carry;
+                carry = product >> 32;
+
+                let difference =
+                    i64::from(remainder[i + j]) - borrow - (product & 0xffff_ffff) as i64;
+                remainder[i + j] = difference as u32;
+                borrow = i64::from(difference < 0);
+            }
+
+            let difference = i64::from(remainder[j + n]) - borrow - carry as i64;
+            remainder[j + n] = difference as u32;
+
+            // The estimate was one too high: add the divisor back.
+            if difference < 0 {
+                let mut carry = 0u64;
+
+                for i in 0..n {
+                    let sum = u64::from(remainder[i + j]) + u64::from(divisor[i]) + carry;
+                    remainder[i + j] = sum as u32;
+                    carry = sum >> 32;
+                }
+
+                remainder[j + n] = remainder[j + n].wrapping_add(carry as u32);
+            }
+        }
+
+        remainder.truncate(n);
+
+        BigUint::normalized(remainder).shr(u64::from(shift))
+    }
+
+    /// `self.pow(exponent) % modulus`.
+    pub fn pow_mod(&self, exponent: &BigUint, modulus: &BigUint) -> BigUint {
+        let base = self.rem(modulus);
+        let mut result = BigUint::from(1).rem(modulus);
+
+        for index in (0..exponent.bits()).rev() {
+            result = result.mul(&result).rem(modulus);
+
+            if exponent.bit(index) {
+                result = result.mul(&base).rem(modulus);
+            }
+        }
+
+        result
+    }
+
+    fn mul_add_small(&mut self, factor: u32, addend: u32) {
+        let mut carry = u64::from(addend);
+
+        for limb in &mut self.limbs {
+            let value = u64::from(*limb) * u64::from(factor) + carry;
+            *limb = value as u32;
+            carry = value >> 32;
+        }
+
+        if carry != 0 {
+            self.limbs.push(carry as u32);
+        }
+    }
+
+    fn normalized(mut limbs: Vec<u32>) -> BigUint {
+        while limbs.last() == Some(&0) {
+            limbs.pop();
+        }
+
+        BigUint { limbs }
+    }
+}
+
+impl From<u64> for BigUint {
+    fn from(value: u64) -> BigUint {
+        BigUint::normalized(vec![value as u32, (value >> 32) as u32])
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &BigUint) -> Ordering {
+        self.limbs
+            .len()
+            .cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &BigUint) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// `limbs` shifted left by `bits` (less than 32), without dropping any overflow.
+fn shl(limbs: &[u32], bits: u32) -> Vec<u32> {
+    if bits == 0 {
+        return limbs.to_vec();
+    }
+
+    let mut shifted: Vec<u32> = (0..limbs.len())
+        .map(|i| {
+            let low = if i == 0 {
+                0
+            } else {
+                limbs[i - 1] >> (32 - bits)
+            };
+            limbs[i] << bits | low
+        })
+        .collect();
+
+    let overflow = limbs[limbs.len() - 1] >> (32 - bits);
+    if overflow != 0 {
+        shifted.push(overflow);
+    }
+
+    shifted
+}
@@ -1,15 +1,241 @@
+mod bigint;
+pub mod cache;
+pub mod methods;
+pub mod models;
+pub mod primality;
+pub mod rpc;
+mod sieve;
+
+use models::{Answer, Request, RpcError, RpcResponse, Version};
+use rpc::Registry;
+use shared::{
+    config::{Config, DEFAULT_IDLE_TIMEOUT},
+    line::{self, LineReader},
+    pool::{Overflow, ThreadPool},
+    server::{Context, Server, Stream},
+    warn,
+};
+use std::{
+    collections::BTreeMap,
+    io::{prelude::*, BufReader, Result},
+    net::Shutdown,
+    num::NonZero,
+    sync::{mpsc, Arc},
+    thread,
+};
+
+/// Requests from one connection that may be evaluating or waiting to be written at once.
+/// Reading stops while this many are outstanding. Each can hold a compute thread for up to a
+/// second, so this is also the most of the compute pool one client can take.
+const MAX_IN_FLIGHT: usize = 4;
+
+/// What to send back for one request: a line, or nothing and disconnect.
+type Outcome = Option<String>;
+
+/// Defaults for `run`, before flags and environment variables are applied.
+pub fn default_config() -> Config {
+    Config {
+        idle_timeout: Some(DEFAULT_IDLE_TIMEOUT),
+        ..Config::new(5)
+    }
+}
+
+/// Serves the protocol as configured by `config` until the process is asked to shut down.
+pub fn run(config: &Config) -> Result<()> {
+    serve(config, Registry::standard())
+}
+
+/// Like `run`, but answering the methods in `registry`.
+pub fn serve(config: &Config, registry: Registry) -> Result<()> {
+    let server = Server::builder()
+        .config(config)
+        .queue(16, Overflow::Spawn { max: 20 })
+        .build()?;
+
+    let max_line_length = config.max_line_length;
+    let registry = Arc::new(registry);
+    let cores = thread::available_parallelism().map_or(1, NonZero::get);
+    let compute = Arc::new(ThreadPool::new(cores));
+
+    server.run(move |stream, _: Context| {
+        handle_connection(stream, max_line_length, &registry, &compute)
+    })
+}
+
+/// Reads requests and evaluates them concurrently on `compute`, while a second thread writes
+/// the responses back in the order the requests arrived.
+fn handle_connection(
+    stream: Stream,
+    max_line_length: usize,
+    registry: &Arc<Registry>,
+    compute: &ThreadPool,
+) -> Result<()> {
+    let (outcomes, outcomes_receiver) = mpsc::channel();
+    let (slots, slots_receiver) = mpsc::sync_channel(MAX_IN_FLIGHT);
+
+    thread::scope(|scope| {
+        let writer = scope.spawn(|| write_responses(&stream, outcomes_receiver, slots_receiver));
+
+        let read = read_requests(&stream, max_line_length, |id, line| {
+            // Blocks while `MAX_IN_FLIGHT` requests are outstanding; fails once the writer
+            // has stopped.
+            slots.send(()).ok()?;
+
+            let registry = Arc::clone(registry);
+            let reply = Reply {
+                id,
+                outcomes: Some(outcomes.clone()),
+            };
+
+            compute.execute(move |_| reply.send(respond(&registry, &line)));
+
+            Some(())
+        });
+
+        drop(outcomes);
+
+        let written = writer.join().unwrap();
+        read.and(written)
+    })
+}
+
+/// Where a request's outcome goes. Dropping it unsent, as a request whose evaluation panics
+/// does, disconnects the client rather than leaving the writer waiting on that request.
+struct Reply {
+    id: usize,
+    outcomes: Option<mpsc::Sender<(usize, Outcome)>>,
+}
+
+impl Reply {
+    fn send(mut self, outcome: Outcome) {
+        if let Some(outcomes) = self.outcomes.take() {
+            outcomes.send((self.id, outcome)).ok();
+        }
+    }
+}
+
+impl Drop for Reply {
+    fn drop(&mut self) {
+        if let Some(outcomes) = self.outcomes.take() {
+            outcomes.send((self.id, None)).ok();
+        }
+    }
+}
+
+/// Passes each request line to `submit` with its position on the connection, until the
+/// client stops sending or `submit` returns `None`.
+fn read_requests<F>(stream: &Stream, max_line_length: usize, mut submit: F) -> Result<()>
+where
+    F: FnMut(usize, String) -> Option<()>,
+{
+    let reader = LineReader::new(BufReader::new(stream), max_line_length);
+
+    for (id, line) in reader.lines().enumerate() {
+        let line = match line {
+            Ok(line) => line,
+            Err(error) if line::is_too_long(&error) => {
+                warn!("Oversized request: {}", error);
+                break;
+            }
+            Err(error) => return Err(error),
+        };
+
+        if submit(id, line).is_none() {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes outcomes in request order as they complete, freeing a slot for each. Closes the
+/// connection, which also stops the reader, at the first request that calls for it.
+fn write_responses(
+    stream: &Stream,
+    outcomes: mpsc::Receiver<(usize, Outcome)>,
+    slots: mpsc::Receiver<()>,
+) -> Result<()> {
+    let mut writer = stream;
+    let mut waiting = BTreeMap::new();
+    let mut next = 0;
+
+    for (id, outcome) in outcomes {
+        waiting.insert(id, outcome);
+
+        while let Some(outcome) = waiting.remove(&next) {
+            next += 1;
+            slots.recv().ok();
+
+            match outcome {
+                Some(response) => {
+                    if let Err(error) = writeln!(writer, "{}", response) {
+                        stream.shutdown(Shutdown::Both).ok();
+                        return Err(error);
+                    }
+                }
+                None => return stream.shutdown(Shutdown::Both),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Evaluates one request line, in the style the client asked for: the response line, or
+/// `None` if the client should be disconnected.
+pub fn respond(registry: &Registry, line: &str) -> Option<String> {
+    let response = match serde_json::from_str::<Request>(line) {
+        Ok(request) if request.jsonrpc.is_some() => {
+            serde_json::to_string(&versioned(registry, request))
+        }
+        Ok(request) => match registry.call(&request.method, request.number.get()) {
+            Ok((key, result)) => serde_json::to_string(&Answer {
+                method: &request.method,
+                key,
+                result: &result,
+            }),
+            Err(error) => {
+                warn!("Failed {} request: {}", request.method, error.message);
+                return None;
+            }
+        },
+        Err(error) => match serde_json::from_str::<Version>(line) {
+            Ok(Version {
+                jsonrpc: Some(_),
+                id,
+            }) => {
+                let error = RpcError::invalid_request(error.to_string());
+                serde_json::to_string(&RpcResponse::new(id, Err(error)))
+            }
+            _ => {
+                warn!("Malformed request: {}", error);
+                return None;
+            }
+        },
+    };
+
+    response.ok()
+}
+
+/// Answers a client that opted in to JSON-RPC 2.0, which gets errors rather than being
+/// disconnected.
+fn versioned(registry: &Registry, request: Request) -> RpcResponse {
+    let outcome = match request.jsonrpc.as_deref() {
+        Some("2.0") => registry
+            .call(&request.method, request.number.get())
+            .map(|(_, result)| result),
+        _ => Err(RpcError::invalid_request("Unsupported jsonrpc version")),
+    };
+
+    RpcResponse::new(request.id, outcome)
+}
+
 pub trait PrimeCheck {
     fn is_prime(&self) -> bool;
 }
 
 impl PrimeCheck for i64 {
     fn is_prime(&self) -> bool {
-        if *self <= 1 {
-            return false;
-        }
-
-        let mut range = 2..=((*self as f64).sqrt() as i64);
-
-        range.all(|n| *self % n != 0)
+        u64::try_from(*self).is_ok_and(primality::is_prime_u64)
     }
 }
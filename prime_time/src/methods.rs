@@ -0,0 +1,139 @@
+use crate::{
+    primality::{self, mul_mod},
+    sieve::{self, sieve},
+};
+
+/// `prime_count` refuses larger arguments, which would take over a second to sieve.
+pub const PRIME_COUNT_LIMIT: u64 = 100_000_000;
+
+/// The largest prime that fits in a `u64`, so the largest `next_prime` argument is one less.
+pub const LARGEST_U64_PRIME: u64 = 18_446_744_073_709_551_557;
+
+/// Primes below this are divided out before Pollard's rho takes over.
+const TRIAL_DIVISION_LIMIT: u32 = 1000;
+
+/// Numbers sieved at a time by `prime_count`.
+const SEGMENT_LENGTH: u64 = 1 << 16;
+
+/// The prime factors of `n`, smallest first and repeated by multiplicity. Empty for 0 and 1.
+pub fn factorize(mut n: u64) -> Vec<u64> {
+    let mut factors = Vec::new();
+
+    if n == 0 {
+        return factors;
+    }
+
+    for prime in sieve().primes_below(TRIAL_DIVISION_LIMIT).map(u64::from) {
+        while n % prime == 0 {
+            factors.push(prime);
+            n /= prime;
+        }
+    }
+
+    let mut unfactored = vec![n];
+
+    while let Some(n) = unfactored.pop() {
+        if n == 1 {
+            continue;
+        }
+
+        if primality::is_prime_u64(n) {
+            factors.push(n);
+        } else {
+            let divisor = pollard_rho(n);
+            unfactored.extend([divisor, n / divisor]);
+        }
+    }
+
+    factors.sort_unstable();
+    factors
+}
+
+/// The smallest prime greater than `n`, if it fits in a `u64`.
+pub fn next_prime(n: u64) -> Option<u64> {
+    (n.checked_add(1)?..=u64::MAX).find(|&candidate| primality::is_prime_u64(candidate))
+}
+
+/// The number of primes up to and including `n`, by a segmented sieve. `n` must be at most
+/// `PRIME_COUNT_LIMIT`, which keeps the sieving primes within the shared sieve.
+pub fn prime_count(n: u64) -> u64 {
+    assert!(n <= PRIME_COUNT_LIMIT);
+
+    let limit = u64::from(sieve::LIMIT);
+
+    if n < limit {
+        return sieve().primes_below(n as u32 + 1).count() as u64;
+    }
+
+    let primes: Vec<u64> = sieve().primes_below(sieve::LIMIT).map(u64::from).collect();
+    let mut count = primes.len() as u64;
+    let mut segment = vec![true; SEGMENT_LENGTH as usize];
+    let mut low = limit;
+
+    while low <= n {
+        let high = (low + SEGMENT_LENGTH - 1).min(n);
+        segment.fill(true);
+
+        for &prime in primes.iter().take_while(|&&prime| prime * prime <= high) {
+            let first = (low.div_ceil(prime) * prime).max(prime * prime);
+
+            for multiple in (first..=high).step_by(prime as usize) {
+                segment[(multiple - low) as usize] = false;
+            }
+        }
+
+        count += segment[..=(high - low) as usize]
+            .iter()
+            .filter(|&&prime| prime)
+            .count() as u64;
+        low = high + 1;
+    }
+
+    count
+}
+
+/// Whether `n` is the sum of its proper divisors.
+pub fn is_perfect(n: u64) -> bool {
+    if n < 2 {
+        return false;
+    }
+
+    let factors = factorize(n);
+    let mut divisor_sum = 1u128;
+
+    for run in factors.chunk_by(|a, b| a == b) {
+        let prime = u128::from(run[0]);
+        divisor_sum *= (prime.pow(run.len() as u32 + 1) - 1) / (prime - 1);
+    }
+
+    divisor_sum == 2 * u128::from(n)
+}
+
+/// A non-trivial divisor of `n`, which must be composite and have no factor below
+/// `TRIAL_DIVISION_LIMIT`, by Pollard's rho with Floyd's cycle finding.
+fn pollard_rho(n: u64) -> u64 {
+    for increment in 1.. {
+        let step = |x: u64| ((u128::from(mul_mod(x, x, n)) + increment) % u128::from(n)) as u64;
+        let (mut tortoise, mut hare, mut divisor) = (2, 2, 1);
+
+        while divisor == 1 {
+            tortoise = step(tortoise);
+            hare = step(step(hare));
+            divisor = gcd(tortoise.abs_diff(hare), n);
+        }
+
+        if divisor != n {
+            return divisor;
+        }
+    }
+
+    unreachable!()
+}
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+
+    a
+}
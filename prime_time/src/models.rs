@@ -1,14 +1,105 @@
-use serde::{Deserialize, Serialize};
-use serde_json::Number;
+use serde::{ser::SerializeMap, Deserialize, Serialize, Serializer};
+use serde_json::{value::RawValue, Value};
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 pub struct Request {
     pub method: String,
-    pub number: Number,
+    /// Kept as written, so that numbers of any size or precision can be tested exactly.
+    pub number: Box<RawValue>,
+    /// Set to `"2.0"` by clients that want JSON-RPC 2.0 style responses, including errors
+    /// in place of being disconnected.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jsonrpc: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<Value>,
 }
 
-#[derive(Serialize)]
+/// Just the version fields of a request, to tell whether a request that couldn't be parsed
+/// should get an error response.
+#[derive(Deserialize)]
+pub struct Version {
+    pub jsonrpc: Option<String>,
+    pub id: Option<Value>,
+}
+
+#[derive(Deserialize, Serialize)]
 pub struct Response {
     pub method: String,
     pub prime: bool,
 }
+
+/// The response to a request without a version: the method name, then its result under
+/// the method's key, e.g. `{"method":"isPrime","prime":true}`.
+pub struct Answer<'a> {
+    pub method: &'a str,
+    pub key: &'a str,
+    pub result: &'a Value,
+}
+
+impl Serialize for Answer<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(2))?;
+        map.serialize_entry("method", self.method)?;
+        map.serialize_entry(self.key, self.result)?;
+        map.end()
+    }
+}
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub struct RpcResponse {
+    pub jsonrpc: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+    pub id: Value,
+}
+
+impl RpcResponse {
+    pub fn new(id: Option<Value>, outcome: Result<Value, RpcError>) -> RpcResponse {
+        let (result, error) = match outcome {
+            Ok(result) => (Some(result), None),
+            Err(error) => (None, Some(error)),
+        };
+
+        RpcResponse {
+            jsonrpc: "2.0".to_string(),
+            result,
+            error,
+            id: id.unwrap_or(Value::Null),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    pub const INVALID_REQUEST: i64 = -32600;
+    pub const METHOD_NOT_FOUND: i64 = -32601;
+    pub const INVALID_PARAMS: i64 = -32602;
+
+    pub fn invalid_request(message: impl Into<String>) -> RpcError {
+        RpcError {
+            code: RpcError::INVALID_REQUEST,
+            message: message.into(),
+        }
+    }
+
+    pub fn method_not_found(method: &str) -> RpcError {
+        RpcError {
+            code: RpcError::METHOD_NOT_FOUND,
+            message: format!("Method not found: {method}"),
+        }
+    }
+
+    pub fn invalid_params(message: impl Into<String>) -> RpcError {
+        RpcError {
+            code: RpcError::INVALID_PARAMS,
+            message: message.into(),
+        }
+    }
+}
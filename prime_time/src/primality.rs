@@ -0,0 +1,271 @@
+use crate::{
+    bigint::BigUint,
+    cache::Lru,
+    sieve::{self, sieve},
+};
+use std::{
+    collections::hash_map::RandomState,
+    error,
+    fmt::{self, Display},
+    hash::{BuildHasher, Hasher},
+    sync::{Mutex, OnceLock},
+};
+
+/// Bases for which Miller-Rabin gives the right answer for every `u64`.
+const U64_BASES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+
+/// Fewer bases that suffice below `SMALL_BASES_LIMIT`, which covers every `u32`.
+const SMALL_BASES: [u64; 3] = [2, 7, 61];
+const SMALL_BASES_LIMIT: u64 = 4_759_123_141;
+
+/// Random Miller-Rabin rounds for integers wider than a `u64`. A composite passes each round
+/// with probability at most 1/4.
+const ROUNDS: usize = 16;
+
+/// Primes below this are tried as factors before anything more expensive.
+const TRIAL_DIVISION_LIMIT: u32 = 1000;
+
+/// Answers remembered for integers too large for the sieve, shared by every connection.
+const CACHE_CAPACITY: usize = 4096;
+
+/// Integers wider than this, with no small factor, are refused rather than tested, since a
+/// single Miller-Rabin round grows with the cube of the width.
+pub const MAX_TESTED_BITS: u64 = 4096;
+
+#[derive(Debug, PartialEq)]
+pub enum NumberError {
+    /// The value wasn't a JSON number.
+    NotANumber,
+    /// The value was an integer too large to work with, e.g. wider than `MAX_TESTED_BITS`.
+    TooLarge,
+}
+
+impl Display for NumberError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NumberError::NotANumber => write!(f, "Not a number"),
+            NumberError::TooLarge => write!(f, "Number too large"),
+        }
+    }
+}
+
+impl error::Error for NumberError {}
+
+/// Whether the JSON number written as `text` is a prime, working from the text so that
+/// integers of any size and integral floats like `7.0` or `7e0` are answered exactly.
+pub fn is_prime_number(text: &str) -> Result<bool, NumberError> {
+    let Some((negative, digits, exponent)) = parse(text.trim()) else {
+        return Err(NumberError::NotANumber);
+    };
+
+    // `digits` has no trailing zeros, so a negative exponent leaves a fraction and a positive
+    // one a multiple of ten.
+    if negative || digits.is_empty() || exponent != 0 {
+        return Ok(false);
+    }
+
+    let small = digits.parse::<u64>().ok();
+
+    if let Some(n) = small.filter(|&n| n < u64::from(sieve::LIMIT)) {
+        return Ok(is_prime_u64(n));
+    }
+
+    if let Some(prime) = cache().lock().unwrap().get(&digits) {
+        return Ok(prime);
+    }
+
+    let prime = match small {
+        Some(n) => is_prime_u64(n),
+        None => is_prime_big(&BigUint::from_decimal(&digits).ok_or(NumberError::NotANumber)?)?,
+    };
+
+    cache().lock().unwrap().insert(digits, prime);
+
+    Ok(prime)
+}
+
+/// The JSON number written as `text` as an `i128`, or `None` if it has a fractional part.
+pub(crate) fn integer(text: &str) -> Result<Option<i128>, NumberError> {
+    let Some((negative, digits, exponent)) = parse(text.trim()) else {
+        return Err(NumberError::NotANumber);
+    };
+
+    if digits.is_empty() {
+        return Ok(Some(0));
+    }
+    if exponent < 0 {
+        return Ok(None);
+    }
+
+    let magnitude = u32::try_from(exponent)
+        .ok()
+        .and_then(|exponent| 10i128.checked_pow(exponent))
+        .zip(digits.parse::<i128>().ok())
+        .and_then(|(scale, significand)| significand.checked_mul(scale))
+        .ok_or(NumberError::TooLarge)?;
+
+    Ok(Some(if negative { -magnitude } else { magnitude }))
+}
+
+/// A sieve lookup for small `n`, and deterministic Miller-Rabin beyond it.
+pub fn is_prime_u64(n: u64) -> bool {
+    if n < u64::from(sieve::LIMIT) {
+        return sieve().is_prime(n as u32);
+    }
+
+    if U64_BASES.iter().any(|&base| n % base == 0) {
+        return false;
+    }
+
+    let bases: &[u64] = if n < SMALL_BASES_LIMIT {
+        &SMALL_BASES
+    } else {
+        &U64_BASES
+    };
+    let zeros = (n - 1).trailing_zeros();
+    let d = (n - 1) >> zeros;
+
+    bases.iter().all(|&base| {
+        let mut x = pow_mod(base, d, n);
+
+        if x == 1 || x == n - 1 {
+            return true;
+        }
+
+        (1..zeros).any(|_| {
+            x = mul_mod(x, x, n);
+            x == n - 1
+        })
+    })
+}
+
+/// Whether each of `numbers` is prime, in the same order.
+pub fn is_prime_batch(numbers: &[u64]) -> Vec<bool> {
+    numbers.iter().map(|&n| is_prime_u64(n)).collect()
+}
+
+/// Trial division, then Miller-Rabin with random bases, for `n` wider than a `u64`.
+fn is_prime_big(n: &BigUint) -> Result<bool, NumberError> {
+    if sieve()
+        .primes_below(TRIAL_DIVISION_LIMIT)
+        .any(|prime| n.rem_small(prime) == 0)
+    {
+        return Ok(false);
+    }
+
+    if n.bits() > MAX_TESTED_BITS {
+        return Err(NumberError::TooLarge);
+    }
+
+    let n_minus_one = n.decrement();
+    let zeros = n_minus_one.trailing_zeros();
+    let d = n_minus_one.shr(zeros);
+    let one = BigUint::from(1);
+    let mut random = RandomState::new().build_hasher();
+
+    Ok((0..ROUNDS).all(|round| {
+        // Any base from 2 up is below `n - 1`, as `n` is wider than a `u64`.
+        random.write_usize(round);
+        let base = BigUint::from(random.finish().max(2));
+
+        let mut x = base.pow_mod(&d, n);
+
+        if x == one || x == n_minus_one {
+            return true;
+        }
+
+        (1..zeros).any(|_| {
+            x = x.mul(&x).rem(n);
+            x == n_minus_one
+        })
+    }))
+}
+
+/// Splits the JSON number `text` into its sign, its significant digits without leading or
+/// trailing zeros, and the power of ten they're scaled by. `None` if `text` isn't a number.
+fn parse(text: &str) -> Option<(bool, String, i64)> {
+    let (negative, text) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text),
+    };
+
+    let (mantissa, exponent) = match text.find(['e', 'E']) {
+        Some(index) => (&text[..index], parse_exponent(&text[index + 1..])?),
+        None => (text, 0),
+    };
+
+    let (integer, fraction) = match mantissa.split_once('.') {
+        Some((_, "")) => return None,
+        Some(parts) => parts,
+        None => (mantissa, ""),
+    };
+
+    let is_digits = |part: &str| part.bytes().all(|byte| byte.is_ascii_digit());
+
+    if integer.is_empty()
+        || !is_digits(integer)
+        || !is_digits(fraction)
+        || (integer.len() > 1 && integer.starts_with('0'))
+    {
+        return None;
+    }
+
+    let mut digits = format!("{integer}{fraction}")
+        .trim_start_matches('0')
+        .to_string();
+    let mut exponent = exponent - fraction.len() as i64;
+
+    while digits.ends_with('0') {
+        digits.pop();
+        exponent += 1;
+    }
+
+    Some((negative, digits, exponent))
+}
+
+/// The exponent after `e`, saturated so that absurd exponents can't overflow.
+fn parse_exponent(text: &str) -> Option<i64> {
+    let (negative, digits) = match text.as_bytes().first() {
+        Some(b'-') => (true, &text[1..]),
+        Some(b'+') => (false, &text[1..]),
+        _ => (false, text),
+    };
+
+    if digits.is_empty() || !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+
+    let magnitude = digits.parse::<i64>().unwrap_or(i64::MAX).min(i64::MAX / 4);
+
+    Some(if negative { -magnitude } else { magnitude })
+}
+
+fn cache() -> &'static Mutex<Lru<String, bool>> {
+    static CACHE: OnceLock<Mutex<Lru<String, bool>>> = OnceLock::new();
+
+    CACHE.get_or_init(|| Mutex::new(Lru::new(CACHE_CAPACITY)))
+}
+
+pub(crate) fn mul_mod(a: u64, b: u64, n: u64) -> u64 {
+    // Both are below `n`, so for a `u32` modulus the product fits without widening.
+    if n <= u64::from(u32::MAX) {
+        a * b % n
+    } else {
+        (u128::from(a) * u128::from(b) % u128::from(n)) as u64
+    }
+}
+
+fn pow_mod(mut base: u64, mut exponent: u64, n: u64) -> u64 {
+    let mut result = 1;
+    base %= n;
+
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = mul_mod(result, base, n);
+        }
+        base = mul_mod(base, base, n);
+        exponent >>= 1;
+    }
+
+    result
+}
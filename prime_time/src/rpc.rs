@@ -0,0 +1,122 @@
+use crate::{
+    methods::{self, LARGEST_U64_PRIME, PRIME_COUNT_LIMIT},
+    models::RpcError,
+    primality::{self, NumberError},
+};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// Computes a method's result from the request's number, as written in the request.
+pub type Handler = fn(&str) -> Result<Value, RpcError>;
+
+struct Method {
+    key: &'static str,
+    handler: Handler,
+}
+
+/// The methods a server answers, by name.
+#[derive(Default)]
+pub struct Registry {
+    methods: HashMap<String, Method>,
+}
+
+impl Registry {
+    pub fn new() -> Registry {
+        Registry::default()
+    }
+
+    /// `isPrime`, `factorize`, `nextPrime`, `primeCount` and `isPerfect`.
+    pub fn standard() -> Registry {
+        let mut registry = Registry::new();
+
+        registry.register("isPrime", "prime", is_prime);
+        registry.register("factorize", "factors", factorize);
+        registry.register("nextPrime", "next", next_prime);
+        registry.register("primeCount", "count", prime_count);
+        registry.register("isPerfect", "perfect", is_perfect);
+
+        registry
+    }
+
+    /// Adds or replaces the method `name`. Clients that didn't ask for JSON-RPC get its result
+    /// under `key`.
+    pub fn register(&mut self, name: &str, key: &'static str, handler: Handler) {
+        self.methods
+            .insert(name.to_string(), Method { key, handler });
+    }
+
+    /// Runs `method` on `number`, returning the key its result goes under and the result.
+    pub fn call(&self, method: &str, number: &str) -> Result<(&'static str, Value), RpcError> {
+        let method_entry = self
+            .methods
+            .get(method)
+            .ok_or_else(|| RpcError::method_not_found(method))?;
+
+        Ok((method_entry.key, (method_entry.handler)(number)?))
+    }
+}
+
+fn is_prime(number: &str) -> Result<Value, RpcError> {
+    primality::is_prime_number(number)
+        .map(Value::from)
+        .map_err(|error| RpcError::invalid_params(error.to_string()))
+}
+
+fn factorize(number: &str) -> Result<Value, RpcError> {
+    let n = natural(number, 1, u64::MAX)?;
+
+    Ok(json!(methods::factorize(n)))
+}
+
+fn next_prime(number: &str) -> Result<Value, RpcError> {
+    let n = match integer(number)? {
+        n if n < 2 => 1,
+        n => u64::try_from(n)
+            .ok()
+            .filter(|&n| n < LARGEST_U64_PRIME)
+            .ok_or_else(|| {
+                RpcError::invalid_params(format!("Number must be below {LARGEST_U64_PRIME}"))
+            })?,
+    };
+
+    Ok(json!(methods::next_prime(n)))
+}
+
+fn prime_count(number: &str) -> Result<Value, RpcError> {
+    let n = match integer(number)? {
+        n if n < 0 => 0,
+        _ => natural(number, 0, PRIME_COUNT_LIMIT)?,
+    };
+
+    Ok(json!(methods::prime_count(n)))
+}
+
+fn is_perfect(number: &str) -> Result<Value, RpcError> {
+    // No perfect number lies between 2^64 and 2^128: the next even one is 2^88 (2^89 - 1),
+    // and any odd one would have over 1500 digits.
+    let perfect = match primality::integer(number) {
+        Ok(Some(n)) => u64::try_from(n).is_ok_and(methods::is_perfect),
+        Ok(None) => false,
+        Err(error) => return Err(invalid(error)),
+    };
+
+    Ok(json!(perfect))
+}
+
+fn integer(number: &str) -> Result<i128, RpcError> {
+    primality::integer(number)
+        .map_err(invalid)?
+        .ok_or_else(|| RpcError::invalid_params("Number must be an integer"))
+}
+
+/// `number` as an integer from `min` to `max`.
+fn natural(number: &str, min: u64, max: u64) -> Result<u64, RpcError> {
+    u64::try_from(integer(number)?)
+        .ok()
+        .filter(|n| (min..=max).contains(n))
+        .ok_or_else(|| RpcError::invalid_params(format!("Number must be from {min} to {max}")))
+}
+
+fn invalid(error: NumberError) -> RpcError {
+    RpcError::invalid_params(error.to_string())
+}
@@ -0,0 +1,52 @@
+use std::sync::OnceLock;
+
+/// Numbers below this are answered from the sieve.
+pub(crate) const LIMIT: u32 = 1 << 16;
+
+/// The primes below `LIMIT`, computed once on first use and shared by every connection.
+pub(crate) struct Sieve {
+    is_prime: Vec<bool>,
+    primes: Vec<u32>,
+}
+
+impl Sieve {
+    fn new() -> Sieve {
+        let mut is_prime = vec![true; LIMIT as usize];
+        is_prime[0] = false;
+        is_prime[1] = false;
+
+        let mut n = 2;
+
+        while n * n < LIMIT as usize {
+            if is_prime[n] {
+                for multiple in (n * n..LIMIT as usize).step_by(n) {
+                    is_prime[multiple] = false;
+                }
+            }
+            n += 1;
+        }
+
+        let primes = (0..LIMIT).filter(|&n| is_prime[n as usize]).collect();
+
+        Sieve { is_prime, primes }
+    }
+
+    /// Whether `n`, which must be below `LIMIT`, is prime.
+    pub fn is_prime(&self, n: u32) -> bool {
+        self.is_prime[n as usize]
+    }
+
+    /// The primes below `limit`, in order.
+    pub fn primes_below(&self, limit: u32) -> impl Iterator<Item = u32> + '_ {
+        self.primes
+            .iter()
+            .copied()
+            .take_while(move |&prime| prime < limit)
+    }
+}
+
+pub(crate) fn sieve() -> &'static Sieve {
+    static SIEVE: OnceLock<Sieve> = OnceLock::new();
+
+    SIEVE.get_or_init(Sieve::new)
+}
@@ -0,0 +1,41 @@
+use prime_time::cache::Lru;
+
+#[test]
+fn forgets_the_least_recently_used_entry() {
+    let mut cache = Lru::new(2);
+
+    cache.insert("a", 1);
+    cache.insert("b", 2);
+    assert_eq!(cache.get(&"a"), Some(1));
+
+    cache.insert("c", 3);
+
+    assert_eq!(cache.get(&"b"), None);
+    assert_eq!(cache.get(&"a"), Some(1));
+    assert_eq!(cache.get(&"c"), Some(3));
+    assert_eq!(cache.len(), 2);
+}
+
+#[test]
+fn replaces_existing_entries_in_place() {
+    let mut cache = Lru::new(2);
+
+    cache.insert("a", 1);
+    cache.insert("b", 2);
+    cache.insert("a", 10);
+    cache.insert("c", 3);
+
+    assert_eq!(cache.get(&"a"), Some(10));
+    assert_eq!(cache.get(&"b"), None);
+    assert_eq!(cache.len(), 2);
+}
+
+#[test]
+fn holds_nothing_with_no_capacity() {
+    let mut cache = Lru::new(0);
+
+    cache.insert("a", 1);
+
+    assert!(cache.is_empty());
+    assert_eq!(cache.get(&"a"), None);
+}
@@ -0,0 +1,261 @@
+use prime_time::{models::RpcResponse, primality, rpc::Registry};
+use shared::{
+    config::Config,
+    testing::{self, TcpClient, Transport},
+};
+use std::net::SocketAddr;
+
+fn server() -> SocketAddr {
+    testing::spawn(
+        Transport::Tcp,
+        prime_time::default_config(),
+        prime_time::run,
+    )
+}
+
+#[test]
+fn answers_conforming_requests() {
+    let mut client = TcpClient::connect(server());
+
+    for (number, prime) in [("7", true), ("8", false), ("-3", false), ("1", false)] {
+        client.send_line(&format!(r#"{{"method":"isPrime","number":{number}}}"#));
+        client.expect_line(&format!(r#"{{"method":"isPrime","prime":{prime}}}"#));
+    }
+}
+
+#[test]
+fn treats_non_integers_as_not_prime() {
+    let mut client = TcpClient::connect(server());
+
+    client.send_line(r#"{"method":"isPrime","number":7.5}"#);
+    client.expect_line(r#"{"method":"isPrime","prime":false}"#);
+}
+
+#[test]
+fn answers_integral_floats_and_bigints_exactly() {
+    let mut client = TcpClient::connect(server());
+
+    for (number, prime) in [
+        ("7.0", true),
+        ("7e0", true),
+        ("1.3e1", true),
+        ("170141183460469231731687303715884105727", true),
+        ("170141183460469231731687303715884105729", false),
+        ("1e400", false),
+    ] {
+        client.send_line(&format!(r#"{{"method":"isPrime","number":{number}}}"#));
+        client.expect_line(&format!(r#"{{"method":"isPrime","prime":{prime}}}"#));
+    }
+}
+
+#[test]
+fn ignores_extra_fields() {
+    let mut client = TcpClient::connect(server());
+
+    client.send_line(r#"{"number":2,"extra":[1,2],"method":"isPrime"}"#);
+    client.expect_line(r#"{"method":"isPrime","prime":true}"#);
+}
+
+#[test]
+fn answers_pipelined_requests_in_order() {
+    let mut client = TcpClient::connect(server());
+
+    client.send(
+        concat!(
+            r#"{"method":"isPrime","number":2}"#,
+            "\n",
+            r#"{"method":"isPrime","number":4}"#,
+            "\n",
+        )
+        .as_bytes(),
+    );
+
+    client.expect_line(r#"{"method":"isPrime","prime":true}"#);
+    client.expect_line(r#"{"method":"isPrime","prime":false}"#);
+}
+
+#[test]
+fn disconnects_on_malformed_json() {
+    let mut client = TcpClient::connect(server());
+
+    client.send_line("{\"method\":\"isPrime\"");
+    client.expect_closed();
+}
+
+#[test]
+fn disconnects_on_unknown_method() {
+    let mut client = TcpClient::connect(server());
+
+    client.send_line(r#"{"method":"isComposite","number":4}"#);
+    client.expect_closed();
+}
+
+#[test]
+fn disconnects_on_non_numeric_number() {
+    let mut client = TcpClient::connect(server());
+
+    client.send_line(r#"{"method":"isPrime","number":"7"}"#);
+    client.expect_closed();
+}
+
+#[test]
+fn disconnects_on_oversized_requests() {
+    let config = Config {
+        max_line_length: 64,
+        ..prime_time::default_config()
+    };
+    let mut client = TcpClient::connect(testing::spawn(Transport::Tcp, config, prime_time::run));
+
+    client.send_line(r#"{"method":"isPrime","number":7}"#);
+    client.expect_line(r#"{"method":"isPrime","prime":true}"#);
+
+    let padding = "x".repeat(64);
+    client.send_line(&format!(
+        r#"{{"method":"isPrime","number":7,"padding":"{padding}"}}"#
+    ));
+    client.expect_closed();
+}
+
+#[test]
+fn disconnects_when_a_method_panics() {
+    let mut registry = Registry::standard();
+    registry.register("isPrime", "prime", |_| panic!("method failed"));
+    let address = testing::spawn(
+        Transport::Tcp,
+        prime_time::default_config(),
+        move |config| prime_time::serve(config, registry),
+    );
+    let mut client = TcpClient::connect(address);
+
+    client.send_line(r#"{"method":"isPrime","number":7}"#);
+    client.expect_closed();
+}
+
+#[test]
+fn answers_number_theory_methods() {
+    let mut client = TcpClient::connect(server());
+
+    for (request, response) in [
+        (
+            r#"{"method":"factorize","number":360}"#,
+            r#"{"method":"factorize","factors":[2,2,2,3,3,5]}"#,
+        ),
+        (
+            r#"{"method":"nextPrime","number":7.0}"#,
+            r#"{"method":"nextPrime","next":11}"#,
+        ),
+        (
+            r#"{"method":"primeCount","number":100}"#,
+            r#"{"method":"primeCount","count":25}"#,
+        ),
+        (
+            r#"{"method":"isPerfect","number":496}"#,
+            r#"{"method":"isPerfect","perfect":true}"#,
+        ),
+    ] {
+        client.send_line(request);
+        client.expect_line(response);
+    }
+}
+
+#[test]
+fn answers_versioned_requests_in_json_rpc_style() {
+    let mut client = TcpClient::connect(server());
+
+    client.send_line(r#"{"jsonrpc":"2.0","id":1,"method":"isPrime","number":7}"#);
+    client.expect_line(r#"{"jsonrpc":"2.0","result":true,"id":1}"#);
+
+    client.send_line(r#"{"jsonrpc":"2.0","id":"a","method":"factorize","number":12}"#);
+    client.expect_line(r#"{"jsonrpc":"2.0","result":[2,2,3],"id":"a"}"#);
+}
+
+#[test]
+fn reports_errors_to_versioned_clients_and_stays_connected() {
+    let mut client = TcpClient::connect(server());
+
+    for (request, code) in [
+        (
+            r#"{"jsonrpc":"2.0","id":1,"method":"isComposite","number":4}"#,
+            -32601,
+        ),
+        (
+            r#"{"jsonrpc":"2.0","id":2,"method":"factorize","number":-4}"#,
+            -32602,
+        ),
+        (
+            r#"{"jsonrpc":"2.0","id":3,"method":"isPrime","number":"7"}"#,
+            -32602,
+        ),
+        (r#"{"jsonrpc":"2.0","id":4,"method":"isPrime"}"#, -32600),
+        (
+            r#"{"jsonrpc":"1.0","id":5,"method":"isPrime","number":7}"#,
+            -32600,
+        ),
+    ] {
+        client.send_line(request);
+
+        let response: RpcResponse = serde_json::from_str(&client.read_line()).unwrap();
+        assert_eq!(
+            response.error.map(|error| error.code),
+            Some(code),
+            "{request}"
+        );
+        assert_eq!(response.result, None);
+    }
+
+    client.send_line(r#"{"jsonrpc":"2.0","id":6,"method":"isPrime","number":7}"#);
+    client.expect_line(r#"{"jsonrpc":"2.0","result":true,"id":6}"#);
+}
+
+#[test]
+fn disconnects_unversioned_clients_on_errors() {
+    let mut client = TcpClient::connect(server());
+
+    client.send_line(r#"{"method":"factorize","number":0}"#);
+    client.expect_closed();
+}
+
+#[test]
+fn answers_many_pipelined_requests_in_order_despite_uneven_work() {
+    // 2^521 - 1 takes far longer to test than the small numbers queued behind it.
+    let slow = format!(
+        r#"{{"method":"isPrime","number":{}}}"#,
+        "6864797660130609714981900799081393217269435300143305409394463459185543183397656052122559640661454554977296311391480858037121987999716643812574028291115057151"
+    );
+    let mut client = TcpClient::connect(server());
+    let mut requests = String::new();
+
+    for n in 0..500 {
+        if n % 100 == 0 {
+            requests += &slow;
+        } else {
+            requests += &format!(r#"{{"method":"isPrime","number":{n}}}"#);
+        }
+        requests.push('\n');
+    }
+    client.send(requests);
+
+    for n in 0..500 {
+        let prime = n % 100 == 0 || primality::is_prime_u64(n);
+        client.expect_line(&format!(r#"{{"method":"isPrime","prime":{prime}}}"#));
+    }
+}
+
+#[test]
+fn answers_requests_before_a_malformed_one_then_disconnects() {
+    let mut client = TcpClient::connect(server());
+
+    client.send(concat!(
+        r#"{"method":"isPrime","number":2}"#,
+        "\n",
+        r#"{"method":"isPrime","number":3}"#,
+        "\n",
+        "not json\n",
+        r#"{"method":"isPrime","number":5}"#,
+        "\n",
+    ));
+
+    client.expect_line(r#"{"method":"isPrime","prime":true}"#);
+    client.expect_line(r#"{"method":"isPrime","prime":true}"#);
+    client.expect_closed();
+}
@@ -0,0 +1,56 @@
+use prime_time::methods;
+
+#[test]
+fn factorizes() {
+    assert_eq!(methods::factorize(1), Vec::<u64>::new());
+    assert_eq!(methods::factorize(360), [2, 2, 2, 3, 3, 5]);
+    assert_eq!(methods::factorize(1_000_003), [1_000_003]);
+
+    // Two primes above the trial division limit, and the product of two near 2^32.
+    assert_eq!(methods::factorize(1009 * 1013), [1009, 1013]);
+    assert_eq!(
+        methods::factorize(4_294_967_291 * 4_294_967_279),
+        [4_294_967_279, 4_294_967_291]
+    );
+    assert_eq!(
+        methods::factorize(u64::MAX),
+        [3, 5, 17, 257, 641, 65_537, 6_700_417]
+    );
+}
+
+#[test]
+fn finds_the_next_prime() {
+    assert_eq!(methods::next_prime(0), Some(2));
+    assert_eq!(methods::next_prime(2), Some(3));
+    assert_eq!(methods::next_prime(65_535), Some(65_537));
+    assert_eq!(methods::next_prime(1_000_000), Some(1_000_003));
+    assert_eq!(
+        methods::next_prime(methods::LARGEST_U64_PRIME - 1),
+        Some(methods::LARGEST_U64_PRIME)
+    );
+    assert_eq!(methods::next_prime(methods::LARGEST_U64_PRIME), None);
+}
+
+#[test]
+fn counts_primes() {
+    for (n, count) in [
+        (0, 0),
+        (2, 1),
+        (10, 4),
+        (65_535, 6542),
+        (65_536, 6542),
+        (65_537, 6543),
+        (1_000_000, 78_498),
+    ] {
+        assert_eq!(methods::prime_count(n), count, "{n}");
+    }
+}
+
+#[test]
+fn recognises_perfect_numbers() {
+    let perfect: Vec<u64> = (0..10_000).filter(|&n| methods::is_perfect(n)).collect();
+
+    assert_eq!(perfect, [6, 28, 496, 8128]);
+    assert!(methods::is_perfect(2_305_843_008_139_952_128));
+    assert!(!methods::is_perfect(2_305_843_008_139_952_127));
+}
@@ -0,0 +1,136 @@
+use prime_time::primality::{self, NumberError};
+
+fn is_prime(text: &str) -> bool {
+    primality::is_prime_number(text).unwrap()
+}
+
+/// `base.pow(exponent)` in decimal.
+fn pow(base: u32, exponent: u32) -> String {
+    let mut digits = vec![1u32];
+
+    for _ in 0..exponent {
+        let mut carry = 0;
+
+        for digit in &mut digits {
+            let product = *digit * base + carry;
+            *digit = product % 10;
+            carry = product / 10;
+        }
+
+        while carry > 0 {
+            digits.push(carry % 10);
+            carry /= 10;
+        }
+    }
+
+    digits.iter().rev().map(|digit| digit.to_string()).collect()
+}
+
+#[test]
+fn answers_small_integers() {
+    let primes: Vec<u64> = (0..100).filter(|&n| primality::is_prime_u64(n)).collect();
+
+    assert_eq!(
+        primes,
+        [
+            2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83,
+            89, 97
+        ]
+    );
+}
+
+#[test]
+fn agrees_with_trial_division_around_the_sieve_limit() {
+    let trial_division = |n: u64| n > 1 && (2..).take_while(|d| d * d <= n).all(|d| n % d != 0);
+    let numbers: Vec<u64> = (60_000..70_000).collect();
+
+    let expected: Vec<bool> = numbers.iter().map(|&n| trial_division(n)).collect();
+
+    assert_eq!(primality::is_prime_batch(&numbers), expected);
+}
+
+#[test]
+fn answers_repeated_questions_the_same_way() {
+    for _ in 0..3 {
+        assert!(is_prime("18446744073709551557"));
+        assert!(!is_prime("18446744073709551559"));
+        assert!(is_prime("170141183460469231731687303715884105727"));
+    }
+}
+
+#[test]
+fn answers_u64_edge_cases() {
+    // The largest prime below 2^64, and strong pseudoprimes to several small bases.
+    assert!(primality::is_prime_u64(18_446_744_073_709_551_557));
+    assert!(!primality::is_prime_u64(u64::MAX));
+    assert!(!primality::is_prime_u64(3_215_031_751));
+    assert!(!primality::is_prime_u64(3_825_123_056_546_413_051));
+    assert!(!primality::is_prime_u64(4_294_967_297));
+}
+
+#[test]
+fn treats_integral_floats_as_integers() {
+    for text in ["7.0", "7e0", "7.000", "0.7e1", "70e-1", "1.3e1", "7E+0"] {
+        assert!(is_prime(text), "{text}");
+    }
+
+    for text in [
+        "7.5",
+        "7.01",
+        "7e-1",
+        "1e1",
+        "2e100",
+        "-7",
+        "-7.0",
+        "0",
+        "0.0",
+        "1.0",
+        "2e-99999999999999999999",
+    ] {
+        assert!(!is_prime(text), "{text}");
+    }
+}
+
+#[test]
+fn answers_integers_wider_than_u64() {
+    // 2^89 - 1 and 2^127 - 1 are Mersenne primes; 2^128 + 1 has a factor of 59649589127497217.
+    assert!(is_prime("618970019642690137449562111"));
+    assert!(is_prime("170141183460469231731687303715884105727"));
+    assert!(is_prime("1.70141183460469231731687303715884105727e38"));
+    assert!(!is_prime("340282366920938463463374607431768211457"));
+    assert!(!is_prime("170141183460469231731687303715884105727.5"));
+
+    // The product of the two Mersenne primes, which has no small factor.
+    assert!(!is_prime(
+        "105312291668557186697918027683670432318895095400549111254310977407"
+    ));
+}
+
+#[test]
+fn answers_a_521_bit_prime() {
+    // 2^521 - 1 is a Mersenne prime, and 2^521 ends in a 2.
+    let mut mersenne = pow(2, 521);
+    mersenne.pop();
+    mersenne.push('1');
+
+    assert!(is_prime(&mersenne));
+    assert!(!is_prime(&format!("{mersenne}1")));
+}
+
+#[test]
+fn refuses_what_is_not_a_number_or_too_large() {
+    for text in ["\"7\"", "null", "[7]", "07", "7.", ".5", "1e", "--7", "7x"] {
+        assert_eq!(
+            primality::is_prime_number(text),
+            Err(NumberError::NotANumber),
+            "{text}"
+        );
+    }
+
+    // Over 4096 bits, and without a factor small enough for trial division.
+    assert_eq!(
+        primality::is_prime_number(&pow(1009, 420)),
+        Err(NumberError::TooLarge)
+    );
+    assert_eq!(primality::is_prime_number(&pow(3, 3000)), Ok(false));
+}
@@ -0,0 +1,61 @@
+use prime_time::{models::RpcError, rpc::Registry};
+use serde_json::{json, Value};
+
+fn double(number: &str) -> Result<Value, RpcError> {
+    let n: u64 = number
+        .parse()
+        .map_err(|_| RpcError::invalid_params("Not a whole number"))?;
+
+    Ok(json!(n * 2))
+}
+
+#[test]
+fn calls_registered_methods() {
+    let mut registry = Registry::new();
+    registry.register("double", "doubled", double);
+
+    assert_eq!(registry.call("double", "21"), Ok(("doubled", json!(42))));
+    assert_eq!(
+        registry.call("double", "2.5").unwrap_err().code,
+        RpcError::INVALID_PARAMS
+    );
+    assert_eq!(
+        registry.call("isPrime", "7").unwrap_err().code,
+        RpcError::METHOD_NOT_FOUND
+    );
+}
+
+#[test]
+fn offers_the_standard_methods() {
+    let registry = Registry::standard();
+
+    assert_eq!(registry.call("isPrime", "7"), Ok(("prime", json!(true))));
+    assert_eq!(
+        registry.call("factorize", "1e3"),
+        Ok(("factors", json!([2, 2, 2, 5, 5, 5])))
+    );
+    assert_eq!(registry.call("nextPrime", "-10"), Ok(("next", json!(2))));
+    assert_eq!(
+        registry.call("primeCount", "1e7").map(|(_, count)| count),
+        Ok(json!(664_579))
+    );
+    assert_eq!(
+        registry.call("isPerfect", "28.5"),
+        Ok(("perfect", json!(false)))
+    );
+
+    for (method, number) in [
+        ("factorize", "0"),
+        ("factorize", "1.5"),
+        ("factorize", "1e20"),
+        ("nextPrime", "18446744073709551557"),
+        ("primeCount", "100000001"),
+        ("isPerfect", "\"6\""),
+    ] {
+        assert_eq!(
+            registry.call(method, number).unwrap_err().code,
+            RpcError::INVALID_PARAMS,
+            "{method} {number}"
+        );
+    }
+}
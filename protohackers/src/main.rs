@@ -0,0 +1,268 @@
+use shared::{
+    config::{Config, USAGE},
+    error, info, log,
+};
+use std::{
+    env,
+    io::{Error, ErrorKind, Result},
+    panic, process,
+    sync::mpsc,
+    thread,
+};
+
+/// A protocol this binary can serve: its crate's default configuration and entry point.
+struct Protocol {
+    name: &'static str,
+    default_config: fn() -> Config,
+    run: fn(&Config) -> Result<()>,
+}
+
+const PROTOCOLS: &[Protocol] = &[
+    Protocol {
+        name: "smoke_test",
+        default_config: smoke_test::default_config,
+        run: smoke_test::run,
+    },
+    Protocol {
+        name: "prime_time",
+        default_config: prime_time::default_config,
+        run: prime_time::run,
+    },
+    Protocol {
+        name: "means_to_an_end",
+        default_config: means_to_an_end::default_config,
+        run: means_to_an_end::run,
+    },
+    Protocol {
+        name: "budget_chat",
+        default_config: budget_chat::default_config,
+        run: budget_chat::run,
+    },
+    Protocol {
+        name: "unusual_database_program",
+        default_config: unusual_database_program::default_config,
+        run: unusual_database_program::run,
+    },
+    Protocol {
+        name: "mob_in_the_middle",
+        default_config: mob_in_the_middle::default_config,
+        run: mob_in_the_middle::run,
+    },
+    Protocol {
+        name: "speed_daemon",
+        default_config: speed_daemon::default_config,
+        run: speed_daemon::run,
+    },
+    Protocol {
+        name: "line_reversal",
+        default_config: line_reversal::default_config,
+        run: line_reversal::run,
+    },
+    Protocol {
+        name: "insecure_sockets_layer",
+        default_config: insecure_sockets_layer::default_config,
+        run: insecure_sockets_layer::run,
+    },
+    Protocol {
+        name: "job_centre",
+        default_config: job_centre::default_config,
+        run: job_centre::run,
+    },
+    Protocol {
+        name: "voracious_code_storage",
+        default_config: voracious_code_storage::default_config,
+        run: voracious_code_storage::run,
+    },
+    Protocol {
+        name: "pest_control",
+        default_config: pest_control::default_config,
+        run: pest_control::run,
+    },
+];
+
+const HEADER: &str = "Usage: protohackers <protocol>[:port]... [options]
+
+Serves each protocol on its own port. A port is required for every protocol when more than
+one is given; a single protocol defaults to --port. Protocols can also be listed in
+PROTOHACKERS_PROTOCOLS, separated by commas.
+";
+
+fn main() -> Result<()> {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        print_usage(true);
+    }
+
+    let (mut services, flags) = split_args(args);
+
+    if services.is_empty() {
+        if let Ok(protocols) = env::var("PROTOHACKERS_PROTOCOLS") {
+            services = protocols
+                .split(',')
+                .map(str::trim)
+                .filter(|service| !service.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+    }
+
+    if services.is_empty() {
+        print_usage(false);
+    }
+
+    let configs = configure(&services, &flags)?;
+
+    if let Some((_, config)) = configs.first() {
+        log::init(config.log_level, config.log_format);
+    }
+
+    let (results, finished) = mpsc::channel();
+
+    for (protocol, config) in configs {
+        info!("Starting {} on {}", protocol.name, config.address());
+        let results = results.clone();
+
+        thread::Builder::new()
+            .name(protocol.name.to_string())
+            .spawn(move || {
+                let result = panic::catch_unwind(|| (protocol.run)(&config))
+                    .unwrap_or_else(|_| Err(Error::other("Protocol thread panicked")));
+                results.send((protocol.name, result)).ok();
+            })?;
+    }
+
+    drop(results);
+
+    // Protocols return together on shutdown. One that fails, whether binding at startup or
+    // later, ends the process rather than leaving the others serving without it.
+    for (name, result) in finished {
+        if let Err(e) = result {
+            error!("{name} failed: {}", e);
+            return Err(e);
+        }
+    }
+
+    Ok(())
+}
+
+/// Prints the shared options, then those of each protocol that has its own, and exits. Unless
+/// they were `requested`, they go to stderr and the exit status reports the missing protocol.
+fn print_usage(requested: bool) -> ! {
+    let names: Vec<&str> = PROTOCOLS.iter().map(|protocol| protocol.name).collect();
+    let mut usage = format!("{HEADER}\nProtocols: {}\n\n{USAGE}", names.join(", "));
+
+    for protocol in PROTOCOLS {
+        let flags = (protocol.default_config)().extra_flags;
+
+        if !flags.is_empty() {
+            usage.push_str(&format!("\n\n{} options:", protocol.name));
+
+            for flag in flags {
+                usage.push('\n');
+                usage.push_str(&flag.usage());
+            }
+        }
+    }
+
+    if requested {
+        println!("{usage}");
+        process::exit(0);
+    }
+
+    eprintln!("{usage}");
+    process::exit(2);
+}
+
+/// A `--flag value` or `--flag=value` argument, kept as given.
+struct FlagArg {
+    name: String,
+    args: Vec<String>,
+}
+
+/// Separates `protocol[:port]` arguments from `--flag value` pairs meant for `Config`.
+fn split_args(args: Vec<String>) -> (Vec<String>, Vec<FlagArg>) {
+    let mut services = Vec::new();
+    let mut flags = Vec::new();
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        match arg.strip_prefix("--") {
+            Some(flag) => {
+                let (name, takes_value) = match flag.split_once('=') {
+                    Some((name, _)) => (name.to_string(), false),
+                    None => (flag.to_string(), true),
+                };
+                let mut flag = FlagArg {
+                    name,
+                    args: vec![arg],
+                };
+
+                if takes_value {
+                    flag.args.extend(args.next());
+                }
+
+                flags.push(flag);
+            }
+            None => services.push(arg),
+        }
+    }
+
+    (services, flags)
+}
+
+/// Builds a configuration for each requested protocol from its defaults, the environment and
+/// `flags`. A protocol only sees the flags it knows, so that one protocol's own flags can be
+/// given alongside others, but every flag must be known to one of them. Only the first keeps
+/// `--metrics-port`, since the registry is shared by all of them.
+fn configure(services: &[String], flags: &[FlagArg]) -> Result<Vec<(&'static Protocol, Config)>> {
+    let mut configs = Vec::new();
+
+    for (index, service) in services.iter().enumerate() {
+        let (name, port) = match service.split_once(':') {
+            Some((name, port)) => match port.parse() {
+                Ok(port) => (name, Some(port)),
+                Err(_) => return Err(invalid(format!("Invalid port for {name}: {port}"))),
+            },
+            None => (service.as_str(), None),
+        };
+
+        let Some(protocol) = PROTOCOLS.iter().find(|protocol| protocol.name == name) else {
+            return Err(invalid(format!("Unknown protocol: {name}")));
+        };
+
+        let defaults = (protocol.default_config)();
+        let args: Vec<String> = flags
+            .iter()
+            .filter(|flag| defaults.knows(&flag.name))
+            .flat_map(|flag| flag.args.clone())
+            .collect();
+        let mut config = defaults.apply(|key| env::var(key).ok(), args)?;
+
+        match port {
+            Some(port) => config.port = port,
+            None if services.len() > 1 => {
+                return Err(invalid(format!("Missing port for {name}")));
+            }
+            None => {}
+        }
+
+        if index > 0 {
+            config.metrics_port = None;
+        }
+
+        configs.push((protocol, config));
+    }
+
+    for flag in flags {
+        if !configs.iter().any(|(_, config)| config.knows(&flag.name)) {
+            return Err(invalid(format!("Unknown option: --{}", flag.name)));
+        }
+    }
+
+    Ok(configs)
+}
+
+fn invalid(message: String) -> Error {
+    Error::new(ErrorKind::InvalidInput, message)
+}
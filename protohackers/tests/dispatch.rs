@@ -0,0 +1,170 @@
+use shared::testing::{self, TcpClient, Transport, TIMEOUT};
+use std::{
+    env, fs,
+    net::{SocketAddr, TcpStream},
+    process::{self, Child, Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// The dispatcher running as a child process, killed when dropped.
+struct Dispatcher(Child);
+
+impl Dispatcher {
+    /// Starts the binary with `args` and `env`, and waits until each of `addresses` accepts
+    /// connections.
+    fn start(args: &[&str], env: &[(&str, &str)], addresses: &[SocketAddr]) -> Dispatcher {
+        let child = command(args, env).stdout(Stdio::null()).spawn().unwrap();
+        let mut dispatcher = Dispatcher(child);
+        let deadline = Instant::now() + TIMEOUT;
+
+        while addresses
+            .iter()
+            .any(|&address| TcpStream::connect(address).is_err())
+        {
+            if let Some(status) = dispatcher.0.try_wait().unwrap() {
+                panic!("Dispatcher exited with {status}");
+            }
+            assert!(Instant::now() < deadline, "Dispatcher did not start");
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        dispatcher
+    }
+}
+
+impl Drop for Dispatcher {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+fn command(args: &[&str], env: &[(&str, &str)]) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_protohackers"));
+    command
+        .args(args)
+        .env_remove("PROTOHACKERS_PROTOCOLS")
+        .envs(env.iter().copied())
+        .stderr(Stdio::null());
+    command
+}
+
+fn message(kind: u8, a: i32, b: i32) -> Vec<u8> {
+    let mut bytes = vec![kind];
+    bytes.extend(a.to_be_bytes());
+    bytes.extend(b.to_be_bytes());
+    bytes
+}
+
+#[test]
+fn serves_protocols_from_the_environment_without_arguments() {
+    let address = testing::free_address(Transport::Tcp);
+    let port = address.port().to_string();
+    let _dispatcher = Dispatcher::start(
+        &[],
+        &[
+            ("PROTOHACKERS_PROTOCOLS", "smoke_test"),
+            ("PROTOHACKERS_BIND", "127.0.0.1"),
+            ("PROTOHACKERS_PORT", &port),
+        ],
+        &[address],
+    );
+
+    let mut client = TcpClient::connect(address);
+    client.send("hello");
+    client.expect("hello");
+}
+
+#[test]
+fn passes_each_protocol_its_own_flags() {
+    let means = testing::free_address(Transport::Tcp);
+    let smoke = testing::free_address(Transport::Tcp);
+    let history = env::temp_dir().join(format!("dispatch-history-{}", process::id()));
+    let _ = fs::remove_file(&history);
+
+    let _dispatcher = Dispatcher::start(
+        &[
+            &format!("means_to_an_end:{}", means.port()),
+            &format!("smoke_test:{}", smoke.port()),
+            "--bind=127.0.0.1",
+            "--history",
+            history.to_str().unwrap(),
+            "--max-bytes=3",
+        ],
+        &[],
+        &[means, smoke],
+    );
+
+    let mut client = TcpClient::connect(means);
+    client.send(message(b'N', 3, 0));
+    client.send("BTC");
+    client.send(message(b'I', 1, 42));
+    client.send(message(b'Q', 0, 2));
+    client.expect(42i32.to_be_bytes());
+
+    let mut client = TcpClient::connect(smoke);
+    client.send("hello");
+    client.expect("hel");
+    client.expect_closed();
+
+    let _ = fs::remove_file(history);
+}
+
+#[test]
+fn prints_help_on_request() {
+    let output = command(&["--help"], &[]).output().unwrap();
+    let usage = String::from_utf8(output.stdout).unwrap();
+
+    assert!(output.status.success());
+    assert!(usage.starts_with("Usage: protohackers"));
+    assert!(usage.contains("means_to_an_end options:\n  --history <path>"));
+}
+
+#[test]
+fn fails_with_help_when_nothing_is_selected() {
+    let output = command(&[], &[]).stderr(Stdio::piped()).output().unwrap();
+    let usage = String::from_utf8(output.stderr).unwrap();
+
+    assert!(!output.status.success());
+    assert!(usage.starts_with("Usage: protohackers"));
+}
+
+#[test]
+fn exits_when_any_protocol_fails_to_start() {
+    let port = testing::free_address(Transport::Tcp).port();
+    let mut child = command(
+        &[
+            &format!("smoke_test:{port}"),
+            &format!("prime_time:{port}"),
+            "--bind=127.0.0.1",
+        ],
+        &[],
+    )
+    .stdout(Stdio::null())
+    .spawn()
+    .unwrap();
+    let deadline = Instant::now() + TIMEOUT;
+
+    let status = loop {
+        if let Some(status) = child.try_wait().unwrap() {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            panic!("Dispatcher kept running after a protocol failed");
+        }
+        thread::sleep(Duration::from_millis(10));
+    };
+
+    assert!(!status.success());
+}
+
+#[test]
+fn rejects_flags_no_selected_protocol_knows() {
+    let output = command(&["prime_time", "--history", "prices"], &[])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+}
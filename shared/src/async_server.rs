@@ -0,0 +1,454 @@
+#[cfg(feature = "tls")]
+use crate::tls::Acceptor;
+use crate::{
+    config::Config,
+    debug, error, log, metrics,
+    server::{self, ConnectionGuard, Limits, ServerMetrics},
+    signal, warn,
+};
+use std::{
+    future::Future,
+    io::{Error, ErrorKind, Result},
+    net::{self, SocketAddr},
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::Arc,
+    task::{self, ready, Poll},
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    net::{TcpListener, TcpStream},
+    runtime::{self, Runtime},
+    sync::Notify,
+    task::JoinSet,
+    time::{self, Sleep},
+};
+
+/// Details about the connection being handled.
+#[derive(Clone, Copy, Debug)]
+pub struct Context {
+    /// Sequence number of the connection, unique for the life of the server.
+    pub id: usize,
+    pub peer: SocketAddr,
+}
+
+pub struct AsyncServerBuilder {
+    address: String,
+    workers: usize,
+    read_timeout: Option<Duration>,
+    write_timeout: Option<Duration>,
+    idle_timeout: Option<Duration>,
+    max_connections: Option<usize>,
+    max_connections_per_ip: Option<usize>,
+    shutdown_timeout: Duration,
+    metrics_address: Option<String>,
+    tls: Option<(PathBuf, PathBuf)>,
+}
+
+/// Tokio-based counterpart to `Server`: every connection is a task rather than a pool job, so
+/// idle connections cost no thread. Timeouts and connection limits from `Config` are applied
+/// as they are by `Server`.
+pub struct AsyncServer {
+    listener: net::TcpListener,
+    runtime: Runtime,
+    read_timeout: Option<Duration>,
+    write_timeout: Option<Duration>,
+    limits: Arc<Limits>,
+    shutdown_timeout: Duration,
+    #[cfg(feature = "tls")]
+    tls: Option<Arc<Acceptor>>,
+}
+
+/// The connection handed to a handler: a `TcpStream`, or a TLS session over one. Reads fail
+/// with `TimedOut` once one has waited longer than the read or idle timeout, and writes once
+/// one has waited longer than the write timeout, like a `std` socket with those timeouts set.
+pub struct Stream {
+    inner: Inner,
+    read_timeout: Option<Duration>,
+    write_timeout: Option<Duration>,
+    read_deadline: Option<Pin<Box<Sleep>>>,
+    write_deadline: Option<Pin<Box<Sleep>>>,
+}
+
+enum Inner {
+    Tcp(TcpStream),
+    #[cfg(feature = "tls")]
+    Tls(Box<tokio_rustls::server::TlsStream<TcpStream>>),
+}
+
+/// Sets the connection's peer as the log context whenever the wrapped future is polled,
+/// since a task may move between runtime threads.
+struct Scoped<F> {
+    peer: SocketAddr,
+    future: Pin<Box<F>>,
+}
+
+impl AsyncServerBuilder {
+    /// Takes the address, runtime thread count, timeouts, connection limits and TLS files from
+    /// `config`.
+    pub fn config(mut self, config: &Config) -> AsyncServerBuilder {
+        self.address = config.address();
+        self.workers = config.workers;
+        self.read_timeout = config.read_timeout;
+        self.write_timeout = config.write_timeout;
+        self.idle_timeout = config.idle_timeout;
+        self.max_connections = config.max_connections;
+        self.max_connections_per_ip = config.max_connections_per_ip;
+        self.shutdown_timeout = config.shutdown_timeout;
+        self.metrics_address = config.metrics_address();
+        self.tls = config.tls();
+        self
+    }
+
+    pub fn bind(mut self, address: &str) -> AsyncServerBuilder {
+        self.address = address.to_string();
+        self
+    }
+
+    /// Number of runtime worker threads.
+    pub fn workers(mut self, workers: usize) -> AsyncServerBuilder {
+        self.workers = workers;
+        self
+    }
+
+    pub fn read_timeout(mut self, timeout: Duration) -> AsyncServerBuilder {
+        self.read_timeout = Some(timeout);
+        self
+    }
+
+    pub fn write_timeout(mut self, timeout: Duration) -> AsyncServerBuilder {
+        self.write_timeout = Some(timeout);
+        self
+    }
+
+    /// Closes connections that send nothing for `timeout`. Applied as the read timeout, or in
+    /// place of `read_timeout` when that is longer.
+    pub fn idle_timeout(mut self, timeout: Duration) -> AsyncServerBuilder {
+        self.idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Closes new connections immediately while `max` connections are already open.
+    pub fn max_connections(mut self, max: usize) -> AsyncServerBuilder {
+        self.max_connections = Some(max);
+        self
+    }
+
+    /// Closes new connections immediately while `max` connections from the same IP address
+    /// are already open.
+    pub fn max_connections_per_ip(mut self, max: usize) -> AsyncServerBuilder {
+        self.max_connections_per_ip = Some(max);
+        self
+    }
+
+    /// How long `AsyncServer::run` waits for open connections after a shutdown signal.
+    pub fn shutdown_timeout(mut self, timeout: Duration) -> AsyncServerBuilder {
+        self.shutdown_timeout = timeout;
+        self
+    }
+
+    /// Serves the process's metrics on `address`; see `metrics::serve`.
+    pub fn metrics(mut self, address: &str) -> AsyncServerBuilder {
+        self.metrics_address = Some(address.to_string());
+        self
+    }
+
+    /// Serves TLS with the PEM certificate chain and private key at `cert` and `key`. The
+    /// handshake runs on the connection's task, before the handler gets the session as its
+    /// `Stream`.
+    pub fn tls(mut self, cert: &Path, key: &Path) -> AsyncServerBuilder {
+        self.tls = Some((cert.to_path_buf(), key.to_path_buf()));
+        self
+    }
+
+    pub fn build(self) -> Result<AsyncServer> {
+        server::check_tls_support(&self.tls)?;
+        let listener = net::TcpListener::bind(&self.address)?;
+        listener.set_nonblocking(true)?;
+
+        if let Some(address) = &self.metrics_address {
+            metrics::serve(address)?;
+        }
+
+        let runtime = runtime::Builder::new_multi_thread()
+            .worker_threads(self.workers.max(1))
+            .thread_name("runtime")
+            .enable_all()
+            .build()?;
+
+        Ok(AsyncServer {
+            listener,
+            runtime,
+            read_timeout: self.read_timeout.into_iter().chain(self.idle_timeout).min(),
+            write_timeout: self.write_timeout,
+            limits: Arc::new(Limits::new(
+                self.max_connections,
+                self.max_connections_per_ip,
+            )),
+            shutdown_timeout: self.shutdown_timeout,
+            #[cfg(feature = "tls")]
+            tls: self
+                .tls
+                .map(|(cert, key)| Acceptor::new(&cert, &key).map(Arc::new))
+                .transpose()?,
+        })
+    }
+}
+
+impl AsyncServer {
+    pub fn builder() -> AsyncServerBuilder {
+        AsyncServerBuilder {
+            address: "0.0.0.0:8080".to_string(),
+            workers: 2,
+            read_timeout: None,
+            write_timeout: None,
+            idle_timeout: None,
+            max_connections: None,
+            max_connections_per_ip: None,
+            shutdown_timeout: Duration::from_secs(5),
+            metrics_address: None,
+            tls: None,
+        }
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Starts a long-lived background task on the server's runtime before `run`.
+    pub fn spawn<F>(&self, future: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.runtime.spawn(future);
+    }
+
+    /// Accepts connections and spawns the future returned by `handler` for each of them until
+    /// the process receives SIGTERM or SIGINT, then waits for open connections to finish.
+    pub fn run<H, F>(self, handler: H) -> Result<()>
+    where
+        H: Fn(Stream, Context) -> F + Send + Sync + 'static,
+        F: Future<Output = Result<()>> + Send + 'static,
+    {
+        let handler = Arc::new(handler);
+        let shutdown = Arc::new(Notify::new());
+        let notify = Arc::clone(&shutdown);
+        signal::on_terminate(move || notify.notify_one())?;
+
+        let AsyncServer {
+            listener,
+            runtime,
+            read_timeout,
+            write_timeout,
+            limits,
+            shutdown_timeout,
+            #[cfg(feature = "tls")]
+            tls,
+        } = self;
+
+        runtime.block_on(async move {
+            let listener = TcpListener::from_std(listener)?;
+            let metrics = Arc::new(ServerMetrics::new());
+            let mut connections = JoinSet::new();
+            let mut next_id = 0;
+
+            loop {
+                let (stream, peer) = tokio::select! {
+                    _ = shutdown.notified() => break,
+                    Some(_) = connections.join_next(), if !connections.is_empty() => continue,
+                    accepted = listener.accept() => match accepted {
+                        Ok(accepted) => accepted,
+                        Err(e) => {
+                            error!("Connection error: {}", e);
+                            continue;
+                        }
+                    },
+                };
+
+                let Some(guard) = ConnectionGuard::admit(&metrics, &limits, peer) else {
+                    continue;
+                };
+
+                let handler = Arc::clone(&handler);
+                #[cfg(feature = "tls")]
+                let tls = tls.clone();
+                let context = Context { id: next_id, peer };
+                next_id += 1;
+
+                connections.spawn(Scoped::new(peer, async move {
+                    debug!("Connection accepted");
+
+                    #[cfg(feature = "tls")]
+                    let stream = match tls {
+                        Some(tls) => match tls.accept_async(stream).await {
+                            Ok(stream) => Inner::Tls(Box::new(stream)),
+                            Err(e) => {
+                                warn!("TLS handshake failed: {e}");
+                                return;
+                            }
+                        },
+                        None => Inner::Tcp(stream),
+                    };
+                    #[cfg(not(feature = "tls"))]
+                    let stream = Inner::Tcp(stream);
+
+                    let stream = Stream::new(stream, read_timeout, write_timeout);
+
+                    match handler(stream, context).await {
+                        Ok(()) => debug!("Connection closed"),
+                        Err(e) if e.kind() == ErrorKind::TimedOut => {
+                            guard.metrics.timeouts.inc();
+                            warn!("Connection timed out; closing");
+                        }
+                        Err(e) => {
+                            guard.metrics.errors.inc();
+                            error!("Connection error: {}", e);
+                        }
+                    }
+                }));
+            }
+
+            drop(listener);
+
+            let drain = async { while connections.join_next().await.is_some() {} };
+
+            if time::timeout(shutdown_timeout, drain).await.is_err() {
+                warn!(
+                    "{} connections still open at shutdown; abandoning.",
+                    connections.len()
+                );
+                connections.abort_all();
+            }
+
+            Ok(())
+        })
+    }
+}
+
+impl Stream {
+    fn new(
+        inner: Inner,
+        read_timeout: Option<Duration>,
+        write_timeout: Option<Duration>,
+    ) -> Stream {
+        Stream {
+            inner,
+            read_timeout,
+            write_timeout,
+            read_deadline: None,
+            write_deadline: None,
+        }
+    }
+
+    pub fn peer_addr(&self) -> Result<SocketAddr> {
+        self.socket().peer_addr()
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        self.socket().local_addr()
+    }
+
+    fn socket(&self) -> &TcpStream {
+        match &self.inner {
+            Inner::Tcp(stream) => stream,
+            #[cfg(feature = "tls")]
+            Inner::Tls(stream) => stream.get_ref().0,
+        }
+    }
+}
+
+impl AsyncRead for Stream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut task::Context,
+        buf: &mut ReadBuf,
+    ) -> Poll<Result<()>> {
+        let this = &mut *self;
+
+        match this.inner.io().poll_read(cx, buf) {
+            Poll::Ready(result) => {
+                this.read_deadline = None;
+                Poll::Ready(result)
+            }
+            Poll::Pending => expire(&mut this.read_deadline, this.read_timeout, cx),
+        }
+    }
+}
+
+impl AsyncWrite for Stream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut task::Context,
+        buf: &[u8],
+    ) -> Poll<Result<usize>> {
+        let this = &mut *self;
+
+        match this.inner.io().poll_write(cx, buf) {
+            Poll::Ready(result) => {
+                this.write_deadline = None;
+                Poll::Ready(result)
+            }
+            Poll::Pending => expire(&mut this.write_deadline, this.write_timeout, cx),
+        }
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut task::Context) -> Poll<Result<()>> {
+        self.inner.io().poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut task::Context) -> Poll<Result<()>> {
+        self.inner.io().poll_shutdown(cx)
+    }
+}
+
+impl Inner {
+    fn io(&mut self) -> Pin<&mut (dyn Io + Send)> {
+        match self {
+            Inner::Tcp(stream) => Pin::new(stream),
+            #[cfg(feature = "tls")]
+            Inner::Tls(stream) => Pin::new(stream.as_mut()),
+        }
+    }
+}
+
+trait Io: AsyncRead + AsyncWrite + Unpin {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin> Io for T {}
+
+/// Starts `deadline` for an operation that is waiting, if there's a `timeout`, and fails the
+/// operation once it passes.
+fn expire<T>(
+    deadline: &mut Option<Pin<Box<Sleep>>>,
+    timeout: Option<Duration>,
+    cx: &mut task::Context,
+) -> Poll<Result<T>> {
+    let Some(timeout) = timeout else {
+        return Poll::Pending;
+    };
+
+    let sleep = deadline.get_or_insert_with(|| Box::pin(time::sleep(timeout)));
+    ready!(sleep.as_mut().poll(cx));
+    *deadline = None;
+
+    Poll::Ready(Err(Error::new(ErrorKind::TimedOut, "timed out")))
+}
+
+impl<F: Future> Scoped<F> {
+    fn new(peer: SocketAddr, future: F) -> Scoped<F> {
+        Scoped {
+            peer,
+            future: Box::pin(future),
+        }
+    }
+}
+
+impl<F: Future> Future for Scoped<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut task::Context) -> Poll<F::Output> {
+        let _scope = log::connection(self.peer);
+
+        self.future.as_mut().poll(cx)
+    }
+}
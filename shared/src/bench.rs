@@ -0,0 +1,23 @@
+//! A minimal timing loop for the crates' `harness = false` benchmarks.
+
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+/// Runs `f` repeatedly for about `budget`, or once if that takes longer, and prints the mean
+/// time per run next to `name`.
+pub fn run<T>(name: &str, budget: Duration, mut f: impl FnMut() -> T) {
+    let started = Instant::now();
+    let mut runs = 0;
+
+    while runs == 0 || started.elapsed() < budget {
+        black_box(f());
+        runs += 1;
+    }
+
+    println!(
+        "{name:<48} {:>12.3?} per run ({runs} runs)",
+        started.elapsed() / runs
+    );
+}
@@ -0,0 +1,259 @@
+//! Binary encoding for framed protocols. Integers are big-endian. Strings and vectors carry a
+//! length prefix whose integer type varies between protocols, so they implement
+//! `EncodePrefixed`/`DecodePrefixed` rather than `Encode`/`Decode` and the prefix type is
+//! chosen where they are used, e.g. with `#[codec(len = u8)]` on a derived struct's field.
+//!
+//! Decoding reads from the front of a byte slice and advances it. Running out of bytes is an
+//! `UnexpectedEof` error, which callers reading from a stream can treat as "wait for more";
+//! `FrameDecoder` does this for them.
+
+pub use shared_derive::{Decode, Encode};
+use std::{
+    io::{Error, ErrorKind, Read, Result},
+    str,
+};
+#[cfg(feature = "async")]
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// How many bytes `FrameDecoder::read` asks a blocking reader for at once.
+const READ_SIZE: usize = 4096;
+
+pub trait Encode {
+    /// Appends the encoding of `self` to `buffer`.
+    fn encode(&self, buffer: &mut Vec<u8>) -> Result<()>;
+}
+
+pub trait Decode: Sized {
+    /// Decodes a value from the front of `input` and advances past it.
+    fn decode(input: &mut &[u8]) -> Result<Self>;
+}
+
+pub trait EncodePrefixed {
+    /// Appends the length of `self` as an `L`, then its contents. Fails with `InvalidInput` if
+    /// the length doesn't fit in an `L`.
+    fn encode_prefixed<L: Length>(&self, buffer: &mut Vec<u8>) -> Result<()>;
+}
+
+pub trait DecodePrefixed: Sized {
+    /// Decodes a length as an `L`, then that many bytes or elements.
+    fn decode_prefixed<L: Length>(input: &mut &[u8]) -> Result<Self>;
+}
+
+/// Integer types used as length prefixes.
+pub trait Length: Encode + Decode + TryFrom<usize> + TryInto<usize> {}
+
+impl Length for u8 {}
+impl Length for u16 {}
+impl Length for u32 {}
+
+/// Encodes `value` into a new buffer.
+pub fn to_bytes(value: &impl Encode) -> Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+    value.encode(&mut buffer)?;
+
+    Ok(buffer)
+}
+
+/// Decodes a `T` that must span all of `input`.
+pub fn from_bytes<T: Decode>(mut input: &[u8]) -> Result<T> {
+    let value = T::decode(&mut input)?;
+
+    if !input.is_empty() {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("{} unexpected bytes after value", input.len()),
+        ));
+    }
+
+    Ok(value)
+}
+
+/// Splits `len` bytes off the front of `input`.
+pub fn take<'a>(input: &mut &'a [u8], len: usize) -> Result<&'a [u8]> {
+    if input.len() < len {
+        return Err(Error::from(ErrorKind::UnexpectedEof));
+    }
+
+    let (taken, rest) = input.split_at(len);
+    *input = rest;
+
+    Ok(taken)
+}
+
+macro_rules! impl_integer {
+    ($($type:ty),*) => {$(
+        impl Encode for $type {
+            fn encode(&self, buffer: &mut Vec<u8>) -> Result<()> {
+                buffer.extend_from_slice(&self.to_be_bytes());
+                Ok(())
+            }
+        }
+
+        impl Decode for $type {
+            fn decode(input: &mut &[u8]) -> Result<Self> {
+                let bytes = take(input, size_of::<$type>())?;
+                Ok(<$type>::from_be_bytes(bytes.try_into().unwrap()))
+            }
+        }
+    )*};
+}
+
+impl_integer!(u8, u16, u32, u64, i8, i16, i32, i64);
+
+fn encode_length<L: Length>(len: usize, buffer: &mut Vec<u8>) -> Result<()> {
+    L::try_from(len)
+        .map_err(|_| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("Length {len} doesn't fit in its prefix"),
+            )
+        })?
+        .encode(buffer)
+}
+
+fn decode_length<L: Length>(input: &mut &[u8]) -> Result<usize> {
+    L::decode(input)?
+        .try_into()
+        .map_err(|_| Error::from(ErrorKind::InvalidData))
+}
+
+impl EncodePrefixed for String {
+    fn encode_prefixed<L: Length>(&self, buffer: &mut Vec<u8>) -> Result<()> {
+        encode_length::<L>(self.len(), buffer)?;
+        buffer.extend_from_slice(self.as_bytes());
+
+        Ok(())
+    }
+}
+
+impl DecodePrefixed for String {
+    fn decode_prefixed<L: Length>(input: &mut &[u8]) -> Result<Self> {
+        let len = decode_length::<L>(input)?;
+        let bytes = take(input, len)?;
+
+        match str::from_utf8(bytes) {
+            Ok(string) => Ok(string.to_string()),
+            Err(e) => Err(Error::new(ErrorKind::InvalidData, e)),
+        }
+    }
+}
+
+impl<T: Encode> EncodePrefixed for Vec<T> {
+    fn encode_prefixed<L: Length>(&self, buffer: &mut Vec<u8>) -> Result<()> {
+        encode_length::<L>(self.len(), buffer)?;
+
+        for item in self {
+            item.encode(buffer)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<T: Decode> DecodePrefixed for Vec<T> {
+    fn decode_prefixed<L: Length>(input: &mut &[u8]) -> Result<Self> {
+        let len = decode_length::<L>(input)?;
+
+        // The claimed length comes off the wire, so don't let it size the allocation beyond
+        // what the remaining input could plausibly hold.
+        let mut items = Vec::with_capacity(len.min(input.len()));
+
+        for _ in 0..len {
+            items.push(T::decode(input)?);
+        }
+
+        Ok(items)
+    }
+}
+
+/// Buffers bytes from a stream and decodes whole messages from the front of them. Bytes of an
+/// incomplete message stay buffered until the rest arrives, so a read that times out or would
+/// block part way through a message loses nothing and can simply be retried.
+pub struct FrameDecoder<T> {
+    buffer: Vec<u8>,
+    decode: fn(&mut &[u8]) -> Result<T>,
+}
+
+impl<T> FrameDecoder<T> {
+    /// `decode` reads one message from the front of its input, failing with `UnexpectedEof`
+    /// if the input ends before the message does.
+    pub fn new(decode: fn(&mut &[u8]) -> Result<T>) -> FrameDecoder<T> {
+        FrameDecoder {
+            buffer: Vec::new(),
+            decode,
+        }
+    }
+
+    /// Appends bytes received from the stream.
+    pub fn extend(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// Whether no bytes are buffered, i.e. the stream is between messages.
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    /// Decodes and removes the first buffered message, or returns `None` without consuming
+    /// anything if it hasn't fully arrived. Other errors leave the buffer as it was.
+    pub fn decode(&mut self) -> Result<Option<T>> {
+        let mut input = &self.buffer[..];
+
+        match (self.decode)(&mut input) {
+            Ok(message) => {
+                let consumed = self.buffer.len() - input.len();
+                self.buffer.drain(..consumed);
+
+                Ok(Some(message))
+            }
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Reads from `reader` until a whole message is buffered and returns it, or `None` if the
+    /// stream ends cleanly between messages. Read errors, including timeouts, are returned
+    /// with any partial message still buffered for the next call.
+    pub fn read<R: Read>(&mut self, reader: &mut R) -> Result<Option<T>> {
+        let mut chunk = [0; READ_SIZE];
+
+        loop {
+            if let Some(message) = self.decode()? {
+                return Ok(Some(message));
+            }
+
+            match reader.read(&mut chunk) {
+                Ok(0) => return self.end(),
+                Ok(n) => self.extend(&chunk[..n]),
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Like `read`, for asynchronous readers. Cancel safe: if the future is dropped, any bytes
+    /// already read stay buffered.
+    #[cfg(feature = "async")]
+    pub async fn read_async<R: AsyncRead + Unpin>(&mut self, reader: &mut R) -> Result<Option<T>> {
+        loop {
+            if let Some(message) = self.decode()? {
+                return Ok(Some(message));
+            }
+
+            if reader.read_buf(&mut self.buffer).await? == 0 {
+                return self.end();
+            }
+        }
+    }
+
+    fn end(&self) -> Result<Option<T>> {
+        if self.is_empty() {
+            Ok(None)
+        } else {
+            Err(Error::new(
+                ErrorKind::UnexpectedEof,
+                "Stream ended part way through a message",
+            ))
+        }
+    }
+}
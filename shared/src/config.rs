@@ -0,0 +1,298 @@
+use crate::{
+    line::DEFAULT_MAX_LINE_LENGTH,
+    log::{self, Format, Level},
+};
+use std::{
+    collections::BTreeMap,
+    env,
+    io::{Error, ErrorKind, Result},
+    path::PathBuf,
+    process,
+    time::Duration,
+};
+
+/// Listener and logging settings shared by every server, read from command-line flags and
+/// `PROTOHACKERS_*` environment variables. Flags take precedence over the environment.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Config {
+    pub bind: String,
+    pub port: u16,
+    pub workers: usize,
+    pub read_timeout: Option<Duration>,
+    pub write_timeout: Option<Duration>,
+    pub idle_timeout: Option<Duration>,
+    pub max_connections: Option<usize>,
+    pub max_connections_per_ip: Option<usize>,
+    pub shutdown_timeout: Duration,
+    pub max_line_length: usize,
+    pub log_level: Level,
+    pub log_format: Format,
+    pub metrics_port: Option<u16>,
+    pub metrics_bind: String,
+    pub tls_cert: Option<PathBuf>,
+    pub tls_key: Option<PathBuf>,
+    /// Flags a crate understands beyond these, registered with `Config::with_flags`.
+    pub extra_flags: &'static [Flag],
+    /// Values given for `extra_flags`, by flag name.
+    pub extras: BTreeMap<&'static str, String>,
+}
+
+/// A flag understood by one crate's server, parsed by that crate from `Config::flag`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Flag {
+    pub name: &'static str,
+    /// Placeholder for the value in help text, e.g. `<path>`.
+    pub value: &'static str,
+    pub env: &'static str,
+    pub help: &'static str,
+}
+
+/// An `idle_timeout` for protocols whose clients have no reason to stay silent, which crates
+/// opt into in their `default_config`. Unset by default, since a silent client is normal for
+/// some protocols, e.g. one waiting on a job_centre `get`.
+pub const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Help text for the flags understood by `Config::apply`.
+pub const USAGE: &str = "Options:
+  --bind <address>            Address to listen on [env: PROTOHACKERS_BIND]
+  --port <port>               Port to listen on [env: PROTOHACKERS_PORT]
+  --workers <count>           Number of pool workers [env: PROTOHACKERS_WORKERS]
+  --read-timeout <duration>   Per-connection read timeout, e.g. 30s or 100ms; 0 disables [env: PROTOHACKERS_READ_TIMEOUT]
+  --write-timeout <duration>  Per-connection write timeout [env: PROTOHACKERS_WRITE_TIMEOUT]
+  --idle-timeout <duration>   Close connections that send nothing for this long [env: PROTOHACKERS_IDLE_TIMEOUT]
+  --max-connections <count>   Refuse connections beyond this many open at once [env: PROTOHACKERS_MAX_CONNECTIONS]
+  --max-connections-per-ip <count>
+                              Refuse connections beyond this many from one address [env: PROTOHACKERS_MAX_CONNECTIONS_PER_IP]
+  --shutdown-timeout <duration>
+                              Time allowed for connections to finish on shutdown [env: PROTOHACKERS_SHUTDOWN_TIMEOUT]
+  --max-line-length <bytes>   Longest request line accepted by line-based protocols [env: PROTOHACKERS_MAX_LINE_LENGTH]
+  --log-level <level>         error, warn, info or debug [env: PROTOHACKERS_LOG_LEVEL]
+  --log-format <format>       text or json [env: PROTOHACKERS_LOG_FORMAT]
+  --metrics-port <port>       Serve Prometheus metrics at /metrics on this port [env: PROTOHACKERS_METRICS_PORT]
+  --metrics-bind <address>    Address to serve metrics on; defaults to loopback [env: PROTOHACKERS_METRICS_BIND]
+  --tls-cert <path>           PEM certificate chain; serves TCP over TLS with --tls-key [env: PROTOHACKERS_TLS_CERT]
+  --tls-key <path>            PEM private key for --tls-cert [env: PROTOHACKERS_TLS_KEY]
+  --help                      Print this message";
+
+impl Flag {
+    /// This flag's line of help text, laid out like `USAGE`.
+    pub fn usage(&self) -> String {
+        let left = format!("  --{} {}", self.name, self.value);
+        let help = format!("{} [env: {}]", self.help, self.env);
+
+        if left.len() < 30 {
+            format!("{left:<30}{help}")
+        } else {
+            format!("{left}\n{:30}{help}", "")
+        }
+    }
+}
+
+/// The flags `Config::set` handles itself, with their environment variables.
+const SHARED_FLAGS: [(&str, &str); 16] = [
+    ("PROTOHACKERS_BIND", "bind"),
+    ("PROTOHACKERS_PORT", "port"),
+    ("PROTOHACKERS_WORKERS", "workers"),
+    ("PROTOHACKERS_READ_TIMEOUT", "read-timeout"),
+    ("PROTOHACKERS_WRITE_TIMEOUT", "write-timeout"),
+    ("PROTOHACKERS_IDLE_TIMEOUT", "idle-timeout"),
+    ("PROTOHACKERS_MAX_CONNECTIONS", "max-connections"),
+    (
+        "PROTOHACKERS_MAX_CONNECTIONS_PER_IP",
+        "max-connections-per-ip",
+    ),
+    ("PROTOHACKERS_SHUTDOWN_TIMEOUT", "shutdown-timeout"),
+    ("PROTOHACKERS_MAX_LINE_LENGTH", "max-line-length"),
+    ("PROTOHACKERS_LOG_LEVEL", "log-level"),
+    ("PROTOHACKERS_LOG_FORMAT", "log-format"),
+    ("PROTOHACKERS_METRICS_PORT", "metrics-port"),
+    ("PROTOHACKERS_METRICS_BIND", "metrics-bind"),
+    ("PROTOHACKERS_TLS_CERT", "tls-cert"),
+    ("PROTOHACKERS_TLS_KEY", "tls-key"),
+];
+
+impl Config {
+    /// Defaults used when neither a flag nor an environment variable is set.
+    pub fn new(workers: usize) -> Config {
+        Config {
+            bind: "0.0.0.0".to_string(),
+            port: 8080,
+            workers,
+            read_timeout: None,
+            write_timeout: None,
+            idle_timeout: None,
+            max_connections: None,
+            max_connections_per_ip: None,
+            shutdown_timeout: Duration::from_secs(5),
+            max_line_length: DEFAULT_MAX_LINE_LENGTH,
+            log_level: Level::Info,
+            log_format: Format::Text,
+            metrics_port: None,
+            metrics_bind: "127.0.0.1".to_string(),
+            tls_cert: None,
+            tls_key: None,
+            extra_flags: &[],
+            extras: BTreeMap::new(),
+        }
+    }
+
+    /// Registers `flags` on top of the shared ones, so that `apply` accepts them.
+    pub fn with_flags(mut self, flags: &'static [Flag]) -> Config {
+        self.extra_flags = flags;
+        self
+    }
+
+    /// Applies the process environment and command-line arguments on top of `self` and
+    /// initialises logging from the result. Prints usage and exits on `--help`.
+    pub fn load(self) -> Result<Config> {
+        self.load_args(env::args().skip(1).collect())
+    }
+
+    /// Like `load`, but with `args` in place of the process's own, for binaries that take
+    /// flags of their own as well.
+    pub fn load_args(self, args: Vec<String>) -> Result<Config> {
+        if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+            println!("{}", self.usage());
+            process::exit(0);
+        }
+
+        let config = self.apply(|key| env::var(key).ok(), args)?;
+        log::init(config.log_level, config.log_format);
+
+        Ok(config)
+    }
+
+    /// Applies variables looked up through `env`, then `args`, on top of `self`.
+    pub fn apply<E, I>(mut self, env: E, args: I) -> Result<Config>
+    where
+        E: Fn(&str) -> Option<String>,
+        I: IntoIterator<Item = String>,
+    {
+        for (key, flag) in SHARED_FLAGS
+            .into_iter()
+            .chain(self.extra_flags.iter().map(|flag| (flag.env, flag.name)))
+        {
+            if let Some(value) = env(key) {
+                self.set(flag, &value)?;
+            }
+        }
+
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            let Some(flag) = arg.strip_prefix("--") else {
+                return Err(invalid(format!("Unexpected argument: {arg}")));
+            };
+
+            match flag.split_once('=') {
+                Some((flag, value)) => self.set(flag, value)?,
+                None => match args.next() {
+                    Some(value) => self.set(flag, &value)?,
+                    None => return Err(invalid(format!("Missing value for --{flag}"))),
+                },
+            }
+        }
+
+        if self.tls_cert.is_some() != self.tls_key.is_some() {
+            return Err(invalid(
+                "--tls-cert and --tls-key must be given together".to_string(),
+            ));
+        }
+
+        Ok(self)
+    }
+
+    /// Whether `apply` accepts `--flag`.
+    pub fn knows(&self, flag: &str) -> bool {
+        SHARED_FLAGS.iter().any(|&(_, name)| name == flag)
+            || self.extra_flags.iter().any(|extra| extra.name == flag)
+    }
+
+    /// The value given for one of `extra_flags`, if any.
+    pub fn flag(&self, name: &str) -> Option<&str> {
+        self.extras.get(name).map(String::as_str)
+    }
+
+    /// `USAGE`, followed by help for `extra_flags`.
+    pub fn usage(&self) -> String {
+        let mut usage = USAGE.to_string();
+
+        for flag in self.extra_flags {
+            usage.push('\n');
+            usage.push_str(&flag.usage());
+        }
+
+        usage
+    }
+
+    /// The `bind:port` pair to hand to `TcpListener::bind` or `UdpSocket::bind`.
+    pub fn address(&self) -> String {
+        format!("{}:{}", self.bind, self.port)
+    }
+
+    /// Where to serve metrics, if `--metrics-port` was given.
+    pub fn metrics_address(&self) -> Option<String> {
+        self.metrics_port
+            .map(|port| format!("{}:{}", self.metrics_bind, port))
+    }
+
+    /// The certificate and key paths, if TLS was configured.
+    pub fn tls(&self) -> Option<(PathBuf, PathBuf)> {
+        self.tls_cert.clone().zip(self.tls_key.clone())
+    }
+
+    fn set(&mut self, flag: &str, value: &str) -> Result<()> {
+        match flag {
+            "bind" => self.bind = value.to_string(),
+            "port" => self.port = parse(flag, value)?,
+            "workers" => self.workers = parse(flag, value)?,
+            "read-timeout" => self.read_timeout = parse_timeout(flag, value)?,
+            "write-timeout" => self.write_timeout = parse_timeout(flag, value)?,
+            "idle-timeout" => self.idle_timeout = parse_timeout(flag, value)?,
+            "max-connections" => self.max_connections = Some(parse(flag, value)?),
+            "max-connections-per-ip" => self.max_connections_per_ip = Some(parse(flag, value)?),
+            "shutdown-timeout" => self.shutdown_timeout = parse_duration(flag, value)?,
+            "max-line-length" => self.max_line_length = parse(flag, value)?,
+            "log-level" => self.log_level = parse(flag, value)?,
+            "log-format" => self.log_format = parse(flag, value)?,
+            "metrics-port" => self.metrics_port = Some(parse(flag, value)?),
+            "metrics-bind" => self.metrics_bind = value.to_string(),
+            "tls-cert" => self.tls_cert = Some(PathBuf::from(value)),
+            "tls-key" => self.tls_key = Some(PathBuf::from(value)),
+            _ => match self.extra_flags.iter().find(|extra| extra.name == flag) {
+                Some(extra) => {
+                    self.extras.insert(extra.name, value.to_string());
+                }
+                None => return Err(invalid(format!("Unknown option: --{flag}"))),
+            },
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses the value of `--flag`, for crates reading their `extra_flags`.
+pub fn parse<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T> {
+    value
+        .parse()
+        .map_err(|_| invalid(format!("Invalid value for --{flag}: {value}")))
+}
+
+/// Parses `100ms`, `30s` or a bare number of seconds, given as the value of `--flag`.
+pub fn parse_duration(flag: &str, value: &str) -> Result<Duration> {
+    if let Some(millis) = value.strip_suffix("ms") {
+        Ok(Duration::from_millis(parse(flag, millis)?))
+    } else {
+        let seconds = value.strip_suffix('s').unwrap_or(value);
+        Ok(Duration::from_secs(parse(flag, seconds)?))
+    }
+}
+
+/// Like `parse_duration`, but a zero duration means no timeout.
+fn parse_timeout(flag: &str, value: &str) -> Result<Option<Duration>> {
+    Ok(Some(parse_duration(flag, value)?).filter(|timeout| !timeout.is_zero()))
+}
+
+fn invalid(message: String) -> Error {
+    Error::new(ErrorKind::InvalidInput, message)
+}
@@ -1 +1,16 @@
+#[cfg(feature = "async")]
+pub mod async_server;
+#[cfg(feature = "bench")]
+pub mod bench;
+pub mod codec;
+pub mod config;
+pub mod line;
+pub mod log;
+pub mod metrics;
 pub mod pool;
+pub mod server;
+pub mod signal;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "tls")]
+mod tls;
@@ -0,0 +1,225 @@
+//! Line reading with an upper bound on line length, for protocols whose clients could
+//! otherwise grow a server's buffers indefinitely by never sending a newline.
+//!
+//! A line over the limit is reported as an `InvalidData` error wrapping `LineTooLong`, which
+//! `is_too_long` recognises. The rest of that line is then skipped, so a handler can answer
+//! with its protocol's error and carry on reading from the next line.
+
+use std::{
+    error,
+    fmt::{self, Display},
+    io::{BufRead, Error, ErrorKind, Result},
+    mem,
+};
+#[cfg(feature = "async")]
+use tokio::io::{AsyncBufRead, AsyncBufReadExt};
+
+/// The longest line accepted unless configured otherwise, in bytes, including the newline.
+pub const DEFAULT_MAX_LINE_LENGTH: usize = 64 * 1024;
+
+#[derive(Debug)]
+pub struct LineTooLong {
+    pub max: usize,
+}
+
+impl Display for LineTooLong {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Line longer than {} bytes", self.max)
+    }
+}
+
+impl error::Error for LineTooLong {}
+
+/// Whether `error` came from a line exceeding its reader's limit.
+pub fn is_too_long(error: &Error) -> bool {
+    error
+        .get_ref()
+        .is_some_and(|inner| inner.is::<LineTooLong>())
+}
+
+/// Wraps a `BufRead` with `read_line` and `lines` that refuse lines longer than `max`. A read
+/// that fails part way through a line, e.g. on a timeout, keeps what it has for the next call.
+pub struct LineReader<R> {
+    reader: R,
+    limit: Limit,
+}
+
+impl<R: BufRead> LineReader<R> {
+    pub fn new(reader: R, max: usize) -> LineReader<R> {
+        LineReader {
+            reader,
+            limit: Limit::new(max),
+        }
+    }
+
+    /// Like `BufRead::read_line`: appends the next line, including its newline, to `buffer`
+    /// and returns its length, or 0 at end of input.
+    pub fn read_line(&mut self, buffer: &mut String) -> Result<usize> {
+        loop {
+            let available = match self.reader.fill_buf() {
+                Ok(available) => available,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+
+            let (consumed, line) = self.limit.scan(available);
+            self.reader.consume(consumed);
+
+            if let Some(line) = line {
+                line?;
+                return self.limit.finish(buffer);
+            }
+        }
+    }
+
+    /// Like `BufRead::lines`: each line without its newline.
+    pub fn lines(self) -> Lines<R> {
+        Lines { reader: self }
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// The underlying reader, for reading anything other than lines. Bytes of a line that
+    /// has only partly arrived are not visible through it.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+}
+
+pub struct Lines<R> {
+    reader: LineReader<R>,
+}
+
+impl<R: BufRead> Iterator for Lines<R> {
+    type Item = Result<String>;
+
+    fn next(&mut self) -> Option<Result<String>> {
+        let mut line = String::new();
+
+        match self.reader.read_line(&mut line) {
+            Ok(0) => None,
+            Ok(_) => Some(Ok(strip_newline(line))),
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+/// The asynchronous counterpart of `LineReader`.
+#[cfg(feature = "async")]
+pub struct AsyncLineReader<R> {
+    reader: R,
+    limit: Limit,
+}
+
+#[cfg(feature = "async")]
+impl<R: AsyncBufRead + Unpin> AsyncLineReader<R> {
+    pub fn new(reader: R, max: usize) -> AsyncLineReader<R> {
+        AsyncLineReader {
+            reader,
+            limit: Limit::new(max),
+        }
+    }
+
+    /// Like `AsyncBufReadExt::read_line`. Cancel safe: a partial line stays buffered.
+    pub async fn read_line(&mut self, buffer: &mut String) -> Result<usize> {
+        loop {
+            let available = self.reader.fill_buf().await?;
+
+            let (consumed, line) = self.limit.scan(available);
+            self.reader.consume(consumed);
+
+            if let Some(line) = line {
+                line?;
+                return self.limit.finish(buffer);
+            }
+        }
+    }
+
+    /// Like `AsyncBufReadExt::lines`' `next_line`: the next line without its newline, or
+    /// `None` at end of input.
+    pub async fn next_line(&mut self) -> Result<Option<String>> {
+        let mut line = String::new();
+
+        match self.read_line(&mut line).await? {
+            0 => Ok(None),
+            _ => Ok(Some(strip_newline(line))),
+        }
+    }
+}
+
+/// The state shared by both readers: the line so far, and whether the rest of an overlong
+/// line is being skipped.
+struct Limit {
+    max: usize,
+    line: Vec<u8>,
+    skipping: bool,
+}
+
+impl Limit {
+    fn new(max: usize) -> Limit {
+        Limit {
+            max,
+            line: Vec::new(),
+            skipping: false,
+        }
+    }
+
+    /// Takes what it can from the front of `available`, returning how many bytes it took and,
+    /// once a line is complete or too long, whether it was accepted. Empty `available` means
+    /// end of input, which completes any partial line.
+    fn scan(&mut self, available: &[u8]) -> (usize, Option<Result<()>>) {
+        if available.is_empty() {
+            self.skipping = false;
+            return (0, Some(Ok(())));
+        }
+
+        let (taken, ended) = match available.iter().position(|&byte| byte == b'\n') {
+            Some(newline) => (newline + 1, true),
+            None => (available.len(), false),
+        };
+
+        if self.skipping {
+            self.skipping = !ended;
+            return (taken, None);
+        }
+
+        if self.line.len() + taken > self.max {
+            self.line.clear();
+            self.skipping = !ended;
+
+            let error = Error::new(ErrorKind::InvalidData, LineTooLong { max: self.max });
+            return (taken, Some(Err(error)));
+        }
+
+        self.line.extend_from_slice(&available[..taken]);
+
+        (taken, ended.then_some(Ok(())))
+    }
+
+    /// Moves the completed line into `buffer`.
+    fn finish(&mut self, buffer: &mut String) -> Result<usize> {
+        let line = mem::take(&mut self.line);
+
+        match String::from_utf8(line) {
+            Ok(line) => {
+                buffer.push_str(&line);
+                Ok(line.len())
+            }
+            Err(e) => Err(Error::new(ErrorKind::InvalidData, e.utf8_error())),
+        }
+    }
+}
+
+fn strip_newline(mut line: String) -> String {
+    if line.ends_with('\n') {
+        line.pop();
+
+        if line.ends_with('\r') {
+            line.pop();
+        }
+    }
+
+    line
+}
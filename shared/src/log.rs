@@ -0,0 +1,237 @@
+use std::{
+    cell::Cell,
+    fmt::{self, Write as _},
+    io::{self, Write},
+    net::SocketAddr,
+    str::FromStr,
+    sync::atomic::{AtomicU8, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error = 1,
+    Warn = 2,
+    Info = 3,
+    Debug = 4,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+static LEVEL: AtomicU8 = AtomicU8::new(Level::Info as u8);
+static JSON: AtomicU8 = AtomicU8::new(0);
+
+thread_local! {
+    static WORKER: Cell<Option<usize>> = const { Cell::new(None) };
+    static PEER: Cell<Option<SocketAddr>> = const { Cell::new(None) };
+}
+
+/// Clears the current thread's peer address when dropped; see `connection`.
+pub struct ConnectionScope {
+    previous: Option<SocketAddr>,
+}
+
+/// Sets the maximum level that gets written and the output format for the whole process.
+pub fn init(level: Level, format: Format) {
+    LEVEL.store(level as u8, Ordering::Relaxed);
+    JSON.store((format == Format::Json) as u8, Ordering::Relaxed);
+}
+
+pub fn enabled(level: Level) -> bool {
+    level as u8 <= LEVEL.load(Ordering::Relaxed)
+}
+
+/// Tags every line logged from the current thread with `worker`.
+pub fn set_worker(worker: usize) {
+    WORKER.with(|cell| cell.set(Some(worker)));
+}
+
+/// Tags every line logged from the current thread with `peer` until the scope is dropped.
+pub fn connection(peer: SocketAddr) -> ConnectionScope {
+    ConnectionScope {
+        previous: PEER.with(|cell| cell.replace(Some(peer))),
+    }
+}
+
+impl Drop for ConnectionScope {
+    fn drop(&mut self) {
+        PEER.with(|cell| cell.set(self.previous));
+    }
+}
+
+/// Writes one line to stderr. Use the `error!`, `warn!`, `info!` and `debug!` macros instead.
+pub fn write(level: Level, target: &str, args: fmt::Arguments) {
+    if !enabled(level) {
+        return;
+    }
+
+    let format = match JSON.load(Ordering::Relaxed) {
+        1 => Format::Json,
+        _ => Format::Text,
+    };
+
+    let mut line = render(level, format, target, args);
+    line.push('\n');
+    io::stderr().lock().write_all(line.as_bytes()).ok();
+}
+
+/// Formats one line as `write` would, without the trailing newline, tagged with the current
+/// thread's worker and peer.
+pub fn render(level: Level, format: Format, target: &str, args: fmt::Arguments) -> String {
+    let timestamp = timestamp();
+    let worker = WORKER.with(Cell::get);
+    let peer = PEER.with(Cell::get);
+
+    let mut line = String::new();
+
+    if format == Format::Json {
+        write!(
+            line,
+            r#"{{"timestamp":"{timestamp}","level":"{level}","target":"{}","message":"{}""#,
+            escape(target),
+            escape(&args.to_string())
+        )
+        .ok();
+
+        if let Some(worker) = worker {
+            write!(line, r#","worker":{worker}"#).ok();
+        }
+
+        if let Some(peer) = peer {
+            write!(line, r#","peer":"{peer}""#).ok();
+        }
+
+        line.push('}');
+    } else {
+        write!(line, "{timestamp} {:<5} {target}", level.to_string()).ok();
+
+        if let Some(worker) = worker {
+            write!(line, " worker={worker}").ok();
+        }
+
+        if let Some(peer) = peer {
+            write!(line, " peer={peer}").ok();
+        }
+
+        write!(line, ": {args}").ok();
+    }
+
+    line
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+        })
+    }
+}
+
+impl FromStr for Level {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Level, ()> {
+        match s.to_lowercase().as_str() {
+            "error" => Ok(Level::Error),
+            "warn" => Ok(Level::Warn),
+            "info" => Ok(Level::Info),
+            "debug" => Ok(Level::Debug),
+            _ => Err(()),
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Format, ()> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            _ => Err(()),
+        }
+    }
+}
+
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str(r#"\""#),
+            '\\' => escaped.push_str(r"\\"),
+            '\n' => escaped.push_str(r"\n"),
+            '\r' => escaped.push_str(r"\r"),
+            '\t' => escaped.push_str(r"\t"),
+            c if c.is_control() => write!(escaped, "\\u{:04x}", c as u32).unwrap(),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// Current UTC time as RFC 3339 with millisecond precision.
+fn timestamp() -> String {
+    let elapsed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let seconds = elapsed.as_secs();
+    let (hour, minute, second) = (seconds / 3600 % 24, seconds / 60 % 60, seconds % 60);
+
+    // Civil-from-days conversion, see http://howardhinnant.github.io/date_algorithms.html
+    let days = (seconds / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{hour:02}:{minute:02}:{second:02}.{:03}Z",
+        elapsed.subsec_millis()
+    )
+}
+
+#[macro_export]
+macro_rules! error {
+    ($($arg:tt)+) => {
+        $crate::log::write($crate::log::Level::Error, module_path!(), format_args!($($arg)+))
+    };
+}
+
+#[macro_export]
+macro_rules! warn {
+    ($($arg:tt)+) => {
+        $crate::log::write($crate::log::Level::Warn, module_path!(), format_args!($($arg)+))
+    };
+}
+
+#[macro_export]
+macro_rules! info {
+    ($($arg:tt)+) => {
+        $crate::log::write($crate::log::Level::Info, module_path!(), format_args!($($arg)+))
+    };
+}
+
+#[macro_export]
+macro_rules! debug {
+    ($($arg:tt)+) => {
+        $crate::log::write($crate::log::Level::Debug, module_path!(), format_args!($($arg)+))
+    };
+}
@@ -0,0 +1,369 @@
+use crate::{debug, error, info, line::LineReader, warn};
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    io::{prelude::*, BufReader, Result},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicI64, AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+    thread,
+    time::Duration,
+};
+
+/// Scrapes answered at once; connections beyond this are closed unanswered.
+const MAX_SCRAPES: usize = 8;
+
+/// Longest request or header line read from a scrape.
+const MAX_REQUEST_LINE: usize = 8 * 1024;
+
+/// Most header lines read from a scrape.
+const MAX_HEADERS: usize = 100;
+
+/// How long a scrape may take to send its request or read the response.
+const SCRAPE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Upper bounds, in seconds, used by `histogram` callers that have no better idea.
+pub const DEFAULT_BUCKETS: &[f64] = &[
+    0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 10.0, 30.0, 60.0, 300.0,
+];
+
+#[derive(Default)]
+pub struct Counter {
+    value: AtomicU64,
+}
+
+#[derive(Default)]
+pub struct Gauge {
+    value: AtomicI64,
+}
+
+pub struct Histogram {
+    buckets: Vec<f64>,
+    state: Mutex<HistogramState>,
+}
+
+#[derive(Default)]
+struct HistogramState {
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+#[derive(Clone)]
+enum Metric {
+    Counter(Arc<Counter>),
+    Gauge(Arc<Gauge>),
+    Histogram(Arc<Histogram>),
+}
+
+struct Family {
+    help: String,
+    kind: &'static str,
+    series: BTreeMap<String, Metric>,
+}
+
+/// Process-wide collection of metrics, rendered in the Prometheus text format.
+#[derive(Default)]
+pub struct Registry {
+    families: Mutex<BTreeMap<String, Family>>,
+}
+
+impl Counter {
+    pub fn inc(&self) {
+        self.add(1);
+    }
+
+    pub fn add(&self, n: u64) {
+        self.value.fetch_add(n, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.value.load(Ordering::Relaxed)
+    }
+}
+
+impl Gauge {
+    pub fn inc(&self) {
+        self.add(1);
+    }
+
+    pub fn dec(&self) {
+        self.add(-1);
+    }
+
+    pub fn add(&self, n: i64) {
+        self.value.fetch_add(n, Ordering::SeqCst);
+    }
+
+    pub fn set(&self, n: i64) {
+        self.value.store(n, Ordering::SeqCst);
+    }
+
+    pub fn get(&self) -> i64 {
+        self.value.load(Ordering::SeqCst)
+    }
+}
+
+impl Histogram {
+    fn new(buckets: &[f64]) -> Histogram {
+        Histogram {
+            buckets: buckets.to_vec(),
+            state: Mutex::new(HistogramState {
+                counts: vec![0; buckets.len()],
+                ..Default::default()
+            }),
+        }
+    }
+
+    pub fn observe(&self, value: f64) {
+        let mut state = self.state.lock().unwrap();
+
+        if let Some(index) = self.buckets.iter().position(|bound| value <= *bound) {
+            state.counts[index] += 1;
+        }
+
+        state.sum += value;
+        state.count += 1;
+    }
+
+    pub fn observe_duration(&self, duration: Duration) {
+        self.observe(duration.as_secs_f64());
+    }
+}
+
+impl Registry {
+    pub fn counter(&self, name: &str, help: &str, labels: &[(&str, &str)]) -> Arc<Counter> {
+        match self.register(name, help, "counter", labels, || {
+            Metric::Counter(Arc::default())
+        }) {
+            Metric::Counter(counter) => counter,
+            _ => panic!("Metric {name} is not a counter"),
+        }
+    }
+
+    pub fn gauge(&self, name: &str, help: &str, labels: &[(&str, &str)]) -> Arc<Gauge> {
+        match self.register(
+            name,
+            help,
+            "gauge",
+            labels,
+            || Metric::Gauge(Arc::default()),
+        ) {
+            Metric::Gauge(gauge) => gauge,
+            _ => panic!("Metric {name} is not a gauge"),
+        }
+    }
+
+    pub fn histogram(
+        &self,
+        name: &str,
+        help: &str,
+        labels: &[(&str, &str)],
+        buckets: &[f64],
+    ) -> Arc<Histogram> {
+        match self.register(name, help, "histogram", labels, || {
+            Metric::Histogram(Arc::new(Histogram::new(buckets)))
+        }) {
+            Metric::Histogram(histogram) => histogram,
+            _ => panic!("Metric {name} is not a histogram"),
+        }
+    }
+
+    /// Returns the series for `name` and `labels`, creating it on first use.
+    fn register<F>(
+        &self,
+        name: &str,
+        help: &str,
+        kind: &'static str,
+        labels: &[(&str, &str)],
+        create: F,
+    ) -> Metric
+    where
+        F: FnOnce() -> Metric,
+    {
+        let mut families = self.families.lock().unwrap();
+
+        let family = families.entry(name.to_string()).or_insert_with(|| Family {
+            help: help.to_string(),
+            kind,
+            series: BTreeMap::new(),
+        });
+
+        assert_eq!(family.kind, kind, "Metric {name} registered twice");
+
+        family
+            .series
+            .entry(render_labels(labels))
+            .or_insert_with(create)
+            .clone()
+    }
+
+    /// Renders every metric in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let families = self.families.lock().unwrap();
+        let mut output = String::new();
+
+        for (name, family) in families.iter() {
+            writeln!(output, "# HELP {name} {}", family.help).ok();
+            writeln!(output, "# TYPE {name} {}", family.kind).ok();
+
+            for (labels, metric) in family.series.iter() {
+                match metric {
+                    Metric::Counter(counter) => {
+                        writeln!(output, "{name}{} {}", braces(labels), counter.get()).ok();
+                    }
+                    Metric::Gauge(gauge) => {
+                        writeln!(output, "{name}{} {}", braces(labels), gauge.get()).ok();
+                    }
+                    Metric::Histogram(histogram) => {
+                        render_histogram(&mut output, name, labels, histogram);
+                    }
+                }
+            }
+        }
+
+        output
+    }
+}
+
+fn render_histogram(output: &mut String, name: &str, labels: &str, histogram: &Histogram) {
+    let state = histogram.state.lock().unwrap();
+    let separator = if labels.is_empty() { "" } else { "," };
+    let mut cumulative = 0;
+
+    for (bound, count) in histogram.buckets.iter().zip(state.counts.iter()) {
+        cumulative += count;
+        writeln!(
+            output,
+            "{name}_bucket{{{labels}{separator}le=\"{bound}\"}} {cumulative}"
+        )
+        .ok();
+    }
+
+    writeln!(
+        output,
+        "{name}_bucket{{{labels}{separator}le=\"+Inf\"}} {}",
+        state.count
+    )
+    .ok();
+    writeln!(output, "{name}_sum{} {}", braces(labels), state.sum).ok();
+    writeln!(output, "{name}_count{} {}", braces(labels), state.count).ok();
+}
+
+fn render_labels(labels: &[(&str, &str)]) -> String {
+    labels
+        .iter()
+        .map(|(key, value)| {
+            let value = value
+                .replace('\\', r"\\")
+                .replace('"', r#"\""#)
+                .replace('\n', r"\n");
+            format!("{key}=\"{value}\"")
+        })
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+fn braces(labels: &str) -> String {
+    if labels.is_empty() {
+        String::new()
+    } else {
+        format!("{{{labels}}}")
+    }
+}
+
+/// The registry shared by the whole process.
+pub fn registry() -> &'static Registry {
+    static REGISTRY: OnceLock<Registry> = OnceLock::new();
+
+    REGISTRY.get_or_init(Registry::default)
+}
+
+pub fn counter(name: &str, help: &str, labels: &[(&str, &str)]) -> Arc<Counter> {
+    registry().counter(name, help, labels)
+}
+
+pub fn gauge(name: &str, help: &str, labels: &[(&str, &str)]) -> Arc<Gauge> {
+    registry().gauge(name, help, labels)
+}
+
+pub fn histogram(
+    name: &str,
+    help: &str,
+    labels: &[(&str, &str)],
+    buckets: &[f64],
+) -> Arc<Histogram> {
+    registry().histogram(name, help, labels, buckets)
+}
+
+/// Serves `GET /metrics` from the global registry on `address` in a background thread, each
+/// scrape on a thread of its own, and returns the address bound.
+pub fn serve(address: &str) -> Result<SocketAddr> {
+    let listener = TcpListener::bind(address)?;
+    let address = listener.local_addr()?;
+    info!("Serving metrics on {}", address);
+
+    thread::spawn(move || {
+        let scrapes = Arc::new(AtomicUsize::new(0));
+
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    error!("Metrics listener error: {}", e);
+                    continue;
+                }
+            };
+
+            if scrapes.fetch_add(1, Ordering::SeqCst) >= MAX_SCRAPES {
+                scrapes.fetch_sub(1, Ordering::SeqCst);
+                warn!("{MAX_SCRAPES} metrics scrapes already running; closing connection");
+                continue;
+            }
+
+            let scrapes = Arc::clone(&scrapes);
+
+            thread::spawn(move || {
+                if let Err(e) = respond(stream) {
+                    debug!("Metrics request error: {}", e);
+                }
+
+                scrapes.fetch_sub(1, Ordering::SeqCst);
+            });
+        }
+    });
+
+    Ok(address)
+}
+
+fn respond(stream: TcpStream) -> Result<()> {
+    stream.set_read_timeout(Some(SCRAPE_TIMEOUT))?;
+    stream.set_write_timeout(Some(SCRAPE_TIMEOUT))?;
+
+    let mut reader = LineReader::new(BufReader::new(&stream), MAX_REQUEST_LINE);
+    let mut writer = &stream;
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+
+    let mut header = String::new();
+    for _ in 0..MAX_HEADERS {
+        if reader.read_line(&mut header)? <= 2 {
+            break;
+        }
+        header.clear();
+    }
+
+    let (status, body) = match request_line.split(' ').collect::<Vec<&str>>()[..] {
+        ["GET", "/metrics", ..] => ("200 OK", registry().render()),
+        _ => ("404 Not Found", "Not Found\n".to_string()),
+    };
+
+    write!(
+        writer,
+        "HTTP/1.1 {status}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )
+}
@@ -1,32 +1,120 @@
+use crate::{
+    debug, error, log,
+    metrics::{self, Counter, Gauge},
+    warn,
+};
 use std::{
-    sync::{mpsc, Arc, Mutex},
+    any::Any,
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc::{self, TrySendError},
+        Arc, Mutex,
+    },
     thread,
+    time::{Duration, Instant},
 };
 
 pub struct ThreadPool {
     workers: Vec<Worker>,
-    sender: Option<mpsc::Sender<Job>>,
+    sender: Option<Sender>,
+    queue: Arc<Queue>,
+    overflow: Overflow,
+    overflow_workers: Mutex<Vec<Worker>>,
+    next_overflow_id: AtomicUsize,
+    closed: Arc<AtomicBool>,
+}
+
+/// What `ThreadPool::execute` does when a bounded queue is full.
+pub enum Overflow {
+    /// Wait until a worker frees up a slot in the queue.
+    Block,
+    /// Drop the job and call the callback with the current queue depth.
+    Reject(Box<dyn Fn(usize) + Send + Sync>),
+    /// Run the job on a temporary thread, blocking once `max` of them are running.
+    Spawn { max: usize },
+}
+
+/// Cloneable handle used to stop a pool from accepting new jobs, e.g. from a signal handler.
+#[derive(Clone)]
+pub struct ShutdownHandle {
+    closed: Arc<AtomicBool>,
+}
+
+/// Outcome of `ThreadPool::shutdown`: which workers exited and which were still busy at the deadline.
+#[derive(Debug, Default)]
+pub struct ShutdownReport {
+    pub finished: Vec<usize>,
+    pub busy: Vec<usize>,
 }
 
 type Job = Box<dyn FnOnce(usize) + Send + 'static>;
 
+enum Sender {
+    Unbounded(mpsc::Sender<Job>),
+    Bounded(mpsc::SyncSender<Job>),
+}
+
+struct Queue {
+    receiver: Mutex<mpsc::Receiver<Job>>,
+    metrics: PoolMetrics,
+}
+
+/// Series exported for each pool, labelled with the order in which pools were created.
+struct PoolMetrics {
+    depth: Arc<Gauge>,
+    busy: Arc<Gauge>,
+    jobs: Arc<Counter>,
+    panics: Arc<Counter>,
+    rejected: Arc<Counter>,
+}
+
+static NEXT_POOL_ID: AtomicUsize = AtomicUsize::new(0);
+
+const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
 impl ThreadPool {
     pub fn new(size: usize) -> ThreadPool {
-        assert!(size > 0);
-
         let (sender, receiver) = mpsc::channel();
 
-        let receiver = Arc::new(Mutex::new(receiver));
+        ThreadPool::with_sender(size, Sender::Unbounded(sender), receiver, Overflow::Block)
+    }
+
+    /// Creates a pool whose queue holds at most `capacity` pending jobs, handling any
+    /// further jobs according to `overflow`.
+    pub fn bounded(size: usize, capacity: usize, overflow: Overflow) -> ThreadPool {
+        let (sender, receiver) = mpsc::sync_channel(capacity);
+
+        ThreadPool::with_sender(size, Sender::Bounded(sender), receiver, overflow)
+    }
+
+    fn with_sender(
+        size: usize,
+        sender: Sender,
+        receiver: mpsc::Receiver<Job>,
+        overflow: Overflow,
+    ) -> ThreadPool {
+        assert!(size > 0);
+
+        let queue = Arc::new(Queue {
+            receiver: Mutex::new(receiver),
+            metrics: PoolMetrics::new(size),
+        });
 
         let mut workers = Vec::with_capacity(size);
 
         for id in 0..size {
-            workers.push(Worker::new(id, Arc::clone(&receiver)));
+            workers.push(Worker::new(id, Arc::clone(&queue)));
         }
 
         ThreadPool {
             workers,
             sender: Some(sender),
+            queue,
+            overflow,
+            overflow_workers: Mutex::new(Vec::new()),
+            next_overflow_id: AtomicUsize::new(size),
+            closed: Arc::new(AtomicBool::new(false)),
         }
     }
 
@@ -34,9 +122,101 @@ impl ThreadPool {
     where
         F: FnOnce(usize) + Send + 'static,
     {
+        if self.closed.load(Ordering::SeqCst) {
+            warn!("Pool is shutting down; dropping job.");
+            return;
+        }
+
         let job = Box::new(f);
 
-        self.sender.as_ref().unwrap().send(job).unwrap();
+        // Counted before the job is sent, so that a worker taking it at once can't take the
+        // depth below zero, and uncounted again if it isn't queued after all.
+        self.queue.metrics.depth.inc();
+
+        let sender = match self.sender.as_ref().unwrap() {
+            Sender::Unbounded(sender) => {
+                sender.send(job).unwrap();
+                return;
+            }
+            Sender::Bounded(sender) => sender,
+        };
+
+        let job = match sender.try_send(job) {
+            Ok(()) => return,
+            Err(TrySendError::Full(job)) => job,
+            Err(TrySendError::Disconnected(_)) => panic!("All workers have exited"),
+        };
+
+        self.queue.metrics.depth.dec();
+
+        match &self.overflow {
+            Overflow::Block => self.send_blocking(sender, job),
+            Overflow::Reject(callback) => {
+                self.queue.metrics.rejected.inc();
+                callback(self.queue_depth());
+            }
+            Overflow::Spawn { max } => {
+                let mut overflow_workers = self.overflow_workers.lock().unwrap();
+                overflow_workers.retain(|worker| !worker.is_finished());
+
+                if overflow_workers.len() < *max {
+                    let id = self.next_overflow_id.fetch_add(1, Ordering::SeqCst);
+                    overflow_workers.push(Worker::overflow(id, job, Arc::clone(&self.queue)));
+                } else {
+                    drop(overflow_workers);
+                    self.send_blocking(sender, job);
+                }
+            }
+        }
+    }
+
+    /// Waits for room in the queue for `job`. Callers waiting here aren't counted in the depth
+    /// until their job is queued, which a worker may take first, briefly reading one low.
+    fn send_blocking(&self, sender: &mpsc::SyncSender<Job>, job: Job) {
+        sender.send(job).unwrap();
+        self.queue.metrics.depth.inc();
+    }
+
+    /// Number of jobs waiting for a worker.
+    pub fn queue_depth(&self) -> usize {
+        self.queue.metrics.depth.get().max(0) as usize
+    }
+
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        ShutdownHandle {
+            closed: Arc::clone(&self.closed),
+        }
+    }
+
+    /// Stops accepting jobs, lets queued and running jobs finish, and waits up to `timeout`
+    /// for every worker to exit. Workers still busy at the deadline are detached and reported.
+    pub fn shutdown(mut self, timeout: Duration) -> ShutdownReport {
+        self.closed.store(true, Ordering::SeqCst);
+        drop(self.sender.take());
+
+        let mut workers: Vec<Worker> = self.workers.drain(..).collect();
+        workers.append(&mut self.overflow_workers.lock().unwrap());
+
+        let deadline = Instant::now() + timeout;
+
+        while workers.iter().any(|worker| !worker.is_finished()) && Instant::now() < deadline {
+            thread::sleep(SHUTDOWN_POLL_INTERVAL);
+        }
+
+        let mut report = ShutdownReport::default();
+
+        for worker in workers {
+            if worker.is_finished() {
+                worker.join();
+
+                report.finished.push(worker.id);
+            } else {
+                debug!("Worker {} still busy at shutdown; abandoning.", worker.id);
+                report.busy.push(worker.id);
+            }
+        }
+
+        report
     }
 }
 
@@ -44,41 +224,170 @@ impl Drop for ThreadPool {
     fn drop(&mut self) {
         drop(self.sender.take());
 
-        for worker in &mut self.workers {
-            println!("Shutting down worker {}", worker.id);
+        let overflow_workers = self.overflow_workers.get_mut().unwrap();
 
-            if let Some(thread) = worker.thread.take() {
-                thread.join().unwrap();
-            }
+        for worker in self.workers.iter().chain(overflow_workers.iter()) {
+            debug!("Shutting down worker {}", worker.id);
+            worker.join();
+        }
+    }
+}
+
+impl ShutdownHandle {
+    pub fn shutdown(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_shutdown(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+    }
+}
+
+impl PoolMetrics {
+    fn new(size: usize) -> PoolMetrics {
+        let pool = NEXT_POOL_ID.fetch_add(1, Ordering::SeqCst).to_string();
+        let labels = [("pool", pool.as_str())];
+
+        metrics::gauge("pool_workers", "Number of permanent workers.", &labels).set(size as i64);
+
+        PoolMetrics {
+            depth: metrics::gauge("pool_queue_depth", "Jobs waiting for a worker.", &labels),
+            busy: metrics::gauge(
+                "pool_busy_workers",
+                "Workers currently running a job.",
+                &labels,
+            ),
+            jobs: metrics::counter("pool_jobs_total", "Jobs started by workers.", &labels),
+            panics: metrics::counter("pool_panics_total", "Jobs that panicked.", &labels),
+            rejected: metrics::counter(
+                "pool_rejected_total",
+                "Jobs dropped because the queue was full.",
+                &labels,
+            ),
         }
     }
+
+    /// Runs `job`, counting it and marking a worker busy for its duration.
+    fn run(&self, id: usize, job: Job) -> thread::Result<()> {
+        self.jobs.inc();
+        self.busy.inc();
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| job(id)));
+
+        self.busy.dec();
+
+        if result.is_err() {
+            self.panics.inc();
+        }
+
+        result
+    }
+}
+
+impl ShutdownReport {
+    pub fn is_clean(&self) -> bool {
+        self.busy.is_empty()
+    }
 }
 
 pub struct Worker {
     id: usize,
-    thread: Option<thread::JoinHandle<()>>,
+    thread: Arc<Mutex<Option<thread::JoinHandle<()>>>>,
 }
 
 impl Worker {
-    fn new(id: usize, receiver: Arc<Mutex<mpsc::Receiver<Job>>>) -> Worker {
-        let thread = thread::spawn(move || loop {
-            let message = receiver.lock().unwrap().recv();
-
-            match message {
-                Ok(job) => {
-                    println!("Worker {id} got a job; executing.");
-                    job(id);
-                }
-                Err(_) => {
-                    println!("Worker {id} disconnected; shutting down.");
-                    break;
+    fn new(id: usize, queue: Arc<Queue>) -> Worker {
+        let thread = Arc::new(Mutex::new(None));
+
+        Worker::spawn(id, queue, Arc::clone(&thread));
+
+        Worker { id, thread }
+    }
+
+    /// Starts the thread for worker `id` and stores its handle in `slot`. If a job panics,
+    /// the thread logs it, spawns its own replacement into the same slot and exits.
+    fn spawn(id: usize, queue: Arc<Queue>, slot: Arc<Mutex<Option<thread::JoinHandle<()>>>>) {
+        let mut handle = slot.lock().unwrap();
+        let respawn_slot = Arc::clone(&slot);
+
+        let thread = thread::Builder::new()
+            .name(format!("worker-{id}"))
+            .spawn(move || {
+                log::set_worker(id);
+
+                loop {
+                    let message = queue.receiver.lock().unwrap().recv();
+
+                    match message {
+                        Ok(job) => {
+                            queue.metrics.depth.dec();
+
+                            debug!("Worker {id} got a job; executing.");
+
+                            if let Err(payload) = queue.metrics.run(id, job) {
+                                error!(
+                                    "Worker {id} panicked: {}; respawning.",
+                                    panic_message(&payload)
+                                );
+                                Worker::spawn(id, queue, respawn_slot);
+                                break;
+                            }
+                        }
+                        Err(_) => {
+                            debug!("Worker {id} disconnected; shutting down.");
+                            break;
+                        }
+                    }
                 }
+            })
+            .unwrap();
+
+        *handle = Some(thread);
+    }
+
+    fn overflow(id: usize, job: Job, queue: Arc<Queue>) -> Worker {
+        let thread = thread::spawn(move || {
+            log::set_worker(id);
+            debug!("Overflow worker {id} got a job; executing.");
+
+            if let Err(payload) = queue.metrics.run(id, job) {
+                error!("Overflow worker {id} panicked: {}", panic_message(&payload));
             }
         });
 
         Worker {
             id,
-            thread: Some(thread),
+            thread: Arc::new(Mutex::new(Some(thread))),
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.thread
+            .lock()
+            .unwrap()
+            .as_ref()
+            .is_none_or(|thread| thread.is_finished())
+    }
+
+    /// Joins the worker's thread, following any replacement spawned while waiting.
+    fn join(&self) {
+        loop {
+            let thread = self.thread.lock().unwrap().take();
+
+            match thread {
+                Some(thread) => thread.join().unwrap(),
+                None => break,
+            }
         }
     }
 }
+
+fn panic_message(payload: &Box<dyn Any + Send>) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "unknown panic"
+    }
+}
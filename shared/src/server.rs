@@ -0,0 +1,563 @@
+#[cfg(feature = "tls")]
+use crate::tls::{Acceptor, TlsStream};
+use crate::{
+    config::Config,
+    debug, error, log,
+    metrics::{self, Counter, Gauge, Histogram},
+    pool::{Overflow, ShutdownHandle, ThreadPool},
+    signal, warn,
+};
+use std::{
+    collections::HashMap,
+    io::{prelude::*, Error, ErrorKind, Result},
+    net::{IpAddr, Shutdown, SocketAddr, TcpListener, TcpStream},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Per-connection logic run on a pool worker for every accepted stream.
+pub trait Handler: Send + Sync + 'static {
+    fn handle(&self, stream: Stream, context: Context) -> Result<()>;
+}
+
+impl<F> Handler for F
+where
+    F: Fn(Stream, Context) -> Result<()> + Send + Sync + 'static,
+{
+    fn handle(&self, stream: Stream, context: Context) -> Result<()> {
+        self(stream, context)
+    }
+}
+
+/// The connection handed to a handler: a `TcpStream`, or a TLS session over one. As with
+/// `TcpStream`, both `Stream` and `&Stream` implement `Read` and `Write`, so one thread can
+/// read while another writes.
+pub struct Stream {
+    inner: Inner,
+}
+
+enum Inner {
+    Tcp(TcpStream),
+    #[cfg(feature = "tls")]
+    Tls(Box<TlsStream>),
+}
+
+/// Details about the connection being handled.
+#[derive(Clone, Copy, Debug)]
+pub struct Context {
+    pub worker: usize,
+    pub peer: SocketAddr,
+}
+
+pub struct ServerBuilder {
+    address: String,
+    workers: usize,
+    queue: Option<(usize, Overflow)>,
+    read_timeout: Option<Duration>,
+    write_timeout: Option<Duration>,
+    idle_timeout: Option<Duration>,
+    max_connections: Option<usize>,
+    max_connections_per_ip: Option<usize>,
+    shutdown_timeout: Duration,
+    metrics_address: Option<String>,
+    tls: Option<(PathBuf, PathBuf)>,
+}
+
+pub struct Server {
+    listener: TcpListener,
+    pool: ThreadPool,
+    read_timeout: Option<Duration>,
+    write_timeout: Option<Duration>,
+    limits: Arc<Limits>,
+    shutdown_timeout: Duration,
+    #[cfg(feature = "tls")]
+    tls: Option<Arc<Acceptor>>,
+}
+
+/// Connection series exported by every server.
+pub(crate) struct ServerMetrics {
+    accepted: Arc<Counter>,
+    refused: Arc<Counter>,
+    pub(crate) errors: Arc<Counter>,
+    pub(crate) timeouts: Arc<Counter>,
+    active: Arc<Gauge>,
+    duration: Arc<Histogram>,
+}
+
+/// Caps on the connections one server holds open, checked as each connection is accepted.
+pub(crate) struct Limits {
+    max_connections: Option<usize>,
+    max_connections_per_ip: Option<usize>,
+    open: Mutex<OpenConnections>,
+}
+
+#[derive(Default)]
+struct OpenConnections {
+    total: usize,
+    per_ip: HashMap<IpAddr, usize>,
+}
+
+/// Tracks one open connection in `ServerMetrics` and `Limits` until dropped.
+pub(crate) struct ConnectionGuard {
+    pub(crate) metrics: Arc<ServerMetrics>,
+    limits: Arc<Limits>,
+    ip: IpAddr,
+    opened: Instant,
+}
+
+impl ServerBuilder {
+    /// Takes the address, worker count, timeouts, connection limits and TLS files from
+    /// `config`.
+    pub fn config(mut self, config: &Config) -> ServerBuilder {
+        self.address = config.address();
+        self.workers = config.workers;
+        self.read_timeout = config.read_timeout;
+        self.write_timeout = config.write_timeout;
+        self.idle_timeout = config.idle_timeout;
+        self.max_connections = config.max_connections;
+        self.max_connections_per_ip = config.max_connections_per_ip;
+        self.shutdown_timeout = config.shutdown_timeout;
+        self.metrics_address = config.metrics_address();
+        self.tls = config.tls();
+        self
+    }
+
+    pub fn bind(mut self, address: &str) -> ServerBuilder {
+        self.address = address.to_string();
+        self
+    }
+
+    pub fn workers(mut self, workers: usize) -> ServerBuilder {
+        self.workers = workers;
+        self
+    }
+
+    /// Bounds the pool's job queue; see `ThreadPool::bounded`.
+    pub fn queue(mut self, capacity: usize, overflow: Overflow) -> ServerBuilder {
+        self.queue = Some((capacity, overflow));
+        self
+    }
+
+    pub fn read_timeout(mut self, timeout: Duration) -> ServerBuilder {
+        self.read_timeout = Some(timeout);
+        self
+    }
+
+    pub fn write_timeout(mut self, timeout: Duration) -> ServerBuilder {
+        self.write_timeout = Some(timeout);
+        self
+    }
+
+    /// Closes connections that send nothing for `timeout`. Applied as the socket read timeout,
+    /// or in place of `read_timeout` when that is longer.
+    pub fn idle_timeout(mut self, timeout: Duration) -> ServerBuilder {
+        self.idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Closes new connections immediately while `max` connections are already open.
+    pub fn max_connections(mut self, max: usize) -> ServerBuilder {
+        self.max_connections = Some(max);
+        self
+    }
+
+    /// Closes new connections immediately while `max` connections from the same IP address
+    /// are already open.
+    pub fn max_connections_per_ip(mut self, max: usize) -> ServerBuilder {
+        self.max_connections_per_ip = Some(max);
+        self
+    }
+
+    /// How long `Server::run` waits for in-flight connections after a shutdown signal.
+    pub fn shutdown_timeout(mut self, timeout: Duration) -> ServerBuilder {
+        self.shutdown_timeout = timeout;
+        self
+    }
+
+    /// Serves the process's metrics on `address`; see `metrics::serve`.
+    pub fn metrics(mut self, address: &str) -> ServerBuilder {
+        self.metrics_address = Some(address.to_string());
+        self
+    }
+
+    /// Serves TLS with the PEM certificate chain and private key at `cert` and `key`. The
+    /// handshake runs on the worker, before the handler gets the session as its `Stream`.
+    pub fn tls(mut self, cert: &Path, key: &Path) -> ServerBuilder {
+        self.tls = Some((cert.to_path_buf(), key.to_path_buf()));
+        self
+    }
+
+    pub fn build(self) -> Result<Server> {
+        check_tls_support(&self.tls)?;
+        let listener = TcpListener::bind(&self.address)?;
+
+        if let Some(address) = &self.metrics_address {
+            metrics::serve(address)?;
+        }
+
+        let pool = match self.queue {
+            Some((capacity, overflow)) => ThreadPool::bounded(self.workers, capacity, overflow),
+            None => ThreadPool::new(self.workers),
+        };
+
+        Ok(Server {
+            listener,
+            pool,
+            read_timeout: self.read_timeout.into_iter().chain(self.idle_timeout).min(),
+            write_timeout: self.write_timeout,
+            limits: Arc::new(Limits::new(
+                self.max_connections,
+                self.max_connections_per_ip,
+            )),
+            shutdown_timeout: self.shutdown_timeout,
+            #[cfg(feature = "tls")]
+            tls: self
+                .tls
+                .map(|(cert, key)| Acceptor::new(&cert, &key).map(Arc::new))
+                .transpose()?,
+        })
+    }
+}
+
+impl Server {
+    pub fn builder() -> ServerBuilder {
+        ServerBuilder {
+            address: "0.0.0.0:8080".to_string(),
+            workers: 5,
+            queue: None,
+            read_timeout: None,
+            write_timeout: None,
+            idle_timeout: None,
+            max_connections: None,
+            max_connections_per_ip: None,
+            shutdown_timeout: Duration::from_secs(5),
+            metrics_address: None,
+            tls: None,
+        }
+    }
+
+    /// The pool connections run on, for spawning long-lived background jobs before `run`.
+    pub fn pool(&self) -> &ThreadPool {
+        &self.pool
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        self.pool.shutdown_handle()
+    }
+
+    /// Accepts connections and runs `handler` for each of them on the pool until the process
+    /// receives SIGTERM or SIGINT, then shuts the pool down.
+    pub fn run<H: Handler>(self, handler: H) -> Result<()> {
+        let handler = Arc::new(handler);
+        let metrics = Arc::new(ServerMetrics::new());
+
+        let shutdown = self.pool.shutdown_handle();
+        signal::shutdown_on_terminate(shutdown.clone(), &self.listener)?;
+
+        for stream in self.listener.incoming() {
+            if shutdown.is_shutdown() {
+                break;
+            }
+
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    error!("Connection error: {}", e);
+                    continue;
+                }
+            };
+
+            let peer = match stream.peer_addr() {
+                Ok(peer) => peer,
+                Err(e) => {
+                    error!("Connection error: {}", e);
+                    continue;
+                }
+            };
+
+            let Some(guard) = ConnectionGuard::admit(&metrics, &self.limits, peer) else {
+                continue;
+            };
+
+            let timeouts = stream
+                .set_read_timeout(self.read_timeout)
+                .and_then(|()| stream.set_write_timeout(self.write_timeout));
+
+            if let Err(e) = timeouts {
+                error!("Connection error: {}", e);
+                continue;
+            }
+
+            let handler = Arc::clone(&handler);
+            #[cfg(feature = "tls")]
+            let tls = self.tls.clone();
+
+            self.pool.execute(move |worker| {
+                let _scope = log::connection(peer);
+
+                debug!("Connection accepted");
+
+                #[cfg(feature = "tls")]
+                let stream = match tls {
+                    Some(tls) => match tls.accept(stream) {
+                        Ok(stream) => Stream::from(stream),
+                        Err(e) => {
+                            warn!("TLS handshake failed: {e}");
+                            return;
+                        }
+                    },
+                    None => Stream::from(stream),
+                };
+                #[cfg(not(feature = "tls"))]
+                let stream = Stream::from(stream);
+
+                match handler.handle(stream, Context { worker, peer }) {
+                    Ok(()) => debug!("Connection closed"),
+                    Err(e) if is_timeout(&e) => {
+                        guard.metrics.timeouts.inc();
+                        warn!("Connection timed out; closing");
+                    }
+                    Err(e) => {
+                        guard.metrics.errors.inc();
+                        error!("Connection error: {}", e);
+                    }
+                }
+            });
+        }
+
+        let report = self.pool.shutdown(self.shutdown_timeout);
+
+        if !report.is_clean() {
+            warn!(
+                "{} connections still open at shutdown on workers {:?}; abandoning.",
+                report.busy.len(),
+                report.busy
+            );
+        }
+
+        Ok(())
+    }
+}
+
+impl Stream {
+    pub fn peer_addr(&self) -> Result<SocketAddr> {
+        self.socket().peer_addr()
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        self.socket().local_addr()
+    }
+
+    /// Moves the socket into or out of nonblocking mode, in which reads with nothing to return
+    /// fail with `WouldBlock`.
+    pub fn set_nonblocking(&self, nonblocking: bool) -> Result<()> {
+        self.socket().set_nonblocking(nonblocking)
+    }
+
+    /// Shuts down the reading, writing or both halves of the connection. Over TLS, shutting
+    /// down writing first tells the client with `close_notify`.
+    pub fn shutdown(&self, how: Shutdown) -> Result<()> {
+        match &self.inner {
+            Inner::Tcp(stream) => stream.shutdown(how),
+            #[cfg(feature = "tls")]
+            Inner::Tls(stream) => stream.shutdown(how),
+        }
+    }
+
+    fn socket(&self) -> &TcpStream {
+        match &self.inner {
+            Inner::Tcp(stream) => stream,
+            #[cfg(feature = "tls")]
+            Inner::Tls(stream) => stream.socket(),
+        }
+    }
+}
+
+impl From<TcpStream> for Stream {
+    fn from(stream: TcpStream) -> Stream {
+        Stream {
+            inner: Inner::Tcp(stream),
+        }
+    }
+}
+
+#[cfg(feature = "tls")]
+impl From<TlsStream> for Stream {
+    fn from(stream: TlsStream) -> Stream {
+        Stream {
+            inner: Inner::Tls(Box::new(stream)),
+        }
+    }
+}
+
+impl Read for &Stream {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        match &self.inner {
+            Inner::Tcp(stream) => (&*stream).read(buf),
+            #[cfg(feature = "tls")]
+            Inner::Tls(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for &Stream {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        match &self.inner {
+            Inner::Tcp(stream) => (&*stream).write(buf),
+            #[cfg(feature = "tls")]
+            Inner::Tls(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        match &self.inner {
+            Inner::Tcp(stream) => (&*stream).flush(),
+            #[cfg(feature = "tls")]
+            Inner::Tls(stream) => stream.flush(),
+        }
+    }
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        (&*self).read(buf)
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        (&*self).write(buf)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        (&*self).flush()
+    }
+}
+
+impl ServerMetrics {
+    pub(crate) fn new() -> ServerMetrics {
+        ServerMetrics {
+            accepted: metrics::counter("server_connections_total", "Connections accepted.", &[]),
+            refused: metrics::counter(
+                "server_connections_refused_total",
+                "Connections closed because a connection limit was reached.",
+                &[],
+            ),
+            errors: metrics::counter(
+                "server_connection_errors_total",
+                "Connections whose handler returned an error.",
+                &[],
+            ),
+            timeouts: metrics::counter(
+                "server_connection_timeouts_total",
+                "Connections closed because a read or write timed out.",
+                &[],
+            ),
+            active: metrics::gauge("server_active_connections", "Connections open now.", &[]),
+            duration: metrics::histogram(
+                "server_connection_duration_seconds",
+                "How long connections stayed open.",
+                &[],
+                metrics::DEFAULT_BUCKETS,
+            ),
+        }
+    }
+}
+
+impl Limits {
+    pub(crate) fn new(
+        max_connections: Option<usize>,
+        max_connections_per_ip: Option<usize>,
+    ) -> Limits {
+        Limits {
+            max_connections,
+            max_connections_per_ip,
+            open: Mutex::new(OpenConnections::default()),
+        }
+    }
+}
+
+impl ConnectionGuard {
+    /// Counts a new connection from `peer`, or logs and returns `None` if that would exceed
+    /// one of `limits`, in which case the caller should drop the stream.
+    pub(crate) fn admit(
+        metrics: &Arc<ServerMetrics>,
+        limits: &Arc<Limits>,
+        peer: SocketAddr,
+    ) -> Option<ConnectionGuard> {
+        let ip = peer.ip();
+        let mut open = limits.open.lock().unwrap();
+        let open_from_ip = open.per_ip.get(&ip).copied().unwrap_or(0);
+
+        if let Some(max) = limits.max_connections.filter(|&max| open.total >= max) {
+            metrics.refused.inc();
+            warn!("Connection limit of {max} reached; closing connection from {peer}");
+            return None;
+        }
+
+        if let Some(max) = limits
+            .max_connections_per_ip
+            .filter(|&max| open_from_ip >= max)
+        {
+            metrics.refused.inc();
+            warn!("Limit of {max} connections per address reached; closing connection from {peer}");
+            return None;
+        }
+
+        open.total += 1;
+        open.per_ip.insert(ip, open_from_ip + 1);
+
+        metrics.accepted.inc();
+        metrics.active.inc();
+
+        Some(ConnectionGuard {
+            metrics: Arc::clone(metrics),
+            limits: Arc::clone(limits),
+            ip,
+            opened: Instant::now(),
+        })
+    }
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        let mut open = self.limits.open.lock().unwrap();
+        open.total -= 1;
+
+        if let Some(count) = open.per_ip.get_mut(&self.ip) {
+            *count -= 1;
+
+            if *count == 0 {
+                open.per_ip.remove(&self.ip);
+            }
+        }
+
+        self.metrics.active.dec();
+        self.metrics
+            .duration
+            .observe_duration(self.opened.elapsed());
+    }
+}
+
+/// Fails if `tls` is set but TLS support was not compiled in.
+pub(crate) fn check_tls_support(tls: &Option<(PathBuf, PathBuf)>) -> Result<()> {
+    if cfg!(not(feature = "tls")) && tls.is_some() {
+        return Err(Error::new(
+            ErrorKind::Unsupported,
+            "TLS was configured, but this build has no TLS support; enable the tls feature",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Whether `error` came from a socket read or write timeout, which `std` reports as
+/// `WouldBlock` on Unix and `TimedOut` on Windows.
+fn is_timeout(error: &Error) -> bool {
+    matches!(error.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut)
+}
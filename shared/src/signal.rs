@@ -0,0 +1,55 @@
+use crate::{info, pool::ShutdownHandle, warn};
+use signal_hook::{
+    consts::{SIGINT, SIGTERM},
+    iterator::Signals,
+};
+use std::{
+    io::Result,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, TcpListener, TcpStream},
+    process, thread,
+};
+
+/// Runs `handler` on a background thread when the process first receives SIGTERM or SIGINT.
+/// A second signal exits immediately.
+pub fn on_terminate<F>(handler: F) -> Result<()>
+where
+    F: FnOnce() + Send + 'static,
+{
+    let mut signals = Signals::new([SIGTERM, SIGINT])?;
+    let mut handler = Some(handler);
+
+    thread::spawn(move || {
+        for signal in signals.forever() {
+            match handler.take() {
+                Some(handler) => {
+                    info!("Received signal {signal}; shutting down.");
+                    handler();
+                }
+                None => {
+                    warn!("Received signal {signal} again; exiting.");
+                    process::exit(1);
+                }
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Closes `handle` on SIGTERM or SIGINT, then connects to `listener` once so that a blocking
+/// `incoming()` loop wakes up and can observe `handle.is_shutdown()`.
+pub fn shutdown_on_terminate(handle: ShutdownHandle, listener: &TcpListener) -> Result<()> {
+    let mut address = listener.local_addr()?;
+
+    if address.ip().is_unspecified() {
+        address.set_ip(match address.ip() {
+            IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::LOCALHOST),
+            IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::LOCALHOST),
+        });
+    }
+
+    on_terminate(move || {
+        handle.shutdown();
+        TcpStream::connect(address).ok();
+    })
+}
@@ -0,0 +1,248 @@
+//! Helpers for end-to-end tests: run a server in-process on an ephemeral localhost port and
+//! drive it with scripted exchanges. Every read is bounded by a timeout so a misbehaving server
+//! fails the test instead of hanging it. Failures panic, with the caller's location.
+
+use crate::{
+    config::Config,
+    log::{self, Format, Level},
+};
+use std::{
+    io::{prelude::*, BufReader, ErrorKind, Result},
+    net::{Shutdown, SocketAddr, TcpListener, TcpStream, UdpSocket},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// How long reads wait before the exchange is considered failed.
+pub const TIMEOUT: Duration = Duration::from_secs(5);
+
+const STARTUP_POLL_INTERVAL: Duration = Duration::from_millis(10);
+const UDP_STARTUP_DELAY: Duration = Duration::from_millis(200);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Transport {
+    Tcp,
+    Udp,
+}
+
+pub struct TcpClient {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+pub struct UdpClient {
+    socket: UdpSocket,
+    server: SocketAddr,
+}
+
+/// Runs `run` with `config` on a background thread, bound to a free port on 127.0.0.1, and
+/// returns that address once the server is listening. The server lives until the test exits.
+///
+/// TCP servers are probed with a connection, which the server sees as a client that hangs up
+/// immediately. UDP servers can't be probed without consuming the port, so `spawn` waits a
+/// short, fixed time for them instead. Logging is limited to errors to keep test output readable.
+pub fn spawn<F>(transport: Transport, config: Config, run: F) -> SocketAddr
+where
+    F: FnOnce(&Config) -> Result<()> + Send + 'static,
+{
+    let address = free_address(transport);
+    log::init(Level::Error, Format::Text);
+
+    let config = Config {
+        bind: address.ip().to_string(),
+        port: address.port(),
+        shutdown_timeout: Duration::ZERO,
+        ..config
+    };
+
+    thread::spawn(move || {
+        if let Err(e) = run(&config) {
+            panic!("Server on {} failed: {}", config.address(), e);
+        }
+    });
+
+    match transport {
+        Transport::Tcp => {
+            let deadline = Instant::now() + TIMEOUT;
+
+            while TcpStream::connect(address).is_err() {
+                assert!(
+                    Instant::now() < deadline,
+                    "Server did not start on {address}"
+                );
+                thread::sleep(STARTUP_POLL_INTERVAL);
+            }
+        }
+        Transport::Udp => thread::sleep(UDP_STARTUP_DELAY),
+    }
+
+    address
+}
+
+/// Asks the OS for a free port by binding to port 0, then releases it for the server.
+pub fn free_address(transport: Transport) -> SocketAddr {
+    match transport {
+        Transport::Tcp => TcpListener::bind("127.0.0.1:0").unwrap().local_addr(),
+        Transport::Udp => UdpSocket::bind("127.0.0.1:0").unwrap().local_addr(),
+    }
+    .unwrap()
+}
+
+impl TcpClient {
+    #[track_caller]
+    pub fn connect(address: SocketAddr) -> TcpClient {
+        let stream = TcpStream::connect(address).unwrap();
+        stream.set_read_timeout(Some(TIMEOUT)).unwrap();
+
+        TcpClient {
+            reader: BufReader::new(stream.try_clone().unwrap()),
+            writer: stream,
+        }
+    }
+
+    #[track_caller]
+    pub fn send(&mut self, bytes: impl AsRef<[u8]>) {
+        self.writer.write_all(bytes.as_ref()).unwrap();
+    }
+
+    /// Sends `line` followed by a newline.
+    #[track_caller]
+    pub fn send_line(&mut self, line: &str) {
+        self.send(format!("{line}\n"));
+    }
+
+    /// Closes the sending half, signalling end of input to the server.
+    #[track_caller]
+    pub fn shutdown_write(&mut self) {
+        self.writer.shutdown(Shutdown::Write).unwrap();
+    }
+
+    #[track_caller]
+    pub fn read_exact(&mut self, len: usize) -> Vec<u8> {
+        let mut buffer = vec![0; len];
+        self.reader.read_exact(&mut buffer).unwrap();
+        buffer
+    }
+
+    /// Reads one line, without its trailing newline.
+    #[track_caller]
+    pub fn read_line(&mut self) -> String {
+        let mut line = String::new();
+        let len = self.reader.read_line(&mut line).unwrap();
+
+        assert!(len > 0, "Connection closed while waiting for a line");
+        assert!(line.ends_with('\n'), "Connection closed mid-line: {line:?}");
+
+        line.pop();
+        line
+    }
+
+    #[track_caller]
+    pub fn expect(&mut self, expected: impl AsRef<[u8]>) {
+        let expected = expected.as_ref();
+        assert_eq!(self.read_exact(expected.len()), expected);
+    }
+
+    #[track_caller]
+    pub fn expect_line(&mut self, expected: &str) {
+        assert_eq!(self.read_line(), expected);
+    }
+
+    /// Asserts that the server closes the connection without sending anything further.
+    #[track_caller]
+    pub fn expect_closed(&mut self) {
+        let mut buffer = Vec::new();
+
+        match self.reader.read_to_end(&mut buffer) {
+            Ok(_) => assert!(
+                buffer.is_empty(),
+                "Unexpected data before close: {buffer:?}"
+            ),
+            Err(e) if e.kind() == ErrorKind::ConnectionReset => {}
+            Err(e) => panic!("Connection not closed: {e}"),
+        }
+    }
+
+    /// Asserts that nothing arrives for `duration`.
+    #[track_caller]
+    pub fn expect_silence(&mut self, duration: Duration) {
+        self.reader
+            .get_ref()
+            .set_read_timeout(Some(duration))
+            .unwrap();
+
+        let mut buffer = [0; 1];
+        let result = self.reader.read(&mut buffer);
+
+        self.reader
+            .get_ref()
+            .set_read_timeout(Some(TIMEOUT))
+            .unwrap();
+
+        match result {
+            Ok(0) => panic!("Connection closed; expected it to stay open"),
+            Ok(_) => panic!("Unexpected data: {:?}", buffer),
+            Err(e) => assert!(
+                matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut),
+                "{e}"
+            ),
+        }
+    }
+}
+
+impl UdpClient {
+    #[track_caller]
+    pub fn connect(server: SocketAddr) -> UdpClient {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        socket.set_read_timeout(Some(TIMEOUT)).unwrap();
+
+        UdpClient { socket, server }
+    }
+
+    #[track_caller]
+    pub fn send(&self, bytes: impl AsRef<[u8]>) {
+        self.socket.send_to(bytes.as_ref(), self.server).unwrap();
+    }
+
+    /// Receives the next datagram from the server.
+    #[track_caller]
+    pub fn recv(&self) -> Vec<u8> {
+        let mut buffer = vec![0; 65536];
+
+        loop {
+            let (len, source) = self.socket.recv_from(&mut buffer).unwrap();
+
+            if source == self.server {
+                buffer.truncate(len);
+                return buffer;
+            }
+        }
+    }
+
+    #[track_caller]
+    pub fn expect(&self, expected: impl AsRef<[u8]>) {
+        let received = self.recv();
+        assert_eq!(
+            String::from_utf8_lossy(&received),
+            String::from_utf8_lossy(expected.as_ref())
+        );
+    }
+
+    /// Asserts that no datagram arrives for `duration`.
+    #[track_caller]
+    pub fn expect_silence(&self, duration: Duration) {
+        self.socket.set_read_timeout(Some(duration)).unwrap();
+
+        let mut buffer = [0; 65536];
+        let result = self.socket.recv_from(&mut buffer);
+
+        self.socket.set_read_timeout(Some(TIMEOUT)).unwrap();
+
+        if let Ok((len, _)) = result {
+            panic!(
+                "Unexpected datagram: {:?}",
+                String::from_utf8_lossy(&buffer[..len])
+            );
+        }
+    }
+}
@@ -0,0 +1,197 @@
+//! TLS for `Server` and `AsyncServer`. The handshake runs on the pool worker or task that
+//! serves the connection, never on the accept loop, and the handler then gets the session as
+//! its `Stream`, with the client's real address.
+
+use rustls::{
+    crypto::ring,
+    pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer},
+    ServerConfig, ServerConnection,
+};
+use std::{
+    io::{prelude::*, Error, ErrorKind, Result},
+    net::{Shutdown, TcpStream},
+    path::Path,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{net, time};
+use tokio_rustls::{server, TlsAcceptor};
+
+/// How long a client has to complete the handshake.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Largest chunk of ciphertext read from the socket at once, a little over one record.
+const READ_SIZE: usize = 17 * 1024;
+
+pub(crate) struct Acceptor {
+    config: Arc<ServerConfig>,
+}
+
+/// A TLS session over a blocking socket. Reads and writes may happen on different threads at
+/// once, as they can on a `TcpStream`: the session is only locked while it is being updated,
+/// not while a read waits on the socket.
+pub(crate) struct TlsStream {
+    socket: TcpStream,
+    session: Mutex<Session>,
+}
+
+struct Session {
+    connection: ServerConnection,
+    /// Ciphertext read from the socket but not yet taken by `connection`.
+    received: Vec<u8>,
+    /// Whether the socket has reached end of input.
+    closed: bool,
+}
+
+impl Acceptor {
+    /// Loads a PEM certificate chain and private key.
+    pub(crate) fn new(cert: &Path, key: &Path) -> Result<Acceptor> {
+        let certs = CertificateDer::pem_file_iter(cert)
+            .and_then(|certs| certs.collect::<std::result::Result<Vec<_>, _>>())
+            .map_err(|e| invalid(cert, e))?;
+        let key = PrivateKeyDer::from_pem_file(key).map_err(|e| invalid(key, e))?;
+
+        let config = ServerConfig::builder_with_provider(Arc::new(ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .and_then(|builder| builder.with_no_client_auth().with_single_cert(certs, key))
+            .map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
+
+        Ok(Acceptor {
+            config: Arc::new(config),
+        })
+    }
+
+    /// Completes a handshake on `socket`, blocking the calling thread for up to
+    /// `HANDSHAKE_TIMEOUT`. The socket's own timeouts apply again afterwards.
+    pub(crate) fn accept(&self, socket: TcpStream) -> Result<TlsStream> {
+        let mut connection = ServerConnection::new(Arc::clone(&self.config))
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+
+        let timeouts = (socket.read_timeout()?, socket.write_timeout()?);
+        socket.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+        socket.set_write_timeout(Some(HANDSHAKE_TIMEOUT))?;
+
+        while connection.is_handshaking() {
+            if connection.complete_io(&mut &socket)? == (0, 0) {
+                return Err(Error::new(
+                    ErrorKind::UnexpectedEof,
+                    "connection closed during handshake",
+                ));
+            }
+        }
+
+        socket.set_read_timeout(timeouts.0)?;
+        socket.set_write_timeout(timeouts.1)?;
+
+        Ok(TlsStream {
+            socket,
+            session: Mutex::new(Session {
+                connection,
+                received: Vec::new(),
+                closed: false,
+            }),
+        })
+    }
+
+    /// Completes a handshake on `socket`, failing after `HANDSHAKE_TIMEOUT`.
+    pub(crate) async fn accept_async(
+        &self,
+        socket: net::TcpStream,
+    ) -> Result<server::TlsStream<net::TcpStream>> {
+        let acceptor = TlsAcceptor::from(Arc::clone(&self.config));
+
+        time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(socket))
+            .await
+            .map_err(|_| Error::new(ErrorKind::TimedOut, "timed out"))?
+    }
+}
+
+impl TlsStream {
+    pub(crate) fn socket(&self) -> &TcpStream {
+        &self.socket
+    }
+
+    pub(crate) fn read(&self, buf: &mut [u8]) -> Result<usize> {
+        loop {
+            {
+                let mut session = self.session.lock().unwrap();
+
+                match session.connection.reader().read(buf) {
+                    Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+                    result => return result,
+                }
+
+                if !session.received.is_empty() || session.closed {
+                    session.process(&self.socket)?;
+                    continue;
+                }
+            }
+
+            let mut ciphertext = vec![0; READ_SIZE];
+            let len = (&self.socket).read(&mut ciphertext)?;
+
+            let mut session = self.session.lock().unwrap();
+            session.received.extend_from_slice(&ciphertext[..len]);
+            session.closed = len == 0;
+        }
+    }
+
+    pub(crate) fn write(&self, buf: &[u8]) -> Result<usize> {
+        let mut session = self.session.lock().unwrap();
+        let len = session.connection.writer().write(buf)?;
+        session.send(&self.socket)?;
+
+        Ok(len)
+    }
+
+    pub(crate) fn flush(&self) -> Result<()> {
+        self.session.lock().unwrap().send(&self.socket)
+    }
+
+    /// Sends `close_notify` before shutting down the writing half of the socket.
+    pub(crate) fn shutdown(&self, how: Shutdown) -> Result<()> {
+        if how != Shutdown::Read {
+            let mut session = self.session.lock().unwrap();
+            session.connection.send_close_notify();
+            session.send(&self.socket)?;
+        }
+
+        self.socket.shutdown(how)
+    }
+}
+
+impl Drop for TlsStream {
+    fn drop(&mut self) {
+        self.shutdown(Shutdown::Write).ok();
+    }
+}
+
+impl Session {
+    /// Feeds received ciphertext to the connection, or end of input once there is none left,
+    /// and sends anything it has to say in reply, including the alert for a protocol error.
+    fn process(&mut self, socket: &TcpStream) -> Result<()> {
+        let taken = self.connection.read_tls(&mut &self.received[..])?;
+        self.received.drain(..taken);
+
+        let processed = self.connection.process_new_packets();
+        self.send(socket)?;
+        processed.map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+
+        Ok(())
+    }
+
+    fn send(&mut self, mut socket: &TcpStream) -> Result<()> {
+        while self.connection.wants_write() {
+            self.connection.write_tls(&mut socket)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn invalid(path: &Path, error: impl std::fmt::Display) -> Error {
+    Error::new(
+        ErrorKind::InvalidInput,
+        format!("{}: {}", path.display(), error),
+    )
+}
@@ -0,0 +1,48 @@
+use shared::{
+    async_server::{AsyncServer, Context, Stream},
+    config::Config,
+    testing::{self, TcpClient, Transport},
+};
+use std::{io::Result, net::SocketAddr, time::Duration};
+use tokio::io::{self, AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+/// Echoes lines back until the client hangs up.
+async fn echo(stream: Stream, _: Context) -> Result<()> {
+    let (reader, mut writer) = io::split(stream);
+    let mut lines = BufReader::new(reader).lines();
+
+    while let Some(line) = lines.next_line().await? {
+        writer.write_all(format!("{line}\n").as_bytes()).await?;
+    }
+
+    Ok(())
+}
+
+fn server(config: Config) -> SocketAddr {
+    testing::spawn(Transport::Tcp, config, |config| {
+        AsyncServer::builder().config(config).build()?.run(echo)
+    })
+}
+
+#[test]
+fn closes_idle_connections() {
+    let mut client = TcpClient::connect(server(Config {
+        idle_timeout: Some(Duration::from_millis(200)),
+        ..Config::new(2)
+    }));
+
+    client.send_line("hello");
+    client.expect_line("hello");
+    client.expect_closed();
+}
+
+#[test]
+fn keeps_idle_connections_without_a_timeout() {
+    let mut client = TcpClient::connect(server(Config::new(2)));
+
+    client.send_line("hello");
+    client.expect_line("hello");
+    client.expect_silence(Duration::from_millis(300));
+    client.send_line("world");
+    client.expect_line("world");
+}
@@ -0,0 +1,224 @@
+use proptest::{collection::vec, prelude::*, sample::Index};
+use shared::{
+    codec::{self, Decode, DecodePrefixed, Encode, EncodePrefixed, FrameDecoder},
+    testing::TIMEOUT,
+};
+use std::{
+    collections::VecDeque,
+    io::{Error, ErrorKind, Read, Result},
+};
+use tokio::{io::AsyncWriteExt, time};
+
+#[derive(Clone, Debug, PartialEq, Encode, Decode)]
+struct Record {
+    id: u32,
+    #[codec(len = u8)]
+    name: String,
+    #[codec(len = u32)]
+    values: Vec<u16>,
+    #[codec(len = u16)]
+    pairs: Vec<Pair>,
+    empty: Empty,
+}
+
+#[derive(Clone, Debug, PartialEq, Encode, Decode)]
+struct Pair(u8, i64);
+
+#[derive(Clone, Debug, PartialEq, Encode, Decode)]
+struct Empty;
+
+fn record() -> impl Strategy<Value = Record> {
+    (
+        any::<u32>(),
+        ".{0,60}",
+        vec(any::<u16>(), 0..50),
+        vec(any::<(u8, i64)>(), 0..10),
+    )
+        .prop_map(|(id, name, values, pairs)| Record {
+            id,
+            name,
+            values,
+            pairs: pairs.into_iter().map(|(a, b)| Pair(a, b)).collect(),
+            empty: Empty,
+        })
+}
+
+/// Yields its chunks one read at a time, timing out between them.
+struct Chunked {
+    chunks: VecDeque<Vec<u8>>,
+    timed_out: bool,
+}
+
+impl Chunked {
+    fn new(bytes: &[u8], sizes: &[usize]) -> Chunked {
+        let mut rest = bytes;
+        let mut chunks = VecDeque::new();
+
+        for size in sizes {
+            let (chunk, remainder) = rest.split_at((*size).min(rest.len()));
+            chunks.push_back(chunk.to_vec());
+            rest = remainder;
+        }
+        if !rest.is_empty() {
+            chunks.push_back(rest.to_vec());
+        }
+
+        Chunked {
+            chunks,
+            timed_out: false,
+        }
+    }
+}
+
+impl Read for Chunked {
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize> {
+        if !self.timed_out {
+            self.timed_out = true;
+            return Err(Error::from(ErrorKind::WouldBlock));
+        }
+        self.timed_out = false;
+
+        let mut chunk = self.chunks.pop_front().unwrap_or_default();
+        let len = chunk.len().min(buffer.len());
+        buffer[..len].copy_from_slice(&chunk[..len]);
+
+        if len < chunk.len() {
+            self.chunks.push_front(chunk.split_off(len));
+        }
+
+        Ok(len)
+    }
+}
+
+/// Reads every message from `reader`, retrying after timeouts.
+fn read_all(decoder: &mut FrameDecoder<Record>, reader: &mut impl Read) -> Result<Vec<Record>> {
+    let mut records = Vec::new();
+
+    loop {
+        match decoder.read(reader) {
+            Ok(Some(record)) => records.push(record),
+            Ok(None) => return Ok(records),
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+proptest! {
+    #[test]
+    fn round_trips_derived_structs(record in record()) {
+        let bytes = codec::to_bytes(&record).unwrap();
+        prop_assert_eq!(codec::from_bytes::<Record>(&bytes).unwrap(), record);
+    }
+
+    #[test]
+    fn reports_truncated_input_as_unexpected_eof(record in record(), cut: Index) {
+        let bytes = codec::to_bytes(&record).unwrap();
+        let error = codec::from_bytes::<Record>(&bytes[..cut.index(bytes.len())]).unwrap_err();
+
+        prop_assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn decodes_frames_split_across_timed_out_reads(
+        records in vec(record(), 0..5),
+        sizes in vec(1..40usize, 0..20),
+    ) {
+        let mut bytes = Vec::new();
+        for record in &records {
+            record.encode(&mut bytes).unwrap();
+        }
+
+        let mut decoder = FrameDecoder::new(Record::decode);
+        let decoded = read_all(&mut decoder, &mut Chunked::new(&bytes, &sizes)).unwrap();
+
+        prop_assert_eq!(decoded, records);
+    }
+
+    #[test]
+    fn encodes_integers_big_endian(a: u16, b: u32, c: i64) {
+        prop_assert_eq!(codec::to_bytes(&a).unwrap(), a.to_be_bytes());
+        prop_assert_eq!(codec::to_bytes(&b).unwrap(), b.to_be_bytes());
+        prop_assert_eq!(codec::to_bytes(&c).unwrap(), c.to_be_bytes());
+    }
+}
+
+#[test]
+fn prefixes_strings_with_the_chosen_width() {
+    let mut short = Vec::new();
+    "abc".to_string().encode_prefixed::<u8>(&mut short).unwrap();
+    assert_eq!(short, b"\x03abc");
+
+    let mut long = Vec::new();
+    "abc".to_string().encode_prefixed::<u32>(&mut long).unwrap();
+    assert_eq!(long, b"\x00\x00\x00\x03abc");
+}
+
+#[test]
+fn rejects_lengths_that_overflow_the_prefix() {
+    let error = "x"
+        .repeat(256)
+        .encode_prefixed::<u8>(&mut Vec::new())
+        .unwrap_err();
+
+    assert_eq!(error.kind(), ErrorKind::InvalidInput);
+}
+
+#[test]
+fn rejects_invalid_utf8() {
+    let error = String::decode_prefixed::<u8>(&mut &[2, 0xff, 0xfe][..]).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+fn rejects_trailing_bytes() {
+    let error = codec::from_bytes::<u8>(&[1, 2]).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+fn does_not_trust_claimed_vector_lengths() {
+    let error =
+        Vec::<u32>::decode_prefixed::<u32>(&mut &[0xff, 0xff, 0xff, 0xff, 0][..]).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn waits_for_whole_frames_without_consuming() {
+    let mut decoder = FrameDecoder::new(u16::decode);
+
+    decoder.extend(&[0x01]);
+    assert_eq!(decoder.decode().unwrap(), None);
+    assert!(!decoder.is_empty());
+
+    decoder.extend(&[0x02, 0x03]);
+    assert_eq!(decoder.decode().unwrap(), Some(0x0102));
+    assert_eq!(decoder.decode().unwrap(), None);
+}
+
+#[test]
+fn reports_streams_ending_mid_frame() {
+    let mut decoder = FrameDecoder::new(u32::decode);
+
+    assert_eq!(decoder.read(&mut &[0, 0, 0, 1][..]).unwrap(), Some(1));
+    assert_eq!(decoder.read(&mut &[][..]).unwrap(), None);
+
+    let error = decoder.read(&mut &[0, 0][..]).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+}
+
+#[tokio::test]
+async fn decodes_frames_from_async_readers() {
+    let (mut client, mut server) = tokio::io::duplex(64);
+    let mut decoder = FrameDecoder::new(u32::decode);
+
+    client.write_all(&[0, 0]).await.unwrap();
+    let pending = time::timeout(TIMEOUT / 10, decoder.read_async(&mut server)).await;
+    assert!(pending.is_err());
+
+    client.write_all(&[1, 0]).await.unwrap();
+    drop(client);
+
+    assert_eq!(decoder.read_async(&mut server).await.unwrap(), Some(0x100));
+    assert_eq!(decoder.read_async(&mut server).await.unwrap(), None);
+}
@@ -0,0 +1,44 @@
+use shared::config::{Config, Flag};
+
+const FLAGS: [Flag; 1] = [Flag {
+    name: "colour",
+    value: "<name>",
+    env: "PROTOHACKERS_COLOUR",
+    help: "Colour to paint things",
+}];
+
+fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+#[test]
+fn accepts_registered_flags_from_args_and_the_environment() {
+    let env = |key: &str| (key == "PROTOHACKERS_COLOUR").then(|| "red".to_string());
+    let config = Config::new(1).with_flags(&FLAGS);
+
+    let from_env = config.clone().apply(env, args(&["--port", "1"])).unwrap();
+    let from_args = config.apply(env, args(&["--colour=blue"])).unwrap();
+
+    assert_eq!(from_env.flag("colour"), Some("red"));
+    assert_eq!(from_env.port, 1);
+    assert_eq!(from_args.flag("colour"), Some("blue"));
+}
+
+#[test]
+fn rejects_flags_nobody_registered() {
+    assert!(Config::new(1)
+        .apply(|_| None, args(&["--colour", "red"]))
+        .is_err());
+    assert!(!Config::new(1).knows("colour"));
+    assert!(Config::new(1).with_flags(&FLAGS).knows("colour"));
+    assert!(Config::new(1).knows("port"));
+}
+
+#[test]
+fn lists_registered_flags_in_usage() {
+    let usage = Config::new(1).with_flags(&FLAGS).usage();
+
+    assert!(usage.ends_with(
+        "\n  --colour <name>             Colour to paint things [env: PROTOHACKERS_COLOUR]"
+    ));
+}
@@ -0,0 +1,132 @@
+use shared::line::{self, AsyncLineReader, LineReader};
+use std::{
+    collections::VecDeque,
+    io::{BufReader, Error, ErrorKind, Read, Result},
+};
+
+/// Yields its chunks one read at a time, with a timeout between each.
+struct Stalling {
+    chunks: VecDeque<Vec<u8>>,
+    stalled: bool,
+}
+
+impl Stalling {
+    fn new(chunks: &[&[u8]]) -> Stalling {
+        Stalling {
+            chunks: chunks.iter().map(|chunk| chunk.to_vec()).collect(),
+            stalled: false,
+        }
+    }
+}
+
+impl Read for Stalling {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        self.stalled = !self.stalled;
+
+        if self.stalled && !self.chunks.is_empty() {
+            return Err(Error::from(ErrorKind::WouldBlock));
+        }
+
+        let Some(chunk) = self.chunks.pop_front() else {
+            return Ok(0);
+        };
+
+        buf[..chunk.len()].copy_from_slice(&chunk);
+        Ok(chunk.len())
+    }
+}
+
+fn lines(input: &[u8], max: usize) -> Vec<Result<String>> {
+    LineReader::new(input, max).lines().collect()
+}
+
+#[test]
+fn reads_lines_within_the_limit() {
+    let lines = lines(b"one\ntwo\r\n\nlast", 5);
+
+    let lines: Vec<String> = lines.into_iter().map(Result::unwrap).collect();
+    assert_eq!(lines, ["one", "two", "", "last"]);
+}
+
+#[test]
+fn keeps_the_newline_in_read_line() {
+    let mut reader = LineReader::new(&b"one\ntwo"[..], 10);
+    let mut line = String::new();
+
+    assert_eq!(reader.read_line(&mut line).unwrap(), 4);
+    assert_eq!(reader.read_line(&mut line).unwrap(), 3);
+    assert_eq!(reader.read_line(&mut line).unwrap(), 0);
+    assert_eq!(line, "one\ntwo");
+}
+
+#[test]
+fn rejects_long_lines_once_and_carries_on_after_them() {
+    let lines = lines(b"short\nmuch too long\nfine\n", 8);
+
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0].as_ref().unwrap(), "short");
+
+    let error = lines[1].as_ref().unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+    assert!(line::is_too_long(error));
+
+    assert_eq!(lines[2].as_ref().unwrap(), "fine");
+}
+
+#[test]
+fn counts_the_newline_towards_the_limit() {
+    let lines = lines(b"1234\n12345\n", 5);
+
+    assert_eq!(lines[0].as_ref().unwrap(), "1234");
+    assert!(line::is_too_long(lines[1].as_ref().unwrap_err()));
+}
+
+#[test]
+fn rejects_endless_lines_without_buffering_them() {
+    let endless = std::io::repeat(b'x').take(10 << 20);
+    let mut reader = LineReader::new(BufReader::new(endless), 1024);
+
+    let error = reader.read_line(&mut String::new()).unwrap_err();
+
+    assert!(line::is_too_long(&error));
+}
+
+#[test]
+fn tells_other_errors_apart() {
+    let lines = lines(b"\xff\n", 8);
+
+    let error = lines[0].as_ref().unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+    assert!(!line::is_too_long(error));
+}
+
+#[test]
+fn keeps_partial_lines_across_timeouts() {
+    let input = Stalling::new(&[b"hel", b"lo\nwor", b"ld\n"]);
+    let mut reader = LineReader::new(BufReader::new(input), 16);
+    let mut lines = Vec::new();
+
+    loop {
+        let mut line = String::new();
+
+        match reader.read_line(&mut line) {
+            Ok(0) => break,
+            Ok(_) => lines.push(line),
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+            Err(e) => panic!("Unexpected error: {e}"),
+        }
+    }
+
+    assert_eq!(lines, ["hello\n", "world\n"]);
+}
+
+#[tokio::test]
+async fn reads_bounded_lines_asynchronously() {
+    let input: &[u8] = b"first\nfar too long for this\nlast";
+    let mut reader = AsyncLineReader::new(tokio::io::BufReader::new(input), 8);
+
+    assert_eq!(reader.next_line().await.unwrap().unwrap(), "first");
+    assert!(line::is_too_long(&reader.next_line().await.unwrap_err()));
+    assert_eq!(reader.next_line().await.unwrap().unwrap(), "last");
+    assert!(reader.next_line().await.unwrap().is_none());
+}
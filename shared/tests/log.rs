@@ -0,0 +1,90 @@
+use serde_json::Value;
+use shared::log::{self, Format, Level};
+use std::{net::SocketAddr, thread};
+
+fn json(level: Level, message: &str) -> Value {
+    let line = log::render(
+        level,
+        Format::Json,
+        "shared::tests",
+        format_args!("{message}"),
+    );
+    assert!(!line.contains('\n'), "{line}");
+
+    serde_json::from_str(&line).unwrap()
+}
+
+#[test]
+fn escapes_json_messages() {
+    let message = "quote \" backslash \\ newline \n tab \t bell \u{7} done";
+    let line = json(Level::Warn, message);
+
+    assert_eq!(line["message"], message);
+    assert_eq!(line["level"], "WARN");
+    assert_eq!(line["target"], "shared::tests");
+}
+
+#[test]
+fn tags_json_lines_with_the_worker_and_peer() {
+    let peer: SocketAddr = "192.0.2.1:4000".parse().unwrap();
+
+    thread::spawn(move || {
+        let line = json(Level::Info, "untagged");
+        assert!(line.get("worker").is_none());
+        assert!(line.get("peer").is_none());
+
+        log::set_worker(3);
+        let scope = log::connection(peer);
+
+        let line = json(Level::Info, "tagged");
+        assert_eq!(line["worker"], 3);
+        assert_eq!(line["peer"], "192.0.2.1:4000");
+
+        drop(scope);
+        assert!(json(Level::Info, "left").get("peer").is_none());
+    })
+    .join()
+    .unwrap();
+}
+
+#[test]
+fn timestamps_lines_in_utc() {
+    let line = json(Level::Error, "now");
+    let timestamp = line["timestamp"].as_str().unwrap();
+
+    assert_eq!(timestamp.len(), "2025-01-01T00:00:00.000Z".len());
+    assert!(timestamp.starts_with("20"));
+    assert!(timestamp.ends_with('Z'));
+}
+
+#[test]
+fn tags_text_lines_with_the_worker_and_peer() {
+    thread::spawn(|| {
+        log::set_worker(7);
+        let _scope = log::connection("192.0.2.1:4000".parse().unwrap());
+
+        let line = log::render(
+            Level::Debug,
+            Format::Text,
+            "shared::tests",
+            format_args!("hi"),
+        );
+        let (_, rest) = line.split_once(' ').unwrap();
+
+        assert_eq!(rest, "DEBUG shared::tests worker=7 peer=192.0.2.1:4000: hi");
+    })
+    .join()
+    .unwrap();
+}
+
+#[test]
+fn writes_only_levels_up_to_the_configured_one() {
+    log::init(Level::Warn, Format::Text);
+
+    assert!(log::enabled(Level::Error));
+    assert!(log::enabled(Level::Warn));
+    assert!(!log::enabled(Level::Info));
+    assert!(!log::enabled(Level::Debug));
+
+    log::init(Level::Info, Format::Text);
+}
@@ -0,0 +1,144 @@
+use shared::{
+    config::Config,
+    metrics::{self, Registry},
+    testing::{TcpClient, TIMEOUT},
+};
+use std::{
+    io::prelude::*,
+    net::{SocketAddr, TcpStream},
+    sync::OnceLock,
+    time::{Duration, Instant},
+};
+
+/// The process's metrics endpoint, started once for every test in this file.
+fn endpoint() -> SocketAddr {
+    static ADDRESS: OnceLock<SocketAddr> = OnceLock::new();
+
+    *ADDRESS.get_or_init(|| metrics::serve("127.0.0.1:0").unwrap())
+}
+
+/// Sends `request` and returns the whole response.
+fn get(request: &str) -> String {
+    let mut stream = TcpStream::connect(endpoint()).unwrap();
+    stream.set_read_timeout(Some(TIMEOUT)).unwrap();
+    stream.write_all(request.as_bytes()).unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+
+    response
+}
+
+#[test]
+fn renders_counters_and_gauges_with_labels() {
+    let registry = Registry::default();
+    registry
+        .counter("requests_total", "Requests seen.", &[("method", "GET")])
+        .add(3);
+    registry
+        .counter("requests_total", "Requests seen.", &[("method", "PUT")])
+        .inc();
+    registry.gauge("open", "Open things.", &[]).set(-2);
+
+    assert_eq!(
+        registry.render(),
+        "# HELP open Open things.\n\
+         # TYPE open gauge\n\
+         open -2\n\
+         # HELP requests_total Requests seen.\n\
+         # TYPE requests_total counter\n\
+         requests_total{method=\"GET\"} 3\n\
+         requests_total{method=\"PUT\"} 1\n"
+    );
+}
+
+#[test]
+fn escapes_label_values() {
+    let registry = Registry::default();
+    registry
+        .counter("odd", "Odd labels.", &[("path", "a\"b\\c\nd")])
+        .inc();
+
+    assert!(registry
+        .render()
+        .contains("odd{path=\"a\\\"b\\\\c\\nd\"} 1\n"));
+}
+
+#[test]
+fn renders_cumulative_histogram_buckets() {
+    let registry = Registry::default();
+    let histogram = registry.histogram("latency", "Latency.", &[("op", "get")], &[0.1, 1.0]);
+    histogram.observe(0.05);
+    histogram.observe(0.5);
+    histogram.observe_duration(Duration::from_secs(2));
+
+    assert_eq!(
+        registry.render(),
+        "# HELP latency Latency.\n\
+         # TYPE latency histogram\n\
+         latency_bucket{op=\"get\",le=\"0.1\"} 1\n\
+         latency_bucket{op=\"get\",le=\"1\"} 2\n\
+         latency_bucket{op=\"get\",le=\"+Inf\"} 3\n\
+         latency_sum{op=\"get\"} 2.55\n\
+         latency_count{op=\"get\"} 3\n"
+    );
+}
+
+#[test]
+#[should_panic(expected = "registered twice")]
+fn refuses_one_name_for_two_kinds() {
+    let registry = Registry::default();
+    registry.counter("things", "Things.", &[]);
+    registry.gauge("things", "Things.", &[]);
+}
+
+#[test]
+fn serves_the_global_registry() {
+    metrics::counter("exporter_test_total", "Counted by the exporter test.", &[]).add(7);
+
+    let response = get("GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n");
+
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{response}");
+    assert!(response.contains("\nexporter_test_total 7\n"), "{response}");
+}
+
+#[test]
+fn answers_other_paths_with_not_found() {
+    let response = get("GET / HTTP/1.1\r\n\r\n");
+
+    assert!(
+        response.starts_with("HTTP/1.1 404 Not Found\r\n"),
+        "{response}"
+    );
+}
+
+#[test]
+fn answers_while_another_scrape_stalls() {
+    let mut stalled = TcpClient::connect(endpoint());
+    stalled.send("GET /metrics HTTP/1.1\r\n");
+
+    let started = Instant::now();
+    let response = get("GET /metrics HTTP/1.1\r\n\r\n");
+
+    assert!(started.elapsed() < Duration::from_secs(1));
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{response}");
+    stalled.expect_silence(Duration::from_millis(100));
+}
+
+#[test]
+fn closes_scrapes_with_overlong_lines() {
+    let mut client = TcpClient::connect(endpoint());
+    client.send(format!("GET /{} HTTP/1.1\r\n", "a".repeat(10_000)));
+
+    client.expect_closed();
+}
+
+#[test]
+fn binds_metrics_to_loopback_by_default() {
+    let config = Config {
+        metrics_port: Some(9100),
+        ..Config::new(1)
+    };
+
+    assert_eq!(config.metrics_address().as_deref(), Some("127.0.0.1:9100"));
+}
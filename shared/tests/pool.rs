@@ -0,0 +1,222 @@
+use shared::pool::{Overflow, ThreadPool};
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc, Condvar, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// How long each job in these tests takes.
+const JOB: Duration = Duration::from_millis(50);
+
+/// Time for a thread to reach the point where it would block.
+const SETTLE: Duration = Duration::from_millis(100);
+
+/// Holds jobs until opened, so that tests can fill a pool's workers and queue.
+#[derive(Clone, Default)]
+struct Gate(Arc<(Mutex<bool>, Condvar)>);
+
+impl Gate {
+    fn wait(&self) {
+        let (open, opened) = &*self.0;
+        let _open = opened.wait_while(open.lock().unwrap(), |open| !*open);
+    }
+
+    fn open(&self) {
+        *self.0 .0.lock().unwrap() = true;
+        self.0 .1.notify_all();
+    }
+}
+
+/// Occupies one of `pool`'s workers until `gate` opens, returning once the job has started.
+fn occupy(pool: &ThreadPool, gate: &Gate) {
+    let (started, starting) = mpsc::channel();
+    let gate = gate.clone();
+
+    pool.execute(move |_| {
+        started.send(()).unwrap();
+        gate.wait();
+    });
+
+    starting.recv().unwrap();
+}
+
+/// Submits `count` jobs that each take `JOB` and then count themselves in the result.
+fn submit(pool: &ThreadPool, count: usize) -> Arc<AtomicUsize> {
+    let done = Arc::new(AtomicUsize::new(0));
+
+    for _ in 0..count {
+        let done = Arc::clone(&done);
+
+        pool.execute(move |_| {
+            thread::sleep(JOB);
+            done.fetch_add(1, Ordering::SeqCst);
+        });
+    }
+
+    done
+}
+
+#[test]
+fn finishes_queued_jobs_before_shutting_down() {
+    let pool = ThreadPool::new(2);
+    let done = submit(&pool, 6);
+
+    let mut report = pool.shutdown(Duration::from_secs(5));
+    report.finished.sort();
+
+    assert!(report.is_clean());
+    assert_eq!(report.finished, [0, 1]);
+    assert_eq!(done.load(Ordering::SeqCst), 6);
+}
+
+#[test]
+fn reports_workers_still_busy_at_the_deadline() {
+    let pool = ThreadPool::new(2);
+    let (release, released) = mpsc::channel::<()>();
+    let (started, starting) = mpsc::channel();
+
+    pool.execute(move |worker| {
+        started.send(worker).unwrap();
+        released.recv().ok();
+    });
+    let busy = starting.recv().unwrap();
+
+    let began = Instant::now();
+    let report = pool.shutdown(Duration::from_millis(100));
+
+    assert!(began.elapsed() < Duration::from_secs(1));
+    assert!(!report.is_clean());
+    assert_eq!(report.busy, [busy]);
+    assert_eq!(report.finished, [1 - busy]);
+
+    drop(release);
+}
+
+#[test]
+fn drops_jobs_once_shut_down() {
+    let pool = ThreadPool::new(1);
+    pool.shutdown_handle().shutdown();
+    assert!(pool.shutdown_handle().is_shutdown());
+
+    let done = submit(&pool, 1);
+
+    assert!(pool.shutdown(Duration::from_secs(5)).is_clean());
+    assert_eq!(done.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn counts_jobs_waiting_for_a_worker() {
+    let pool = ThreadPool::bounded(1, 4, Overflow::Block);
+    let gate = Gate::default();
+    occupy(&pool, &gate);
+
+    let done = submit(&pool, 3);
+    assert_eq!(pool.queue_depth(), 3);
+
+    gate.open();
+    assert!(pool.shutdown(Duration::from_secs(5)).is_clean());
+    assert_eq!(done.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn blocks_submitters_while_the_queue_is_full() {
+    let pool = ThreadPool::bounded(1, 1, Overflow::Block);
+    let gate = Gate::default();
+    occupy(&pool, &gate);
+    submit(&pool, 1);
+
+    let (returned, returning) = mpsc::channel();
+
+    let depth = thread::scope(|scope| {
+        scope.spawn(|| {
+            submit(&pool, 1);
+            returned.send(()).unwrap();
+        });
+
+        assert!(returning.recv_timeout(SETTLE).is_err());
+        let depth = pool.queue_depth();
+        gate.open();
+        returning.recv().unwrap();
+        depth
+    });
+
+    // The blocked submitter's job isn't queued yet.
+    assert_eq!(depth, 1);
+
+    assert!(pool.shutdown(Duration::from_secs(5)).is_clean());
+}
+
+#[test]
+fn rejects_jobs_while_the_queue_is_full() {
+    let rejected = Arc::new(Mutex::new(Vec::new()));
+    let depths = Arc::clone(&rejected);
+    let pool = ThreadPool::bounded(
+        1,
+        2,
+        Overflow::Reject(Box::new(move |depth| depths.lock().unwrap().push(depth))),
+    );
+    let gate = Gate::default();
+    occupy(&pool, &gate);
+
+    let done = submit(&pool, 4);
+    assert_eq!(*rejected.lock().unwrap(), [2, 2]);
+    assert_eq!(pool.queue_depth(), 2);
+
+    gate.open();
+    assert!(pool.shutdown(Duration::from_secs(5)).is_clean());
+    assert_eq!(done.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn spawns_temporary_workers_while_the_queue_is_full() {
+    let pool = ThreadPool::bounded(1, 1, Overflow::Spawn { max: 1 });
+    let gate = Gate::default();
+    occupy(&pool, &gate);
+    submit(&pool, 1);
+
+    let (ran, running) = mpsc::channel();
+    pool.execute(move |worker| ran.send(worker).unwrap());
+
+    // The overflow worker takes the next id after the permanent ones.
+    assert_eq!(running.recv_timeout(SETTLE), Ok(1));
+    assert_eq!(pool.queue_depth(), 1);
+
+    gate.open();
+    assert!(pool.shutdown(Duration::from_secs(5)).is_clean());
+}
+
+#[test]
+fn replaces_workers_whose_jobs_panic() {
+    let pool = ThreadPool::new(1);
+    pool.execute(|_| panic!("job failed"));
+
+    let (ran, running) = mpsc::channel();
+    pool.execute(move |worker| ran.send(worker).unwrap());
+
+    assert_eq!(running.recv_timeout(Duration::from_secs(5)), Ok(0));
+
+    let report = pool.shutdown(Duration::from_secs(5));
+    assert!(report.is_clean());
+    assert_eq!(report.finished, [0]);
+}
+
+#[test]
+fn survives_panics_on_temporary_workers() {
+    let pool = ThreadPool::bounded(1, 1, Overflow::Spawn { max: 1 });
+    let gate = Gate::default();
+    occupy(&pool, &gate);
+    submit(&pool, 1);
+
+    pool.execute(|_| panic!("job failed"));
+    thread::sleep(SETTLE);
+
+    let (ran, running) = mpsc::channel();
+    pool.execute(move |worker| ran.send(worker).unwrap());
+    assert_eq!(running.recv_timeout(SETTLE), Ok(2));
+
+    gate.open();
+    assert!(pool.shutdown(Duration::from_secs(5)).is_clean());
+}
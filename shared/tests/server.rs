@@ -0,0 +1,93 @@
+use shared::{
+    config::Config,
+    server::{Context, Server, Stream},
+    testing::{self, TcpClient, Transport},
+};
+use std::{
+    io::{prelude::*, BufReader, Result},
+    net::SocketAddr,
+    thread,
+    time::Duration,
+};
+
+/// Time for the server to release a closed connection's slot, which happens just after the
+/// client sees the close. `testing::spawn`'s startup probe also holds a slot briefly.
+const SETTLE: Duration = Duration::from_millis(100);
+
+/// Echoes lines back until the client hangs up.
+fn echo(stream: Stream, _: Context) -> Result<()> {
+    let mut writer = &stream;
+
+    for line in BufReader::new(&stream).lines() {
+        writeln!(writer, "{}", line?)?;
+    }
+
+    Ok(())
+}
+
+fn server(config: Config) -> SocketAddr {
+    testing::spawn(Transport::Tcp, config, |config| {
+        Server::builder().config(config).build()?.run(echo)
+    })
+}
+
+#[test]
+fn closes_idle_connections() {
+    let mut client = TcpClient::connect(server(Config {
+        idle_timeout: Some(Duration::from_millis(200)),
+        ..Config::new(2)
+    }));
+
+    client.send_line("hello");
+    client.expect_line("hello");
+    client.expect_closed();
+}
+
+#[test]
+fn refuses_connections_over_the_limit() {
+    let address = server(Config {
+        max_connections: Some(2),
+        ..Config::new(4)
+    });
+    thread::sleep(SETTLE);
+
+    let mut first = TcpClient::connect(address);
+    let mut second = TcpClient::connect(address);
+    first.send_line("1");
+    first.expect_line("1");
+    second.send_line("2");
+    second.expect_line("2");
+
+    TcpClient::connect(address).expect_closed();
+
+    first.shutdown_write();
+    first.expect_closed();
+    thread::sleep(SETTLE);
+
+    let mut third = TcpClient::connect(address);
+    third.send_line("3");
+    third.expect_line("3");
+}
+
+#[test]
+fn refuses_connections_over_the_per_address_limit() {
+    let address = server(Config {
+        max_connections_per_ip: Some(1),
+        ..Config::new(4)
+    });
+    thread::sleep(SETTLE);
+
+    let mut first = TcpClient::connect(address);
+    first.send_line("1");
+    first.expect_line("1");
+
+    TcpClient::connect(address).expect_closed();
+
+    first.shutdown_write();
+    first.expect_closed();
+    thread::sleep(SETTLE);
+
+    let mut second = TcpClient::connect(address);
+    second.send_line("2");
+    second.expect_line("2");
+}
@@ -0,0 +1,202 @@
+use rcgen::CertifiedKey;
+use rustls::{
+    crypto::ring,
+    pki_types::{CertificateDer, ServerName},
+    ClientConfig, ClientConnection, RootCertStore, StreamOwned,
+};
+use shared::{
+    async_server::{self, AsyncServer},
+    config::Config,
+    server::{Context, Server, Stream},
+    testing::{self, Transport, TIMEOUT},
+};
+use std::{
+    env, fs,
+    io::{prelude::*, BufReader, Result},
+    net::{SocketAddr, TcpStream},
+    path::PathBuf,
+    process,
+    sync::Arc,
+};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+
+type TlsStream = BufReader<StreamOwned<ClientConnection, TcpStream>>;
+
+/// A self-signed certificate for `localhost`, written to PEM files for the server to load.
+struct Certificate {
+    cert: PathBuf,
+    key: PathBuf,
+    der: CertificateDer<'static>,
+}
+
+impl Certificate {
+    fn generate(name: &str) -> Certificate {
+        let CertifiedKey { cert, key_pair } =
+            rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+
+        let directory = env::temp_dir().join(format!("shared-tls-{}-{name}", process::id()));
+        fs::create_dir_all(&directory).unwrap();
+
+        let certificate = Certificate {
+            cert: directory.join("cert.pem"),
+            key: directory.join("key.pem"),
+            der: cert.der().clone(),
+        };
+        fs::write(&certificate.cert, cert.pem()).unwrap();
+        fs::write(&certificate.key, key_pair.serialize_pem()).unwrap();
+
+        certificate
+    }
+
+    fn config(&self) -> Config {
+        Config {
+            tls_cert: Some(self.cert.clone()),
+            tls_key: Some(self.key.clone()),
+            ..Config::new(2)
+        }
+    }
+
+    /// Connects to `address` and completes a handshake, trusting only this certificate.
+    fn connect(&self, address: SocketAddr) -> TlsStream {
+        let mut roots = RootCertStore::empty();
+        roots.add(self.der.clone()).unwrap();
+
+        let config = ClientConfig::builder_with_provider(Arc::new(ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        let connection =
+            ClientConnection::new(Arc::new(config), ServerName::try_from("localhost").unwrap())
+                .unwrap();
+
+        let stream = TcpStream::connect(address).unwrap();
+        stream.set_read_timeout(Some(TIMEOUT)).unwrap();
+
+        BufReader::new(StreamOwned::new(connection, stream))
+    }
+}
+
+fn send_line(stream: &mut TlsStream, line: &str) {
+    writeln!(stream.get_mut(), "{line}").unwrap();
+    stream.get_mut().flush().unwrap();
+}
+
+fn read_line(stream: &mut TlsStream) -> String {
+    let mut line = String::new();
+    stream.read_line(&mut line).unwrap();
+    line
+}
+
+/// Echoes lines back, prefixed with the client's port as seen by the server.
+fn echo(stream: Stream, context: Context) -> Result<()> {
+    assert_eq!(stream.peer_addr()?, context.peer);
+    let mut writer = &stream;
+
+    for line in BufReader::new(&stream).lines() {
+        writeln!(writer, "{} {}", context.peer.port(), line?)?;
+    }
+
+    Ok(())
+}
+
+/// Replies with the number of bytes received once the client stops sending.
+fn count(stream: Stream, _: Context) -> Result<()> {
+    let mut received = Vec::new();
+    (&stream).read_to_end(&mut received)?;
+
+    writeln!(&stream, "{}", received.len())
+}
+
+#[test]
+fn serves_handlers_over_tls() {
+    let certificate = Certificate::generate("sync");
+    let address = testing::spawn(Transport::Tcp, certificate.config(), |config| {
+        Server::builder().config(config).build()?.run(echo)
+    });
+
+    let mut stream = certificate.connect(address);
+    let port = stream.get_ref().sock.local_addr().unwrap().port();
+
+    send_line(&mut stream, "hello");
+    assert_eq!(read_line(&mut stream), format!("{port} hello\n"));
+    send_line(&mut stream, "world");
+    assert_eq!(read_line(&mut stream), format!("{port} world\n"));
+}
+
+#[test]
+fn passes_end_of_input_through() {
+    let certificate = Certificate::generate("half-close");
+    let address = testing::spawn(Transport::Tcp, certificate.config(), |config| {
+        Server::builder().config(config).build()?.run(count)
+    });
+
+    let mut stream = certificate.connect(address);
+    stream.get_mut().write_all(&[0; 10000]).unwrap();
+    stream.get_mut().conn.send_close_notify();
+    stream.get_mut().flush().unwrap();
+
+    assert_eq!(read_line(&mut stream), "10000\n");
+}
+
+#[test]
+fn serves_async_handlers_over_tls() {
+    let certificate = Certificate::generate("async");
+    let address = testing::spawn(Transport::Tcp, certificate.config(), |config| {
+        AsyncServer::builder().config(config).build()?.run(
+            |stream: async_server::Stream, _| async move {
+                let port = stream.peer_addr()?.port();
+                let (reader, mut writer) = tokio::io::split(stream);
+                let mut lines = tokio::io::BufReader::new(reader).lines();
+
+                while let Some(line) = lines.next_line().await? {
+                    writer
+                        .write_all(format!("{port} {line}!\n").as_bytes())
+                        .await?;
+                }
+
+                Ok(())
+            },
+        )
+    });
+
+    let mut stream = certificate.connect(address);
+    let port = stream.get_ref().sock.local_addr().unwrap().port();
+
+    send_line(&mut stream, "hello");
+    assert_eq!(read_line(&mut stream), format!("{port} hello!\n"));
+}
+
+#[test]
+fn serves_clients_while_another_handshake_stalls() {
+    let certificate = Certificate::generate("stall");
+    let address = testing::spawn(Transport::Tcp, certificate.config(), |config| {
+        Server::builder().config(config).build()?.run(echo)
+    });
+    let _stalled = TcpStream::connect(address).unwrap();
+
+    let mut stream = certificate.connect(address);
+    let port = stream.get_ref().sock.local_addr().unwrap().port();
+
+    send_line(&mut stream, "hello");
+    assert_eq!(read_line(&mut stream), format!("{port} hello\n"));
+}
+
+#[test]
+fn closes_plaintext_clients() {
+    let certificate = Certificate::generate("plaintext");
+    let address = testing::spawn(Transport::Tcp, certificate.config(), |config| {
+        Server::builder().config(config).build()?.run(echo)
+    });
+
+    let mut stream = TcpStream::connect(address).unwrap();
+    stream.set_read_timeout(Some(TIMEOUT)).unwrap();
+    stream.write_all(b"hello\n").unwrap();
+
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).unwrap();
+
+    // A single alert record, then end of stream.
+    assert_eq!(response[0], 0x15);
+    assert_eq!(response.len(), 7);
+}
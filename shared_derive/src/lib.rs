@@ -0,0 +1,137 @@
+//! Derives for `shared::codec::Encode` and `shared::codec::Decode`. Fields are encoded in
+//! declaration order. Strings and vectors have no default wire format, so each such field must
+//! name the integer type of its length prefix, e.g. `#[codec(len = u8)]`.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Field, Fields, Result, Type};
+
+#[proc_macro_derive(Encode, attributes(codec))]
+pub fn derive_encode(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    expand_encode(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+#[proc_macro_derive(Decode, attributes(codec))]
+pub fn derive_decode(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    expand_decode(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand_encode(input: &DeriveInput) -> Result<TokenStream2> {
+    let name = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+
+    let fields = fields(input)?
+        .iter()
+        .enumerate()
+        .map(|(index, field)| {
+            let member = member(field, index);
+
+            Ok(match length_prefix(field)? {
+                Some(length) => quote! {
+                    ::shared::codec::EncodePrefixed::encode_prefixed::<#length>(&self.#member, buffer)?;
+                },
+                None => quote! {
+                    ::shared::codec::Encode::encode(&self.#member, buffer)?;
+                },
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(quote! {
+        impl #impl_generics ::shared::codec::Encode for #name #type_generics #where_clause {
+            fn encode(&self, buffer: &mut ::std::vec::Vec<u8>) -> ::std::io::Result<()> {
+                #(#fields)*
+                Ok(())
+            }
+        }
+    })
+}
+
+fn expand_decode(input: &DeriveInput) -> Result<TokenStream2> {
+    let name = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    let fields = fields(input)?;
+
+    let values = fields
+        .iter()
+        .map(|field| {
+            Ok(match length_prefix(field)? {
+                Some(length) => quote! {
+                    ::shared::codec::DecodePrefixed::decode_prefixed::<#length>(input)?
+                },
+                None => quote! {
+                    ::shared::codec::Decode::decode(input)?
+                },
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let construct = match &fields {
+        Fields::Named(_) => {
+            let names = fields.iter().map(|field| &field.ident);
+            quote! { #name { #(#names: #values),* } }
+        }
+        Fields::Unnamed(_) => quote! { #name(#(#values),*) },
+        Fields::Unit => quote! { #name },
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::shared::codec::Decode for #name #type_generics #where_clause {
+            fn decode(input: &mut &[u8]) -> ::std::io::Result<Self> {
+                Ok(#construct)
+            }
+        }
+    })
+}
+
+fn fields(input: &DeriveInput) -> Result<&Fields> {
+    match &input.data {
+        Data::Struct(data) => Ok(&data.fields),
+        _ => Err(Error::new_spanned(
+            &input.ident,
+            "Encode and Decode can only be derived for structs",
+        )),
+    }
+}
+
+/// `self.name` for named fields, `self.0` for tuple fields.
+fn member(field: &Field, index: usize) -> TokenStream2 {
+    match &field.ident {
+        Some(ident) => quote! { #ident },
+        None => {
+            let index = syn::Index::from(index);
+            quote! { #index }
+        }
+    }
+}
+
+/// The type in `#[codec(len = ...)]`, if the field has one.
+fn length_prefix(field: &Field) -> Result<Option<Type>> {
+    let mut length = None;
+
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("codec"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("len") {
+                length = Some(meta.value()?.parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("expected `len = <integer type>`"))
+            }
+        })?;
+    }
+
+    Ok(length)
+}
@@ -0,0 +1,80 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Seconds from 1900-01-01, where RFC 868 counts from, to the Unix epoch.
+const SECONDS_BEFORE_UNIX_EPOCH: u64 = 2_208_988_800;
+
+const WEEKDAYS: [&str; 7] = [
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+];
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// `now` as an RFC 867 daytime line in UTC, e.g. `Tuesday, February 22, 1982 17:37:43-UTC`.
+pub fn daytime(now: SystemTime) -> String {
+    let seconds = unix_seconds(now);
+    let days = seconds / 86_400;
+    let (year, month, day) = civil_from_days(days);
+    let time = seconds % 86_400;
+
+    format!(
+        "{}, {} {}, {} {:02}:{:02}:{:02}-UTC\r\n",
+        WEEKDAYS[(days % 7) as usize],
+        MONTHS[month - 1],
+        day,
+        year,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60,
+    )
+}
+
+/// `now` as the RFC 868 32-bit count of seconds since 1900, which wraps in 2036.
+pub fn time(now: SystemTime) -> [u8; 4] {
+    let seconds = unix_seconds(now) + SECONDS_BEFORE_UNIX_EPOCH;
+
+    (seconds as u32).to_be_bytes()
+}
+
+fn unix_seconds(now: SystemTime) -> u64 {
+    now.duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs())
+}
+
+/// The year, month and day `days` after 1970-01-01, using Howard Hinnant's algorithm.
+fn civil_from_days(days: u64) -> (u64, usize, u64) {
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+    (year, month as usize, day)
+}
@@ -0,0 +1,105 @@
+pub mod clock;
+mod service;
+
+pub use service::Service;
+
+use shared::{
+    config::{parse, Config, Flag, DEFAULT_IDLE_TIMEOUT},
+    pool::Overflow,
+    server::{Context, Server},
+};
+use std::{io::Result, net::UdpSocket, thread};
+
+/// Flags for choosing the service and limiting what it sends, read by `Options::from_config`.
+pub const FLAGS: [Flag; 4] = [
+    Flag {
+        name: "service",
+        value: "<name>",
+        env: "PROTOHACKERS_SERVICE",
+        help: "echo, discard, chargen, daytime or time; defaults to the one on --port, else echo",
+    },
+    Flag {
+        name: "rate",
+        value: "<bytes>",
+        env: "PROTOHACKERS_ECHO_RATE",
+        help: "Send at most this many bytes per second on each TCP connection, and in UDP replies",
+    },
+    Flag {
+        name: "max-bytes",
+        value: "<bytes>",
+        env: "PROTOHACKERS_ECHO_MAX_BYTES",
+        help: "Close TCP connections after sending this many bytes",
+    },
+    Flag {
+        name: "udp",
+        value: "<bool>",
+        env: "PROTOHACKERS_UDP",
+        help: "Also offer the service over UDP on the same port",
+    },
+];
+
+/// Which service to offer and limits on what it sends, beyond the listener settings in
+/// `Config`. Unlimited and TCP only by default.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Options {
+    pub service: Option<Service>,
+    pub rate: Option<u64>,
+    pub max_bytes: Option<u64>,
+    pub udp: bool,
+}
+
+impl Options {
+    /// Reads the values given for `FLAGS` in `config`.
+    pub fn from_config(config: &Config) -> Result<Options> {
+        let mut options = Options::default();
+
+        if let Some(value) = config.flag("service") {
+            options.service = Some(parse("service", value)?);
+        }
+        if let Some(value) = config.flag("rate") {
+            options.rate = Some(parse("rate", value)?).filter(|&rate| rate > 0);
+        }
+        if let Some(value) = config.flag("max-bytes") {
+            options.max_bytes = Some(parse("max-bytes", value)?);
+        }
+        if let Some(value) = config.flag("udp") {
+            options.udp = parse("udp", value)?;
+        }
+
+        Ok(options)
+    }
+}
+
+/// Defaults for `run`, before flags and environment variables are applied.
+pub fn default_config() -> Config {
+    Config {
+        idle_timeout: Some(DEFAULT_IDLE_TIMEOUT),
+        ..Config::new(5).with_flags(&FLAGS)
+    }
+}
+
+/// Serves the protocol as configured by `config` until the process is asked to shut down.
+pub fn run(config: &Config) -> Result<()> {
+    serve(config, Options::from_config(config)?)
+}
+
+/// Like `run`, but offers the service chosen by `options` on the configured port, over UDP
+/// too if `options.udp` is set, so that firewall rules for either can be checked.
+pub fn serve(config: &Config, options: Options) -> Result<()> {
+    let service = options
+        .service
+        .or(Service::for_port(config.port))
+        .unwrap_or(Service::Echo);
+
+    let server = Server::builder()
+        .config(config)
+        .queue(16, Overflow::Spawn { max: 20 })
+        .build()?;
+
+    if options.udp {
+        let socket = UdpSocket::bind(server.local_addr()?)?;
+        thread::spawn(move || service.serve_datagrams(&socket, options));
+    }
+
+    server.run(move |stream, _: Context| service.handle_stream(stream, options))
+}
@@ -0,0 +1,248 @@
+use crate::{clock, Options};
+use shared::{server::Stream, warn};
+use std::{
+    io::{prelude::*, ErrorKind, Result},
+    net::{Shutdown, UdpSocket},
+    str::FromStr,
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+/// Characters per chargen line, before the CRLF.
+const LINE_LENGTH: usize = 72;
+
+/// Lines of chargen output sent in reply to each UDP datagram, keeping replies under the
+/// 512 bytes RFC 864 allows.
+const LINES_PER_DATAGRAM: usize = 6;
+
+/// Longest reply to one UDP datagram, whatever the service, so that a datagram with a forged
+/// source address can't draw much traffic onto its victim.
+const MAX_REPLY_LENGTH: usize = 512;
+
+/// The classic diagnostic services, each on its well-known port when run as root.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Service {
+    /// RFC 862: sends back whatever it receives.
+    Echo,
+    /// RFC 863: throws away whatever it receives.
+    Discard,
+    /// RFC 864: sends a rotating pattern of printable characters.
+    Chargen,
+    /// RFC 867: sends the current date and time as text.
+    Daytime,
+    /// RFC 868: sends the current time as seconds since 1900.
+    Time,
+}
+
+impl Service {
+    /// The service assigned `port`, if any.
+    pub fn for_port(port: u16) -> Option<Service> {
+        match port {
+            7 => Some(Service::Echo),
+            9 => Some(Service::Discard),
+            13 => Some(Service::Daytime),
+            19 => Some(Service::Chargen),
+            37 => Some(Service::Time),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn handle_stream(self, stream: Stream, options: Options) -> Result<()> {
+        let result = match self {
+            Service::Echo => echo(&stream, options),
+            Service::Discard => discard(&stream),
+            Service::Chargen => chargen(&stream, options),
+            Service::Daytime => (&stream).write_all(clock::daytime(SystemTime::now()).as_bytes()),
+            Service::Time => (&stream).write_all(&clock::time(SystemTime::now())),
+        };
+
+        match result {
+            // The client hanging up is how chargen normally ends.
+            Err(e) if matches!(e.kind(), ErrorKind::BrokenPipe | ErrorKind::ConnectionReset) => {
+                Ok(())
+            }
+            Err(e) => Err(e),
+            Ok(()) => stream.shutdown(Shutdown::Write),
+        }
+    }
+
+    /// Answers each datagram arriving on `socket` as the UDP variant of the service does,
+    /// with replies cut to `MAX_REPLY_LENGTH` and paced by `Options::rate`.
+    pub(crate) fn serve_datagrams(self, socket: &UdpSocket, options: Options) {
+        let mut buffer = vec![0; 65536];
+        let mut pattern = Chargen::default();
+        let mut budget = Budget::new(Options {
+            max_bytes: None,
+            ..options
+        });
+
+        loop {
+            let (len, source) = match socket.recv_from(&mut buffer) {
+                Ok(received) => received,
+                Err(e) => {
+                    warn!("UDP receive failed: {}", e);
+                    continue;
+                }
+            };
+
+            let mut reply = match self {
+                Service::Echo => buffer[..len].to_vec(),
+                Service::Discard => continue,
+                Service::Chargen => {
+                    let mut lines = vec![0; LINES_PER_DATAGRAM * (LINE_LENGTH + 2)];
+                    pattern.fill(&mut lines);
+                    lines
+                }
+                Service::Daytime => clock::daytime(SystemTime::now()).into_bytes(),
+                Service::Time => clock::time(SystemTime::now()).to_vec(),
+            };
+            reply.truncate(MAX_REPLY_LENGTH);
+
+            match socket.send_to(&reply, source) {
+                Ok(sent) => budget.spend(sent),
+                Err(e) => warn!("UDP reply to {} failed: {}", source, e),
+            }
+        }
+    }
+}
+
+impl FromStr for Service {
+    type Err = ();
+
+    fn from_str(s: &str) -> std::result::Result<Service, ()> {
+        match s.to_lowercase().as_str() {
+            "echo" => Ok(Service::Echo),
+            "discard" => Ok(Service::Discard),
+            "chargen" => Ok(Service::Chargen),
+            "daytime" => Ok(Service::Daytime),
+            "time" => Ok(Service::Time),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Echoes bytes as they arrive, until the client finishes sending or `max_bytes` have been
+/// echoed.
+fn echo(mut stream: &Stream, options: Options) -> Result<()> {
+    let mut buffer = [0; 8192];
+    let mut budget = Budget::new(options);
+
+    loop {
+        let wanted = budget.next(buffer.len());
+
+        if wanted == 0 {
+            return Ok(());
+        }
+
+        let read = match stream.read(&mut buffer[..wanted]) {
+            Ok(0) => return Ok(()),
+            Ok(read) => read,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+
+        stream.write_all(&buffer[..read])?;
+        budget.spend(read);
+    }
+}
+
+fn discard(mut stream: &Stream) -> Result<()> {
+    let mut buffer = [0; 8192];
+
+    loop {
+        match stream.read(&mut buffer) {
+            Ok(0) => return Ok(()),
+            Ok(_) => {}
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Sends the chargen pattern until the client hangs up or `max_bytes` have been sent.
+fn chargen(mut stream: &Stream, options: Options) -> Result<()> {
+    let mut buffer = [0; 8192];
+    let mut budget = Budget::new(options);
+    let mut pattern = Chargen::default();
+
+    loop {
+        let wanted = budget.next(buffer.len());
+
+        if wanted == 0 {
+            return Ok(());
+        }
+
+        pattern.fill(&mut buffer[..wanted]);
+        stream.write_all(&buffer[..wanted])?;
+        budget.spend(wanted);
+    }
+}
+
+/// Tracks bytes sent on a connection, or in UDP replies, against `Options::rate` and `Options::max_bytes`.
+struct Budget {
+    options: Options,
+    sent: u64,
+    started: Instant,
+}
+
+impl Budget {
+    fn new(options: Options) -> Budget {
+        Budget {
+            options,
+            sent: 0,
+            started: Instant::now(),
+        }
+    }
+
+    /// How many of `wanted` bytes to send next; none once `max_bytes` have been sent.
+    fn next(&self, wanted: usize) -> usize {
+        let mut wanted = wanted as u64;
+
+        if let Some(max_bytes) = self.options.max_bytes {
+            wanted = wanted.min(max_bytes - self.sent);
+        }
+        if let Some(rate) = self.options.rate {
+            // A tenth of a second's worth at a time, so the rate holds over short spans too.
+            wanted = wanted.min((rate / 10).max(1));
+        }
+
+        wanted as usize
+    }
+
+    /// Records `sent` more bytes, sleeping until the rate allows for them.
+    fn spend(&mut self, sent: usize) {
+        self.sent += sent as u64;
+
+        if let Some(rate) = self.options.rate {
+            let due = Duration::from_secs_f64(self.sent as f64 / rate as f64);
+            thread::sleep(due.saturating_sub(self.started.elapsed()));
+        }
+    }
+}
+
+/// The RFC 864 pattern: lines of 72 printable ASCII characters, each starting one character
+/// further along than the last.
+#[derive(Default)]
+struct Chargen {
+    line: usize,
+    column: usize,
+}
+
+impl Chargen {
+    fn fill(&mut self, buffer: &mut [u8]) {
+        for byte in buffer {
+            *byte = match self.column {
+                LINE_LENGTH => b'\r',
+                column if column > LINE_LENGTH => b'\n',
+                column => b' ' + ((self.line + column) % 95) as u8,
+            };
+
+            self.column += 1;
+
+            if self.column == LINE_LENGTH + 2 {
+                self.column = 0;
+                self.line += 1;
+            }
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use smoke_test::clock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+fn at(seconds: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(seconds)
+}
+
+#[test]
+fn formats_daytime() {
+    assert_eq!(
+        clock::daytime(at(0)),
+        "Thursday, January 1, 1970 00:00:00-UTC\r\n"
+    );
+    assert_eq!(
+        clock::daytime(at(383_247_463)),
+        "Monday, February 22, 1982 17:37:43-UTC\r\n"
+    );
+    assert_eq!(
+        clock::daytime(at(951_782_400)),
+        "Tuesday, February 29, 2000 00:00:00-UTC\r\n"
+    );
+    assert_eq!(
+        clock::daytime(at(1_735_689_599)),
+        "Tuesday, December 31, 2024 23:59:59-UTC\r\n"
+    );
+}
+
+#[test]
+fn counts_seconds_from_1900() {
+    assert_eq!(clock::time(at(0)), 2_208_988_800u32.to_be_bytes());
+    assert_eq!(
+        clock::time(at(1_000_000_000)),
+        3_208_988_800u32.to_be_bytes()
+    );
+}
@@ -0,0 +1,219 @@
+use shared::testing::{self, TcpClient, Transport, UdpClient};
+use smoke_test::{Options, Service};
+use std::{
+    net::SocketAddr,
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+fn server() -> SocketAddr {
+    testing::spawn(
+        Transport::Tcp,
+        smoke_test::default_config(),
+        smoke_test::run,
+    )
+}
+
+/// Offers `service` over both TCP and UDP.
+fn service(service: Service) -> SocketAddr {
+    server_with(Options {
+        service: Some(service),
+        udp: true,
+        ..Options::default()
+    })
+}
+
+fn server_with(options: Options) -> SocketAddr {
+    testing::spawn(
+        Transport::Tcp,
+        smoke_test::default_config(),
+        move |config| smoke_test::serve(config, options),
+    )
+}
+
+#[test]
+fn echoes_everything_back_after_half_close() {
+    let address = server();
+    let mut client = TcpClient::connect(address);
+
+    client.send("hello\n");
+    client.send([0u8, 159, 146, 150, 255]);
+    client.shutdown_write();
+
+    client.expect(b"hello\n\x00\x9f\x92\x96\xff");
+    client.expect_closed();
+}
+
+#[test]
+fn serves_five_clients_at_once() {
+    let address = server();
+
+    let clients: Vec<_> = (0..5)
+        .map(|n| {
+            thread::spawn(move || {
+                let mut client = TcpClient::connect(address);
+                let payload = vec![n as u8; 100_000];
+
+                client.send(&payload);
+                client.shutdown_write();
+                client.expect(&payload);
+            })
+        })
+        .collect();
+
+    for client in clients {
+        client.join().unwrap();
+    }
+}
+
+#[test]
+fn echoes_data_as_it_arrives() {
+    let mut client = TcpClient::connect(server());
+
+    for chunk in ["first\n", "second\n"] {
+        client.send(chunk);
+        client.expect(chunk.as_bytes());
+    }
+
+    client.shutdown_write();
+    client.expect_closed();
+}
+
+#[test]
+fn closes_after_echoing_max_bytes() {
+    let mut client = TcpClient::connect(server_with(Options {
+        max_bytes: Some(5),
+        ..Options::default()
+    }));
+
+    client.send("hello world");
+    client.expect(b"hello");
+    client.expect_closed();
+}
+
+#[test]
+fn throttles_to_the_configured_rate() {
+    let mut client = TcpClient::connect(server_with(Options {
+        rate: Some(10_000),
+        ..Options::default()
+    }));
+    let payload = vec![7u8; 5_000];
+    let started = Instant::now();
+
+    client.send(&payload);
+    client.shutdown_write();
+    client.expect(&payload);
+
+    assert!(started.elapsed() >= Duration::from_millis(300));
+}
+
+#[test]
+fn echoes_datagrams() {
+    let client = UdpClient::connect(service(Service::Echo));
+
+    client.send("ping");
+    client.expect("ping");
+}
+
+#[test]
+fn cuts_long_datagram_replies() {
+    let client = UdpClient::connect(service(Service::Echo));
+
+    client.send([b'x'; 2000]);
+    client.expect([b'x'; 512]);
+}
+
+#[test]
+fn serves_udp_only_when_asked() {
+    let client = UdpClient::connect(server());
+
+    client.send("ping");
+    client.expect_silence(Duration::from_millis(100));
+}
+
+#[test]
+fn discards_everything() {
+    let address = service(Service::Discard);
+    let mut client = TcpClient::connect(address);
+
+    client.send(vec![1u8; 100_000]);
+    client.expect_silence(Duration::from_millis(100));
+    client.shutdown_write();
+    client.expect_closed();
+
+    let client = UdpClient::connect(address);
+    client.send("ignored");
+    client.expect_silence(Duration::from_millis(100));
+}
+
+#[test]
+fn generates_rotating_lines_of_characters() {
+    let address = server_with(Options {
+        service: Some(Service::Chargen),
+        max_bytes: Some(74 * 96),
+        udp: true,
+        ..Options::default()
+    });
+    let mut client = TcpClient::connect(address);
+
+    let first = client.read_line();
+    assert_eq!(first.len(), 73);
+    assert!(first.starts_with(" !\"#$%&'()*+,-./0123456789"));
+    assert!(first.ends_with('\r'));
+    assert!(client.read_line().starts_with("!\"#$%&"));
+
+    for _ in 2..95 {
+        client.read_line();
+    }
+    assert_eq!(client.read_line(), first);
+    client.expect_closed();
+
+    let client = UdpClient::connect(address);
+    client.send("");
+    let lines = client.recv();
+    assert!(lines.len() <= 512);
+    assert!(lines.starts_with(b" !\"#$%&"));
+}
+
+#[test]
+fn keeps_generating_until_the_client_hangs_up() {
+    let mut client = TcpClient::connect(service(Service::Chargen));
+
+    assert_eq!(client.read_exact(1_000_000).len(), 1_000_000);
+}
+
+#[test]
+fn tells_the_date_and_time() {
+    let address = service(Service::Daytime);
+    let mut client = TcpClient::connect(address);
+
+    let line = client.read_line();
+    assert!(line.ends_with("-UTC\r"), "{line}");
+    client.expect_closed();
+
+    let client = UdpClient::connect(address);
+    client.send("");
+    assert!(client.recv().ends_with(b"-UTC\r\n"));
+}
+
+#[test]
+fn tells_the_time_in_seconds_since_1900() {
+    let address = service(Service::Time);
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+        + 2_208_988_800;
+    let close_to_now = |bytes: Vec<u8>| {
+        let seconds = u32::from_be_bytes(bytes.try_into().unwrap());
+        (seconds.wrapping_sub(now as u32) as i32).abs() < 5
+    };
+
+    let mut client = TcpClient::connect(address);
+    assert!(close_to_now(client.read_exact(4)));
+    client.expect_closed();
+
+    let client = UdpClient::connect(address);
+    client.send("");
+    assert!(close_to_now(client.recv()));
+}
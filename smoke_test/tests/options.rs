@@ -0,0 +1,89 @@
+use shared::config::Config;
+use smoke_test::{Options, Service};
+use std::io::Result;
+
+fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+fn options<E>(env: E, flags: &[&str]) -> Result<(Options, Config)>
+where
+    E: Fn(&str) -> Option<String>,
+{
+    let config = smoke_test::default_config().apply(env, args(flags))?;
+
+    Ok((Options::from_config(&config)?, config))
+}
+
+#[test]
+fn takes_its_own_flags_alongside_the_shared_ones() {
+    let (options, config) = options(
+        |_| None,
+        &[
+            "--port",
+            "7",
+            "--service",
+            "chargen",
+            "--rate",
+            "1024",
+            "--max-bytes=10",
+            "--udp=true",
+            "--workers=2",
+        ],
+    )
+    .unwrap();
+
+    assert_eq!(
+        options,
+        Options {
+            service: Some(Service::Chargen),
+            rate: Some(1024),
+            max_bytes: Some(10),
+            udp: true,
+        }
+    );
+    assert_eq!((config.port, config.workers), (7, 2));
+}
+
+#[test]
+fn prefers_flags_to_the_environment() {
+    let env = |key: &str| match key {
+        "PROTOHACKERS_ECHO_RATE" => Some("100".to_string()),
+        "PROTOHACKERS_ECHO_MAX_BYTES" => Some("50".to_string()),
+        _ => None,
+    };
+
+    let (options, _) = options(env, &["--rate", "0"]).unwrap();
+
+    assert_eq!(
+        options,
+        Options {
+            service: None,
+            rate: None,
+            max_bytes: Some(50),
+            udp: false,
+        }
+    );
+}
+
+#[test]
+fn rejects_bad_values() {
+    for bad in [
+        &["--rate", "fast"][..],
+        &["--max-bytes"],
+        &["--service", "finger"],
+        &["--udp", "yes"],
+    ] {
+        assert!(options(|_| None, bad).is_err());
+    }
+}
+
+#[test]
+fn knows_the_well_known_ports() {
+    assert_eq!(Service::for_port(7), Some(Service::Echo));
+    assert_eq!(Service::for_port(9), Some(Service::Discard));
+    assert_eq!(Service::for_port(13), Some(Service::Daytime));
+    assert_eq!(Service::for_port(19), Some(Service::Chargen));
+    assert_eq!(Service::for_port(37), Some(Service::Time));
+    assert_eq!(Service::for_port(8080), None);
+}
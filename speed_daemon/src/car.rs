@@ -42,7 +42,7 @@ impl Car {
                         timestamp: sighting.timestamp,
                     },
                 );
-                sightings.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+                sightings.sort_by_key(|point| point.timestamp);
             }
             None => {
                 self.roads.insert(
@@ -0,0 +1,202 @@
+mod car;
+pub mod models;
+pub mod read;
+mod write;
+
+use car::Car;
+use models::{IAmCamera, Message, Sighting};
+use read::consume_messages;
+use shared::{
+    async_server::{AsyncServer, Context, Stream},
+    config::Config,
+    error, metrics,
+};
+use std::{
+    collections::HashMap,
+    io::{ErrorKind, Result},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{
+    io::{self, AsyncWriteExt},
+    sync::mpsc::{self, UnboundedReceiver, UnboundedSender},
+    time::{self, Instant, Interval, MissedTickBehavior},
+};
+use write::{write_error, write_heartbeat, write_ticket};
+
+type Dispatchers = Arc<Mutex<HashMap<u16, UnboundedSender<Message>>>>;
+
+/// Defaults for `run`, before flags and environment variables are applied.
+pub fn default_config() -> Config {
+    Config::new(2)
+}
+
+/// Serves the protocol as configured by `config` until the process is asked to shut down.
+pub fn run(config: &Config) -> Result<()> {
+    let server = AsyncServer::builder().config(config).build()?;
+
+    let (camera_sender, camera_receiver) = mpsc::unbounded_channel();
+    let dispatchers = Dispatchers::default();
+
+    server.spawn(orchestrate(Arc::clone(&dispatchers), camera_receiver));
+
+    server.run(move |stream, _: Context| {
+        handle_client(camera_sender.clone(), Arc::clone(&dispatchers), stream)
+    })
+}
+
+/// Records sightings and hands any new tickets to the dispatcher for their road. Runs after
+/// every sighting and every dispatcher registration, so tickets held back for a road without
+/// a dispatcher go out as soon as one connects.
+async fn orchestrate(
+    dispatch_senders: Dispatchers,
+    mut camera_receiver: UnboundedReceiver<Message>,
+) {
+    let mut cars = HashMap::<String, Car>::new();
+    let tickets_dispatched = metrics::counter(
+        "speed_daemon_tickets_dispatched_total",
+        "Tickets handed to a dispatcher.",
+        &[],
+    );
+
+    while let Some(event) = camera_receiver.recv().await {
+        if let Message::Sighting(message) = event {
+            if let Some(car) = cars.get_mut(&message.plate.to_string()) {
+                car.add_sighting(message);
+            } else {
+                let mut car = Car::new(message.plate.clone());
+                car.add_sighting(message.clone());
+
+                cars.insert(message.plate.clone(), car);
+            }
+        }
+
+        let mut dispatchers = dispatch_senders.lock().unwrap();
+
+        for car in cars.values_mut() {
+            for ticket in car.get_outstanding_tickets() {
+                if let Some(sender) = dispatchers.get(&ticket.road) {
+                    if let Err(e) = sender.send(Message::Ticket(ticket.clone())) {
+                        error!("Ticket dispatch error: {}", e);
+                        dispatchers.retain(|_, sender| !sender.is_closed());
+                        continue;
+                    }
+                    car.mark_ticket_dispatched(ticket.clone());
+                    tickets_dispatched.inc();
+                }
+            }
+        }
+    }
+}
+
+async fn handle_client(
+    camera_sender: UnboundedSender<Message>,
+    dispatchers: Dispatchers,
+    stream: Stream,
+) -> Result<()> {
+    let (reader, mut writer) = io::split(stream);
+    let (message_sender, mut messages) = mpsc::unbounded_channel();
+    let reading = tokio::spawn(consume_messages(reader, message_sender));
+
+    let mut some_camera: Option<IAmCamera> = None;
+    let mut some_dispatcher: Option<UnboundedReceiver<Message>> = None;
+    let mut some_heartbeat: Option<Interval> = None;
+
+    let result = loop {
+        let mut buffer = Vec::new();
+
+        let written = tokio::select! {
+            _ = tick(&mut some_heartbeat) => write_heartbeat(&mut buffer),
+            Some(Message::Ticket(ticket)) = next_ticket(&mut some_dispatcher) => {
+                write_ticket(&mut buffer, ticket)
+            }
+            message = messages.recv() => match message {
+                Some(Ok(Message::IAmCamera(message))) => {
+                    if some_dispatcher.is_none() && some_camera.is_none() {
+                        some_camera = Some(message.clone());
+                        Ok(())
+                    } else {
+                        write_error(&mut buffer, "Client already identified".to_string())
+                    }
+                }
+                Some(Ok(Message::IAmDispatcher(message))) => {
+                    if some_dispatcher.is_none() && some_camera.is_none() {
+                        let (sender, receiver) = mpsc::unbounded_channel();
+                        some_dispatcher = Some(receiver);
+                        for road in message.roads.iter() {
+                            dispatchers.lock().unwrap().insert(*road, sender.clone());
+                        }
+                        camera_sender.send(Message::IAmDispatcher(message)).ok();
+                        Ok(())
+                    } else {
+                        write_error(&mut buffer, "Client already identified".to_string())
+                    }
+                }
+                Some(Ok(Message::Plate(message))) => {
+                    if let Some(ref mut camera) = some_camera {
+                        camera_sender
+                            .send(Message::Sighting(Sighting {
+                                plate: message.plate.to_string(),
+                                timestamp: message.timestamp,
+                                road: camera.road,
+                                mile: camera.mile,
+                                limit: camera.limit,
+                            }))
+                            .ok();
+                        Ok(())
+                    } else {
+                        write_error(&mut buffer, "Unknown client sending plate".to_string())
+                    }
+                }
+                Some(Ok(Message::WantHeartbeat(message))) => {
+                    if some_heartbeat.is_none() {
+                        if message.interval > 0 {
+                            let period = Duration::from_millis(u64::from(message.interval) * 100);
+                            let mut interval = time::interval_at(Instant::now() + period, period);
+                            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+                            some_heartbeat = Some(interval);
+                        }
+                        Ok(())
+                    } else {
+                        write_error(&mut buffer, "Heartbeat already set".to_string())
+                    }
+                }
+                Some(Ok(_)) => Ok(()),
+                Some(Err(e)) if e.kind() == ErrorKind::InvalidData => {
+                    write_error(&mut buffer, "Invalid message".to_string())
+                }
+                Some(Err(_)) | None => break Ok(()),
+            },
+        };
+
+        if let Err(e) = written {
+            break Err(e);
+        }
+
+        if let Err(e) = writer.write_all(&buffer).await {
+            break Err(e);
+        }
+    };
+
+    reading.abort();
+
+    result
+}
+
+/// Waits for the next heartbeat, or forever if the client never asked for them.
+async fn tick(heartbeat: &mut Option<Interval>) {
+    match heartbeat {
+        Some(interval) => {
+            interval.tick().await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+/// Waits for the next ticket, or forever if the client is not a dispatcher.
+async fn next_ticket(dispatcher: &mut Option<UnboundedReceiver<Message>>) -> Option<Message> {
+    match dispatcher {
+        Some(receiver) => receiver.recv().await,
+        None => std::future::pending().await,
+    }
+}
@@ -1,18 +1,15 @@
-use std::io::BufReader;
+use shared::codec::{Decode, Encode};
 
-#[derive(Clone, PartialEq, Debug)]
-pub struct ClientError {
-    pub message: String,
-}
-
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug, Encode, Decode)]
 pub struct Plate {
+    #[codec(len = u8)]
     pub plate: String,
     pub timestamp: u32,
 }
 
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug, Encode, Decode)]
 pub struct Ticket {
+    #[codec(len = u8)]
     pub plate: String,
     pub road: u16,
     pub mile1: u16,
@@ -31,23 +28,21 @@ pub struct Sighting {
     pub limit: u16,
 }
 
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug, Encode, Decode)]
 pub struct WantHeartbeat {
     pub interval: u32,
 }
 
-#[derive(Clone, PartialEq, Debug)]
-pub struct Heartbeat {}
-
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug, Encode, Decode)]
 pub struct IAmCamera {
     pub road: u16,
     pub mile: u16,
     pub limit: u16,
 }
 
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug, Encode, Decode)]
 pub struct IAmDispatcher {
+    #[codec(len = u8)]
     pub roads: Vec<u16>,
 }
 
@@ -60,7 +55,3 @@ pub enum Message {
     IAmDispatcher(IAmDispatcher),
     Sighting(Sighting),
 }
-
-pub struct MessageIterator<R> {
-    pub reader: BufReader<R>,
-}
@@ -1,92 +1,43 @@
-use std::io::ErrorKind;
-use std::io::{prelude::Read, BufReader, Error, Result};
-use std::str;
-
-use crate::models::{IAmCamera, IAmDispatcher, Message, MessageIterator, Plate, WantHeartbeat};
-
-fn read_char<R: Read>(reader: &mut BufReader<R>) -> std::io::Result<u8> {
-    let mut buffer = [0; 1];
-    reader.read_exact(&mut buffer)?;
-
-    Ok(buffer[0])
-}
-
-fn read_string<R: Read>(reader: &mut BufReader<R>) -> std::io::Result<String> {
-    let mut length_buffer = [0; 1];
-    reader.read_exact(&mut length_buffer)?;
-
-    let mut string_buffer = vec![0; length_buffer[0] as usize];
-    reader.read_exact(&mut string_buffer)?;
-
-    match str::from_utf8(&string_buffer) {
-        Ok(string) => Ok(string.to_string()),
-        Err(_) => Err(Error::from(ErrorKind::InvalidData)),
-    }
-}
-
-fn read_vec<R: Read>(reader: &mut BufReader<R>) -> std::io::Result<Vec<u16>> {
-    let mut length_buffer = [0; 1];
-    reader.read_exact(&mut length_buffer)?;
-
-    let mut numbers = Vec::new();
-    for _ in 0..length_buffer[0] as usize {
-        let mut buffer = [0u8; 2];
-        reader.read_exact(&mut buffer)?;
-        numbers.insert(0, u16::from_be_bytes(buffer))
-    }
-
-    Ok(numbers)
-}
-
-fn read_u32<R: Read>(reader: &mut BufReader<R>) -> std::io::Result<u32> {
-    let mut buffer = [0u8; 4];
-    reader.read_exact(&mut buffer)?;
-
-    Ok(u32::from_be_bytes(buffer))
+use shared::codec::{Decode, FrameDecoder};
+use std::io::{Error, ErrorKind, Result};
+use tokio::io::AsyncRead;
+use tokio::sync::mpsc;
+
+use crate::models::{IAmCamera, IAmDispatcher, Message, Plate, WantHeartbeat};
+
+/// Decodes one client message from the front of `input` and advances past it.
+pub fn decode_message(input: &mut &[u8]) -> Result<Message> {
+    let message = match u8::decode(input)? {
+        0x20 => Message::Plate(Plate::decode(input)?),
+        0x40 => Message::WantHeartbeat(WantHeartbeat::decode(input)?),
+        0x80 => Message::IAmCamera(IAmCamera::decode(input)?),
+        0x81 => Message::IAmDispatcher(IAmDispatcher::decode(input)?),
+        _ => return Err(Error::from(ErrorKind::InvalidData)),
+    };
+
+    Ok(message)
 }
 
-fn read_u16<R: Read>(reader: &mut BufReader<R>) -> std::io::Result<u16> {
-    let mut buffer = [0u8; 2];
-    reader.read_exact(&mut buffer)?;
-
-    Ok(u16::from_be_bytes(buffer))
-}
-
-impl<R: Read> Iterator for MessageIterator<R> {
-    type Item = Result<Message>;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        match read_char(&mut self.reader) {
-            Ok(0x20) => {
-                let plate = read_string(&mut self.reader).ok()?;
-                let timestamp = read_u32(&mut self.reader).ok()?;
-
-                Some(Ok(Message::Plate(Plate { plate, timestamp })))
+/// Forwards messages read from `reader` to `sender` until the stream ends. A read error is
+/// sent as the final item.
+pub async fn consume_messages<R>(mut reader: R, sender: mpsc::UnboundedSender<Result<Message>>)
+where
+    R: AsyncRead + Unpin,
+{
+    let mut decoder = FrameDecoder::new(decode_message);
+
+    loop {
+        match decoder.read_async(&mut reader).await {
+            Ok(Some(message)) => {
+                if sender.send(Ok(message)).is_err() {
+                    break;
+                }
             }
-            Ok(0x40) => {
-                let interval = read_u32(&mut self.reader).ok()?;
-
-                Some(Ok(Message::WantHeartbeat(WantHeartbeat { interval })))
-            }
-            Ok(0x80) => {
-                let road = read_u16(&mut self.reader).ok()?;
-                let mile = read_u16(&mut self.reader).ok()?;
-                let limit = read_u16(&mut self.reader).ok()?;
-
-                Some(Ok(Message::IAmCamera(IAmCamera { road, mile, limit })))
+            Ok(None) => break,
+            Err(e) => {
+                sender.send(Err(e)).ok();
+                break;
             }
-            Ok(0x81) => {
-                let roads = read_vec(&mut self.reader).ok()?;
-
-                Some(Ok(Message::IAmDispatcher(IAmDispatcher { roads })))
-            }
-            Ok(_) => Some(Err(Error::from(ErrorKind::InvalidData))),
-            Err(e) if e.kind() == ErrorKind::WouldBlock => None,
-            Err(e) => Some(Err(e)),
         }
     }
 }
-
-pub fn consume_messages<R>(reader: BufReader<R>) -> MessageIterator<R> {
-    MessageIterator { reader }
-}
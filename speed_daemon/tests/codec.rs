@@ -0,0 +1,74 @@
+use proptest::{collection::vec, prelude::*};
+use shared::codec::{self, Decode, Encode};
+use speed_daemon::models::{IAmCamera, IAmDispatcher, Plate, Ticket, WantHeartbeat};
+use std::fmt::Debug;
+
+/// Plates are at most 255 bytes on the wire.
+const PLATE: &str = "[A-Z0-9]{0,255}";
+
+fn round_trip<T: Encode + Decode + PartialEq + Debug>(value: T) -> Result<(), TestCaseError> {
+    let bytes = codec::to_bytes(&value).unwrap();
+    prop_assert_eq!(codec::from_bytes::<T>(&bytes).unwrap(), value);
+
+    Ok(())
+}
+
+proptest! {
+    #[test]
+    fn round_trips_plates(plate in PLATE, timestamp: u32) {
+        round_trip(Plate { plate, timestamp })?;
+    }
+
+    #[test]
+    fn round_trips_tickets(
+        plate in PLATE,
+        (road, mile1, mile2, speed) in any::<(u16, u16, u16, u16)>(),
+        (timestamp1, timestamp2) in any::<(u32, u32)>(),
+    ) {
+        round_trip(Ticket { plate, road, mile1, timestamp1, mile2, timestamp2, speed })?;
+    }
+
+    #[test]
+    fn round_trips_heartbeat_requests(interval: u32) {
+        round_trip(WantHeartbeat { interval })?;
+    }
+
+    #[test]
+    fn round_trips_cameras(road: u16, mile: u16, limit: u16) {
+        round_trip(IAmCamera { road, mile, limit })?;
+    }
+
+    #[test]
+    fn round_trips_dispatchers(roads in vec(any::<u16>(), 0..=255)) {
+        round_trip(IAmDispatcher { roads })?;
+    }
+}
+
+#[test]
+fn encodes_the_example_ticket() {
+    let ticket = Ticket {
+        plate: "UN1X".to_string(),
+        road: 66,
+        mile1: 100,
+        timestamp1: 123456,
+        mile2: 110,
+        timestamp2: 123816,
+        speed: 10000,
+    };
+
+    assert_eq!(
+        codec::to_bytes(&ticket).unwrap(),
+        [
+            0x04, 0x55, 0x4e, 0x31, 0x58, 0x00, 0x42, 0x00, 0x64, 0x00, 0x01, 0xe2, 0x40, 0x00,
+            0x6e, 0x00, 0x01, 0xe3, 0xa8, 0x27, 0x10
+        ]
+    );
+}
+
+#[test]
+fn decodes_the_example_dispatcher() {
+    let dispatcher: IAmDispatcher =
+        codec::from_bytes(&[0x03, 0x00, 0x42, 0x01, 0x70, 0x13, 0x88]).unwrap();
+
+    assert_eq!(dispatcher.roads, [66, 368, 5000]);
+}
@@ -0,0 +1,210 @@
+use shared::testing::{self, TcpClient, Transport};
+use std::{
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+fn server() -> SocketAddr {
+    testing::spawn(
+        Transport::Tcp,
+        speed_daemon::default_config(),
+        speed_daemon::run,
+    )
+}
+
+fn string(value: &str) -> Vec<u8> {
+    let mut bytes = vec![value.len() as u8];
+    bytes.extend(value.as_bytes());
+    bytes
+}
+
+fn camera(address: SocketAddr, road: u16, mile: u16, limit: u16) -> TcpClient {
+    let mut client = TcpClient::connect(address);
+    let mut message = vec![0x80];
+    message.extend(road.to_be_bytes());
+    message.extend(mile.to_be_bytes());
+    message.extend(limit.to_be_bytes());
+    client.send(message);
+    client
+}
+
+fn dispatcher(address: SocketAddr, roads: &[u16]) -> TcpClient {
+    let mut client = TcpClient::connect(address);
+    let mut message = vec![0x81, roads.len() as u8];
+    for road in roads {
+        message.extend(road.to_be_bytes());
+    }
+    client.send(message);
+    client
+}
+
+fn plate(plate: &str, timestamp: u32) -> Vec<u8> {
+    let mut message = vec![0x20];
+    message.extend(string(plate));
+    message.extend(timestamp.to_be_bytes());
+    message
+}
+
+fn ticket(
+    plate: &str,
+    road: u16,
+    mile1: u16,
+    timestamp1: u32,
+    mile2: u16,
+    timestamp2: u32,
+    speed: u16,
+) -> Vec<u8> {
+    let mut message = vec![0x21];
+    message.extend(string(plate));
+    message.extend(road.to_be_bytes());
+    message.extend(mile1.to_be_bytes());
+    message.extend(timestamp1.to_be_bytes());
+    message.extend(mile2.to_be_bytes());
+    message.extend(timestamp2.to_be_bytes());
+    message.extend(speed.to_be_bytes());
+    message
+}
+
+fn error(message: &str) -> Vec<u8> {
+    let mut bytes = vec![0x10];
+    bytes.extend(string(message));
+    bytes
+}
+
+#[test]
+fn tickets_the_example_car() {
+    let address = server();
+
+    let mut first = camera(address, 123, 8, 60);
+    first.send(plate("UN1X", 0));
+
+    let mut second = camera(address, 123, 9, 60);
+    second.send(plate("UN1X", 45));
+
+    let mut dispatcher = dispatcher(address, &[123]);
+    dispatcher.expect(ticket("UN1X", 123, 8, 0, 9, 45, 8000));
+}
+
+#[test]
+fn reassembles_messages_split_across_pauses() {
+    let address = server();
+
+    let mut first = camera(address, 123, 8, 60);
+    for byte in plate("UN1X", 0) {
+        first.send([byte]);
+        std::thread::sleep(Duration::from_millis(20));
+    }
+
+    let mut second = camera(address, 123, 9, 60);
+    second.send(plate("UN1X", 45));
+
+    let mut dispatcher = dispatcher(address, &[123]);
+    dispatcher.expect(ticket("UN1X", 123, 8, 0, 9, 45, 8000));
+}
+
+#[test]
+fn holds_tickets_until_a_dispatcher_connects() {
+    let address = server();
+
+    let mut first = camera(address, 7, 0, 50);
+    let mut second = camera(address, 7, 10, 50);
+    first.send(plate("LATE1", 1000));
+    second.send(plate("LATE1", 1300));
+
+    std::thread::sleep(Duration::from_millis(200));
+
+    let mut unrelated = dispatcher(address, &[8]);
+    let mut dispatcher = dispatcher(address, &[6, 7]);
+
+    dispatcher.expect(ticket("LATE1", 7, 0, 1000, 10, 1300, 12000));
+    unrelated.expect_silence(Duration::from_millis(200));
+}
+
+#[test]
+fn issues_at_most_one_ticket_per_day() {
+    let address = server();
+    let mut dispatcher = dispatcher(address, &[42]);
+
+    let mut first = camera(address, 42, 0, 60);
+    let mut second = camera(address, 42, 10, 60);
+    let mut third = camera(address, 42, 20, 60);
+
+    first.send(plate("SPEEDY", 0));
+    second.send(plate("SPEEDY", 300));
+    dispatcher.expect(ticket("SPEEDY", 42, 0, 0, 10, 300, 12000));
+
+    third.send(plate("SPEEDY", 600));
+    dispatcher.expect_silence(Duration::from_millis(300));
+}
+
+#[test]
+fn does_not_ticket_cars_within_the_limit() {
+    let address = server();
+    let mut dispatcher = dispatcher(address, &[5]);
+
+    let mut first = camera(address, 5, 0, 60);
+    let mut second = camera(address, 5, 1, 60);
+    first.send(plate("SLOW", 0));
+    second.send(plate("SLOW", 60));
+
+    dispatcher.expect_silence(Duration::from_millis(300));
+}
+
+#[test]
+fn sends_heartbeats_at_the_requested_interval() {
+    let mut client = TcpClient::connect(server());
+
+    let started = Instant::now();
+    client.send([0x40, 0, 0, 0, 2]);
+
+    for _ in 0..3 {
+        client.expect([0x41]);
+    }
+
+    let elapsed = started.elapsed();
+    assert!(elapsed >= Duration::from_millis(550), "{elapsed:?}");
+    assert!(elapsed < Duration::from_secs(2), "{elapsed:?}");
+}
+
+#[test]
+fn rejects_a_second_heartbeat_request() {
+    let mut client = TcpClient::connect(server());
+
+    client.send([0x40, 0, 0, 0, 100]);
+    client.send([0x40, 0, 0, 0, 100]);
+    client.expect(error("Heartbeat already set"));
+}
+
+#[test]
+fn accepts_the_longest_heartbeat_interval() {
+    let mut client = TcpClient::connect(server());
+
+    client.send([0x40, 0xff, 0xff, 0xff, 0xff]);
+    client.send([0x40, 0, 0, 0, 100]);
+    client.expect(error("Heartbeat already set"));
+}
+
+#[test]
+fn rejects_plates_from_unidentified_clients() {
+    let mut client = TcpClient::connect(server());
+
+    client.send(plate("NOPE", 1));
+    client.expect(error("Unknown client sending plate"));
+}
+
+#[test]
+fn rejects_clients_identifying_twice() {
+    let mut client = camera(server(), 1, 1, 1);
+
+    client.send([0x81, 1, 0, 1]);
+    client.expect(error("Client already identified"));
+}
+
+#[test]
+fn rejects_unknown_messages_and_disconnects() {
+    let mut client = TcpClient::connect(server());
+
+    client.send([0xff]);
+    client.expect(error("Invalid message"));
+    client.expect_closed();
+}
@@ -0,0 +1,85 @@
+use shared::{config::Config, metrics, signal, warn};
+use std::{
+    collections::HashMap,
+    io::{ErrorKind, Result},
+    net::UdpSocket,
+    str,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+/// How often `run` wakes from `recv_from` to check for a shutdown signal.
+const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Defaults for `run`, before flags and environment variables are applied.
+pub fn default_config() -> Config {
+    Config::new(1)
+}
+
+/// Serves the protocol as configured by `config` until the process is asked to shut down.
+pub fn run(config: &Config) -> Result<()> {
+    let mut store = HashMap::from([(
+        "version".to_string(),
+        "Sam's Key-Value Store 1.0".to_string(),
+    )]);
+
+    let socket = UdpSocket::bind(config.address())?;
+    socket.set_read_timeout(Some(SHUTDOWN_POLL_INTERVAL))?;
+
+    let closed = Arc::new(AtomicBool::new(false));
+    let on_signal = Arc::clone(&closed);
+    signal::on_terminate(move || on_signal.store(true, Ordering::SeqCst))?;
+
+    if let Some(address) = config.metrics_address() {
+        metrics::serve(&address)?;
+    }
+
+    let mut buf = vec![0; 1000];
+
+    while !closed.load(Ordering::SeqCst) {
+        let (amt, src) = match socket.recv_from(&mut buf) {
+            Ok(received) => received,
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => continue,
+            Err(e) => return Err(e),
+        };
+
+        let reply = match parse_request(&buf[..amt]) {
+            Some(Request::Retrieve(key)) => {
+                let value = store.get(key).map_or("", String::as_str);
+                format!("{}={}", key, value)
+            }
+            Some(Request::Insert("version", _)) | None => continue,
+            Some(Request::Insert(key, value)) => {
+                store.insert(key.to_string(), value.to_string());
+                continue;
+            }
+        };
+
+        if let Err(e) = socket.send_to(reply.as_bytes(), src) {
+            warn!("Failed to reply to {}: {}", src, e);
+        }
+    }
+
+    Ok(())
+}
+
+/// A request datagram: a key to look up, or a key and the value to store for it.
+#[derive(Debug, PartialEq)]
+pub enum Request<'a> {
+    Retrieve(&'a str),
+    Insert(&'a str, &'a str),
+}
+
+/// Splits `datagram` on its first `=`, or returns `None` if it isn't UTF-8, since keys and
+/// values are text.
+pub fn parse_request(datagram: &[u8]) -> Option<Request<'_>> {
+    let request = str::from_utf8(datagram).ok()?;
+
+    Some(match request.split_once('=') {
+        Some((key, value)) => Request::Insert(key, value),
+        None => Request::Retrieve(request),
+    })
+}
@@ -0,0 +1,80 @@
+use shared::testing::{self, Transport, UdpClient};
+use std::{net::SocketAddr, time::Duration};
+
+fn server() -> SocketAddr {
+    testing::spawn(
+        Transport::Udp,
+        unusual_database_program::default_config(),
+        unusual_database_program::run,
+    )
+}
+
+#[test]
+fn stores_and_retrieves_values() {
+    let client = UdpClient::connect(server());
+
+    client.send("foo=bar");
+    client.send("foo");
+    client.expect("foo=bar");
+
+    client.send("foo=baz");
+    client.send("foo");
+    client.expect("foo=baz");
+}
+
+#[test]
+fn splits_on_the_first_equals_sign() {
+    let client = UdpClient::connect(server());
+
+    client.send("foo=bar=baz");
+    client.send("foo");
+    client.expect("foo=bar=baz");
+
+    client.send("=empty key");
+    client.send("");
+    client.expect("=empty key");
+
+    client.send("empty value=");
+    client.send("empty value");
+    client.expect("empty value=");
+}
+
+#[test]
+fn returns_an_empty_value_for_missing_keys() {
+    let client = UdpClient::connect(server());
+
+    client.send("missing");
+    client.expect("missing=");
+}
+
+#[test]
+fn does_not_reply_to_inserts() {
+    let client = UdpClient::connect(server());
+
+    client.send("key=value");
+    client.expect_silence(Duration::from_millis(200));
+}
+
+#[test]
+fn reports_a_read_only_version() {
+    let client = UdpClient::connect(server());
+
+    client.send("version=hacked");
+    client.send("version");
+
+    let reply = String::from_utf8(client.recv()).unwrap();
+    assert!(reply.starts_with("version="));
+    assert_ne!(reply, "version=hacked");
+}
+
+#[test]
+fn ignores_requests_that_are_not_utf8() {
+    let client = UdpClient::connect(server());
+
+    client.send(b"\xff\xfe");
+    client.send(b"foo=\xff");
+    client.expect_silence(Duration::from_millis(100));
+
+    client.send("version");
+    client.expect("version=Sam's Key-Value Store 1.0");
+}
@@ -0,0 +1,256 @@
+use shared::{
+    config::{parse, Config, Flag, DEFAULT_IDLE_TIMEOUT},
+    line::{self, LineReader},
+    server::{Context, Server},
+};
+use std::{
+    collections::HashMap,
+    io::{prelude::*, BufReader, ErrorKind, Result},
+    sync::Mutex,
+};
+
+/// Flags for limiting what clients can store, read by `Options::from_config`.
+pub const FLAGS: [Flag; 1] = [Flag {
+    name: "max-file-size",
+    value: "<bytes>",
+    env: "PROTOHACKERS_MAX_FILE_SIZE",
+    help: "Largest file accepted by PUT",
+}];
+
+/// The largest file accepted when `--max-file-size` isn't given.
+pub const DEFAULT_MAX_FILE_SIZE: u64 = 1024 * 1024;
+
+/// Limits on a session, beyond the listener settings in `Config`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Options {
+    pub max_line_length: usize,
+    pub max_file_size: u64,
+}
+
+impl Options {
+    /// Reads `--max-line-length` and the values given for `FLAGS` in `config`.
+    pub fn from_config(config: &Config) -> Result<Options> {
+        let max_file_size = match config.flag("max-file-size") {
+            Some(value) => parse("max-file-size", value)?,
+            None => DEFAULT_MAX_FILE_SIZE,
+        };
+
+        Ok(Options {
+            max_line_length: config.max_line_length,
+            max_file_size,
+        })
+    }
+}
+
+/// Every file's revisions, shared by all sessions.
+#[derive(Default)]
+pub struct Storage {
+    files: Mutex<HashMap<String, Blob>>,
+}
+
+struct Blob {
+    pub revisions: Vec<String>,
+}
+
+/// Defaults for `run`, before flags and environment variables are applied.
+pub fn default_config() -> Config {
+    Config {
+        idle_timeout: Some(DEFAULT_IDLE_TIMEOUT),
+        ..Config::new(100).with_flags(&FLAGS)
+    }
+}
+
+/// Serves the protocol as configured by `config` until the process is asked to shut down.
+pub fn run(config: &Config) -> Result<()> {
+    let options = Options::from_config(config)?;
+    let server = Server::builder().config(config).build()?;
+    let storage = Storage::default();
+
+    server.run(move |stream, _: Context| {
+        handle_session(BufReader::new(&stream), &stream, options, &storage)
+    })
+}
+
+/// Answers the requests read from `reader` until it ends or the client makes an illegal one.
+pub fn handle_session<R: BufRead, W: Write>(
+    reader: R,
+    mut writer: W,
+    options: Options,
+    storage: &Storage,
+) -> Result<()> {
+    let mut reader = LineReader::new(reader, options.max_line_length);
+
+    writeln!(writer, "READY")?;
+
+    loop {
+        let mut line = String::new();
+
+        match reader.read_line(&mut line) {
+            Ok(_) => {}
+            Err(e) if line::is_too_long(&e) => {
+                writeln!(writer, "ERR line too long")?;
+                writeln!(writer, "READY")?;
+                continue;
+            }
+            Err(e) => return Err(e),
+        }
+
+        match line.trim().to_lowercase().split(' ').collect::<Vec<&str>>()[..] {
+            ["help"] => {
+                writeln!(writer, "OK usage: HELP|GET|PUT|LIST")?;
+            }
+            ["put", file, length] => 'put: {
+                let allowed_chars = |c: char| c.is_alphanumeric() || "/._-".contains(c);
+
+                if !file.starts_with('/') || !file.chars().all(allowed_chars) {
+                    writeln!(writer, "ERR illegal file name")?;
+                    break 'put;
+                }
+
+                let Ok(size) = length.parse::<u64>() else {
+                    writeln!(writer, "ERR usage: PUT file length newline data")?;
+                    break 'put;
+                };
+
+                // The data would be read as requests if we carried on without it.
+                if size > options.max_file_size {
+                    writeln!(writer, "ERR file too large")?;
+                    return Ok(());
+                }
+
+                // Read as it arrives, rather than allocating what the client claims up front.
+                let mut buffer = Vec::new();
+                reader.get_mut().take(size).read_to_end(&mut buffer)?;
+
+                if (buffer.len() as u64) < size {
+                    return Err(ErrorKind::UnexpectedEof.into());
+                }
+
+                let data = match String::from_utf8(buffer) {
+                    Ok(data)
+                        if data
+                            .bytes()
+                            .all(|b| b.is_ascii_graphic() || b.is_ascii_whitespace()) =>
+                    {
+                        data
+                    }
+                    _ => {
+                        writeln!(writer, "ERR invalid data")?;
+                        break 'put;
+                    }
+                };
+                let data = data.as_str();
+
+                let mut storage = storage.files.lock().unwrap();
+
+                if let Some(blob) = storage.get_mut(file) {
+                    if data != blob.revisions[blob.revisions.len() - 1] {
+                        blob.revisions.push(data.to_string());
+                    }
+
+                    writeln!(writer, "OK r{}", blob.revisions.len())?;
+                } else {
+                    let blob = Blob {
+                        revisions: Vec::from([data.to_string()]),
+                    };
+                    storage.insert(file.to_string(), blob);
+
+                    writeln!(writer, "OK r1")?;
+                }
+            }
+            ["put", ..] => {
+                writeln!(writer, "ERR usage: PUT file length newline data")?;
+            }
+            ["get", file] => 'get: {
+                if !file.starts_with('/') {
+                    writeln!(writer, "ERR illegal file name")?;
+                    break 'get;
+                }
+
+                if let Some(blob) = storage.files.lock().unwrap().get_mut(file) {
+                    writeln!(
+                        writer,
+                        "OK {}",
+                        blob.revisions[blob.revisions.len() - 1].len()
+                    )?;
+                    write!(writer, "{}", blob.revisions[blob.revisions.len() - 1])?;
+                } else {
+                    writeln!(writer, "ERR no such file")?;
+                }
+            }
+            ["get", file, revision] => 'get: {
+                if !file.starts_with('/') {
+                    writeln!(writer, "ERR illegal file name")?;
+                    break 'get;
+                }
+
+                if let Some(blob) = storage.files.lock().unwrap().get_mut(file) {
+                    let revision: usize = revision.replace("r", "").parse().unwrap_or(0);
+
+                    if revision < 1 || revision > blob.revisions.len() {
+                        writeln!(writer, "ERR no such revision")?;
+                    } else {
+                        writeln!(writer, "OK {}", blob.revisions[revision - 1].len())?;
+                        write!(writer, "{}", blob.revisions[revision - 1])?;
+                    }
+                } else {
+                    writeln!(writer, "ERR no such file")?;
+                }
+            }
+            ["get", ..] => {
+                writeln!(writer, "ERR usage: GET file [revision]")?;
+            }
+            ["list", dir] => 'list: {
+                if !dir.starts_with('/') {
+                    writeln!(writer, "ERR illegal dir name")?;
+                    break 'list;
+                }
+                let directory = dir.trim_end_matches("/");
+
+                let mut blobs: Vec<String> = Vec::new();
+
+                for (key, blob) in storage.files.lock().unwrap().iter() {
+                    match key.rsplitn(3, "/").collect::<Vec<&str>>()[..] {
+                        [filename] if directory.is_empty() => {
+                            blobs.push(format!("{} r{}", filename, blob.revisions.len()));
+                        }
+                        [filename, base_dir] if base_dir == directory => {
+                            blobs.push(format!("{} r{}", filename, blob.revisions.len()));
+                        }
+                        [filename, sub_dir, base_dir] => {
+                            if base_dir == directory {
+                                blobs.push(format!("{}/ DIR", sub_dir));
+                            }
+                            if [base_dir, sub_dir].join("/") == directory {
+                                blobs.push(format!("{} r{}", filename, blob.revisions.len()));
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+
+                writeln!(writer, "OK {}", blobs.len())?;
+
+                blobs.sort();
+                for blob in blobs {
+                    writeln!(writer, "{}", blob)?;
+                }
+            }
+            ["list", ..] => {
+                writeln!(writer, "ERR usage: LIST dir")?;
+            }
+            [first, ..] => {
+                writeln!(writer, "ERR illegal method: {}", first)?;
+                break;
+            }
+            [] => {
+                writeln!(writer, "ERR illegal method: ")?;
+                break;
+            }
+        }
+
+        writeln!(writer, "READY")?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,168 @@
+use shared::{
+    config::Config,
+    testing::{self, TcpClient, Transport},
+};
+use std::net::SocketAddr;
+
+fn server() -> SocketAddr {
+    testing::spawn(
+        Transport::Tcp,
+        voracious_code_storage::default_config(),
+        voracious_code_storage::run,
+    )
+}
+
+fn connect(address: SocketAddr) -> TcpClient {
+    let mut client = TcpClient::connect(address);
+    client.expect_line("READY");
+    client
+}
+
+fn put(client: &mut TcpClient, file: &str, data: &str) -> String {
+    client.send(format!("PUT {file} {}\n{data}", data.len()));
+    let response = client.read_line();
+    client.expect_line("READY");
+    response
+}
+
+#[test]
+fn answers_help() {
+    let mut client = connect(server());
+
+    client.send_line("HELP");
+    client.expect_line("OK usage: HELP|GET|PUT|LIST");
+    client.expect_line("READY");
+}
+
+#[test]
+fn stores_revisions() {
+    let mut client = connect(server());
+
+    assert_eq!(put(&mut client, "/test.txt", "hello\n"), "OK r1");
+    assert_eq!(put(&mut client, "/test.txt", "hello\n"), "OK r1");
+    assert_eq!(put(&mut client, "/test.txt", "world\n"), "OK r2");
+
+    client.send_line("GET /test.txt");
+    client.expect_line("OK 6");
+    client.expect("world\n");
+    client.expect_line("READY");
+
+    client.send_line("GET /test.txt r1");
+    client.expect_line("OK 6");
+    client.expect("hello\n");
+    client.expect_line("READY");
+
+    client.send_line("GET /test.txt r3");
+    client.expect_line("ERR no such revision");
+    client.expect_line("READY");
+}
+
+#[test]
+fn shares_files_between_clients() {
+    let address = server();
+    let mut writer = connect(address);
+    let mut reader = connect(address);
+
+    put(&mut writer, "/shared", "data");
+
+    reader.send_line("GET /shared");
+    reader.expect_line("OK 4");
+    reader.expect("data");
+    reader.expect_line("READY");
+}
+
+#[test]
+fn lists_files_and_directories() {
+    let mut client = connect(server());
+
+    put(&mut client, "/a.txt", "a");
+    put(&mut client, "/a.txt", "b");
+    put(&mut client, "/dir/b.txt", "b");
+
+    client.send_line("LIST /");
+    client.expect_line("OK 2");
+    client.expect_line("a.txt r2");
+    client.expect_line("dir/ DIR");
+    client.expect_line("READY");
+
+    client.send_line("LIST /dir/");
+    client.expect_line("OK 1");
+    client.expect_line("b.txt r1");
+    client.expect_line("READY");
+}
+
+#[test]
+fn rejects_bad_requests() {
+    let mut client = connect(server());
+
+    client.send_line("GET /missing");
+    client.expect_line("ERR no such file");
+    client.expect_line("READY");
+
+    client.send_line("PUT bad 1");
+    client.expect_line("ERR illegal file name");
+    client.expect_line("READY");
+
+    assert_eq!(put(&mut client, "/binary", "\u{1}"), "ERR invalid data");
+
+    client.send_line("LIST");
+    client.expect_line("ERR usage: LIST dir");
+    client.expect_line("READY");
+}
+
+#[test]
+fn disconnects_on_illegal_method() {
+    let mut client = connect(server());
+
+    client.send_line("DELETE /file");
+    client.expect_line("ERR illegal method: delete");
+    client.expect_closed();
+}
+
+#[test]
+fn rejects_oversized_lines_and_carries_on() {
+    let config = Config {
+        max_line_length: 64,
+        ..voracious_code_storage::default_config()
+    };
+    let address = testing::spawn(Transport::Tcp, config, voracious_code_storage::run);
+    let mut client = connect(address);
+
+    client.send_line(&format!("GET /{}", "a".repeat(64)));
+    client.expect_line("ERR line too long");
+    client.expect_line("READY");
+
+    assert_eq!(put(&mut client, "/file", &"data ".repeat(20)), "OK r1");
+}
+
+#[test]
+fn rejects_lengths_that_are_not_numbers() {
+    let mut client = connect(server());
+
+    client.send_line("PUT /file ten");
+    client.expect_line("ERR usage: PUT file length newline data");
+    client.expect_line("READY");
+
+    client.send_line("PUT /file -1");
+    client.expect_line("ERR usage: PUT file length newline data");
+    client.expect_line("READY");
+}
+
+#[test]
+fn disconnects_on_files_over_the_limit() {
+    let config = voracious_code_storage::default_config()
+        .apply(|_| None, ["--max-file-size=8".to_string()])
+        .unwrap();
+    let address = testing::spawn(Transport::Tcp, config, voracious_code_storage::run);
+    let mut client = connect(address);
+
+    assert_eq!(put(&mut client, "/small", "12345678"), "OK r1");
+
+    client.send_line("PUT /huge 900000000000000");
+    client.expect_line("ERR file too large");
+    client.expect_closed();
+
+    let mut client = connect(address);
+    client.send_line("GET /small");
+    client.expect_line("OK 8");
+}